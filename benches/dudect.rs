@@ -0,0 +1,358 @@
+//! dudect-style timing leakage tests (Reparaz, Balasch and Verbauwhede, "Dude, is my code
+//! constant time?", DATE 2017).
+//!
+//! Each target runs on inputs of two classes, drawn at random and interleaved, and Welch's t-test
+//! compares the two distributions of execution times, raw and cropped at increasing percentiles
+//! (which removes the long tail of interrupts and context switches). A target leaks when the
+//! largest |t| exceeds 10, the threshold of the reference dudect; the process then exits with a
+//! non-zero status.
+//!
+//! ```text
+//! cargo bench --bench dudect                                   # every target
+//! cargo bench --bench dudect -- --samples 1000000 compress     # more samples, filtered targets
+//! ```
+
+use std::hint::black_box;
+use std::process::ExitCode;
+use std::time::Instant;
+
+use kyber_nz::constants::{KyberParams, PolyParams};
+use kyber_nz::conversion::{byte_encode, compress};
+use kyber_nz::hash::H;
+use kyber_nz::kem_scheme::{KemDecapsKey, KemEncapsKey, MlKem};
+use kyber_nz::params::{Kyber1024Params, Kyber512Params, Kyber768Params, SecurityLevel};
+use kyber_nz::pke_scheme::KPke;
+use kyber_nz::polynomial::{Polynomial, PolynomialNTT};
+use kyber_nz::traits::{KemScheme, PkeScheme};
+use rand::rngs::{OsRng, StdRng};
+use rand::{Rng, RngCore, SeedableRng};
+
+/// |t| above which a target is reported as leaking.
+const THRESHOLD: f64 = 10.0;
+/// Number of percentile crops, as `1 - 0.5^(10 (i + 1) / CROPS)`.
+const CROPS: usize = 20;
+/// Measurements per batch; inputs are prepared before each batch. The first one is a warm-up
+/// that only sets the crop thresholds.
+const BATCH: usize = 1000;
+/// Default number of measurements of the KEM and K-PKE targets, and of the other targets.
+const KEM_SAMPLES: usize = 20_000;
+const PRIMITIVE_SAMPLES: usize = 200_000;
+
+/// Welch's t-test, with Welford's online mean and variance.
+#[derive(Clone, Default)]
+struct TTest {
+    n: [f64; 2],
+    mean: [f64; 2],
+    m2: [f64; 2],
+}
+
+impl TTest {
+    fn push(&mut self, class: usize, x: f64) {
+        self.n[class] += 1.0;
+        let delta = x - self.mean[class];
+        self.mean[class] += delta / self.n[class];
+        self.m2[class] += delta * (x - self.mean[class]);
+    }
+
+    fn t(&self) -> f64 {
+        if self.n[0] < 2.0 || self.n[1] < 2.0 {
+            return 0.0;
+        }
+        let var0 = self.m2[0] / (self.n[0] - 1.0);
+        let var1 = self.m2[1] / (self.n[1] - 1.0);
+        let denominator = (var0 / self.n[0] + var1 / self.n[1]).sqrt();
+        if denominator == 0.0 {
+            return 0.0;
+        }
+        (self.mean[0] - self.mean[1]) / denominator
+    }
+}
+
+struct Report {
+    name: String,
+    samples: usize,
+    max_t: f64,
+}
+
+impl Report {
+    fn leaks(&self) -> bool {
+        self.max_t > THRESHOLD
+    }
+}
+
+struct Harness {
+    samples: Option<usize>,
+    filters: Vec<String>,
+    rng: StdRng,
+    reports: Vec<Report>,
+}
+
+impl Harness {
+    /// Measures `run` on inputs of class 0 and 1 returned by `prepare`.
+    fn measure<I, O>(
+        &mut self,
+        name: &str,
+        default_samples: usize,
+        mut prepare: impl FnMut(usize, &mut StdRng) -> I,
+        mut run: impl FnMut(&I) -> O,
+    ) {
+        if !self.filters.is_empty() && !self.filters.iter().any(|f| name.contains(f.as_str())) {
+            return;
+        }
+        let samples = self.samples.unwrap_or(default_samples);
+
+        let mut raw = TTest::default();
+        let mut crops = vec![TTest::default(); CROPS];
+        let mut thresholds = Vec::new();
+        let mut measured = 0;
+        while measured < samples {
+            let n = if thresholds.is_empty() {
+                BATCH
+            } else {
+                BATCH.min(samples - measured)
+            };
+            let classes: Vec<usize> = (0..n).map(|_| self.rng.gen_range(0..2)).collect();
+            let inputs: Vec<I> = classes
+                .iter()
+                .map(|&class| prepare(class, &mut self.rng))
+                .collect();
+            let times: Vec<f64> = inputs
+                .iter()
+                .map(|input| {
+                    let start = Instant::now();
+                    black_box(run(black_box(input)));
+                    start.elapsed().as_nanos() as f64
+                })
+                .collect();
+
+            if thresholds.is_empty() {
+                let mut sorted = times.clone();
+                sorted.sort_by(f64::total_cmp);
+                thresholds = (0..CROPS)
+                    .map(|i| {
+                        let p = 1.0 - 0.5f64.powf(10.0 * (i + 1) as f64 / CROPS as f64);
+                        sorted[((p * n as f64) as usize).min(n - 1)]
+                    })
+                    .collect();
+                continue;
+            }
+
+            for (&class, &time) in classes.iter().zip(&times) {
+                raw.push(class, time);
+                for (crop, &threshold) in crops.iter_mut().zip(&thresholds) {
+                    if time < threshold {
+                        crop.push(class, time);
+                    }
+                }
+            }
+            measured += n;
+        }
+
+        let max_t = crops
+            .iter()
+            .chain([&raw])
+            .map(|test| test.t().abs())
+            .fold(0.0, f64::max);
+        let report = Report {
+            name: name.to_string(),
+            samples,
+            max_t,
+        };
+        println!(
+            "{:<52} {:>9} samples   max |t| = {:>7.2}   {}",
+            report.name,
+            report.samples,
+            report.max_t,
+            if report.leaks() { "LEAK" } else { "PASS" }
+        );
+        self.reports.push(report);
+    }
+}
+
+fn random_bytes(rng: &mut StdRng, len: usize) -> Vec<u8> {
+    let mut bytes = vec![0u8; len];
+    rng.fill_bytes(&mut bytes);
+    bytes
+}
+
+/// Key pair of matrix seed `rho`, with fresh secret and error vectors. The run time of SampleNTT
+/// depends on the (public) `rho`, so keys compared for secret-dependent timing must share it.
+fn key_with_rho<const K: usize, S: SecurityLevel>(
+    rho: &[u8; 32],
+    rng: &mut StdRng,
+) -> (KemEncapsKey<K>, KemDecapsKey<K>) {
+    let mut cbd = || {
+        let bytes = random_bytes(rng, 64 * S::ETA1);
+        Polynomial::<KyberParams>::sample_poly_cbd(&bytes, S::ETA1)
+            .unwrap()
+            .to_ntt()
+    };
+    let s: Vec<PolynomialNTT<KyberParams>> = (0..K).map(|_| cbd()).collect();
+
+    let (mut t_bytes, mut s_bytes) = ([[0u8; 384]; K], [[0u8; 384]; K]);
+    for i in 0..K {
+        let mut t = cbd();
+        for (j, s_j) in s.iter().enumerate() {
+            let mut input = [0u8; 34];
+            input[..32].copy_from_slice(rho);
+            input[32] = j as u8;
+            input[33] = i as u8;
+            t += &(&PolynomialNTT::<KyberParams>::sample_ntt(&input) * s_j);
+        }
+        byte_encode(&t.coeffs, 12, &mut t_bytes[i]).unwrap();
+        byte_encode(&s[i].coeffs, 12, &mut s_bytes[i]).unwrap();
+    }
+
+    let ek_bytes = [t_bytes.as_flattened(), rho].concat();
+    let mut z = [0u8; 32];
+    rng.fill_bytes(&mut z);
+    let dk_bytes = [
+        s_bytes.as_flattened(),
+        &ek_bytes,
+        &H::evaluate(&ek_bytes),
+        &z,
+    ]
+    .concat();
+    (
+        KemEncapsKey::<K>::from_bytes(&ek_bytes).unwrap(),
+        KemDecapsKey::<K>::from_bytes(&dk_bytes).unwrap(),
+    )
+}
+
+fn kem_targets<const K: usize, S: SecurityLevel>(harness: &mut Harness, name: &str) {
+    let kem = MlKem::<K, S, KyberParams>::new();
+    let ct_len = MlKem::<K, S, KyberParams>::ciphertext_len();
+    let (ek, dk) = kem.key_gen(&mut harness.rng).unwrap();
+    let dk_bytes = dk.to_bytes();
+    let rho = *ek.rho();
+
+    let (ek_rho, dk_rho) = key_with_rho::<K, S>(&rho, &mut harness.rng);
+    let (ss, ct) = kem.encaps(&ek_rho, &mut harness.rng).unwrap();
+    assert_eq!(kem.decaps(&dk_rho, &ct).unwrap(), ss);
+
+    harness.measure(
+        &format!("{} Decaps: valid vs rejected ct", name),
+        KEM_SAMPLES,
+        |class, rng| match class {
+            0 => kem.encaps(&ek, rng).unwrap().1,
+            _ => random_bytes(rng, ct_len),
+        },
+        |ct| *kem.decaps(&dk, ct).unwrap().as_bytes(),
+    );
+
+    harness.measure(
+        &format!("{} Decaps: fixed vs random secret key", name),
+        KEM_SAMPLES,
+        |class, rng| match class {
+            0 => {
+                let dk = KemDecapsKey::<K>::from_bytes(&dk_bytes).unwrap();
+                (dk, kem.encaps(&ek, rng).unwrap().1)
+            }
+            _ => {
+                let (ek, dk) = key_with_rho::<K, S>(&rho, rng);
+                (dk, kem.encaps(&ek, rng).unwrap().1)
+            }
+        },
+        |(dk, ct)| *kem.decaps(dk, ct).unwrap().as_bytes(),
+    );
+
+    let pke = KPke::<K, S, KyberParams>::new();
+    let mut seed = [0u8; 32];
+    harness.rng.fill_bytes(&mut seed);
+    let (ek_pke, dk_pke) = pke.key_gen(&seed).unwrap();
+    let fixed_ct = pke.encrypt(&ek_pke, &[0x5a; 32], &seed).unwrap();
+
+    harness.measure(
+        &format!("{} K-PKE.Decrypt: fixed vs random ct", name),
+        KEM_SAMPLES,
+        |class, rng| match class {
+            0 => fixed_ct.clone(),
+            _ => random_bytes(rng, ct_len),
+        },
+        |ct| pke.decrypt(&dk_pke, ct).unwrap(),
+    );
+}
+
+fn primitive_targets(harness: &mut Harness) {
+    let q = KyberParams::Q;
+    let random_coeffs = |rng: &mut StdRng| {
+        let mut coeffs = [0i16; 256];
+        for c in coeffs.iter_mut() {
+            *c = rng.gen_range(0..q);
+        }
+        coeffs
+    };
+    let fixed_coeffs = random_coeffs(&mut harness.rng);
+
+    // d = 1 encodes the message, 4 and 5 are d_v, 10 and 11 are d_u
+    for d in [1, 4, 5, 10, 11] {
+        harness.measure(
+            &format!("Compress_{}: fixed vs random coefficients", d),
+            PRIMITIVE_SAMPLES,
+            |class, rng| match class {
+                0 => fixed_coeffs,
+                _ => random_coeffs(rng),
+            },
+            |coeffs| coeffs.iter().fold(0i16, |acc, &c| acc ^ compress(c, d, q)),
+        );
+    }
+
+    for eta in [2, 3] {
+        let fixed_bytes = random_bytes(&mut harness.rng, 64 * eta);
+        harness.measure(
+            &format!("SamplePolyCBD_{}: fixed vs random bytes", eta),
+            PRIMITIVE_SAMPLES,
+            |class, rng| match class {
+                0 => fixed_bytes.clone(),
+                _ => random_bytes(rng, 64 * eta),
+            },
+            |bytes| {
+                Polynomial::<KyberParams>::sample_poly_cbd(bytes, eta)
+                    .unwrap()
+                    .coeffs
+            },
+        );
+    }
+}
+
+fn main() -> ExitCode {
+    let mut samples = None;
+    let mut filters = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--samples" => {
+                let value = args.next().and_then(|n| n.parse().ok());
+                samples = Some(value.expect("--samples expects a number"));
+            }
+            // Passed by `cargo bench`
+            "--bench" => {}
+            _ => filters.push(arg),
+        }
+    }
+
+    let seed = OsRng.next_u64();
+    println!("dudect, seed {}, threshold |t| > {}", seed, THRESHOLD);
+    let mut harness = Harness {
+        samples,
+        filters,
+        rng: StdRng::seed_from_u64(seed),
+        reports: Vec::new(),
+    };
+
+    kem_targets::<2, Kyber512Params>(&mut harness, "ML-KEM-512");
+    kem_targets::<3, Kyber768Params>(&mut harness, "ML-KEM-768");
+    kem_targets::<4, Kyber1024Params>(&mut harness, "ML-KEM-1024");
+    primitive_targets(&mut harness);
+
+    let leaks: Vec<&Report> = harness.reports.iter().filter(|r| r.leaks()).collect();
+    println!("{} targets, {} leaking", harness.reports.len(), leaks.len());
+    if leaks.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        for report in leaks {
+            println!("LEAK: {} (max |t| = {:.2})", report.name, report.max_t);
+        }
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,159 @@
+fn main() {
+    #[cfg(feature = "capi")]
+    generate_header();
+}
+
+/// Generates the per-level C entry points into `OUT_DIR`, and `kyber_nz.h` from them and
+/// `src/capi.rs` into `OUT_DIR` and next to the compiled library
+/// (`target/<profile>/include/kyber_nz.h`).
+#[cfg(feature = "capi")]
+fn generate_header() {
+    use std::path::PathBuf;
+
+    println!("cargo:rerun-if-changed=src/capi.rs");
+
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").unwrap());
+    let levels = out_dir.join("capi_levels.rs");
+    std::fs::write(&levels, level_exports()).unwrap();
+
+    let bindings = cbindgen::Builder::new()
+        .with_src("src/capi.rs")
+        .with_src(&levels)
+        .with_language(cbindgen::Language::C)
+        .with_include_guard("KYBER_NZ_H")
+        .with_header("/* Generated by cbindgen from src/capi.rs. Do not edit. */")
+        .with_cpp_compat(true)
+        .generate()
+        .expect("Unable to generate kyber_nz.h");
+
+    let out_header = out_dir.join("include").join("kyber_nz.h");
+    std::fs::create_dir_all(out_header.parent().unwrap()).unwrap();
+    bindings.write_to_file(&out_header);
+
+    // OUT_DIR is target/<profile>/build/kyber-nz-<hash>/out
+    if let Some(profile_dir) = out_dir.ancestors().nth(3) {
+        let include_dir = profile_dir.join("include");
+        std::fs::create_dir_all(&include_dir).unwrap();
+        bindings.write_to_file(include_dir.join("kyber_nz.h"));
+    }
+}
+
+/// Per-level entry points of the C API, included by `src/capi.rs` and read by cbindgen, so that
+/// the exports and the header come from the same source.
+#[cfg(feature = "capi")]
+fn level_exports() -> String {
+    let mut exports = String::new();
+    for (level, k) in [(512, 2), (768, 3), (1024, 4)] {
+        let params = format!("Kyber{level}Params");
+        let pqclean = format!("PQCLEAN_MLKEM{level}");
+        let oqs = format!("OQS_KEM_ml_kem_{level}");
+        exports.push_str(&format!(
+            r#"
+/// # Safety
+///
+/// `pk` and `sk` must point to `{pqclean}_CRYPTO_PUBLICKEYBYTES` and
+/// `{pqclean}_CRYPTO_SECRETKEYBYTES` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn {pqclean}_crypto_kem_keypair(pk: *mut u8, sk: *mut u8) -> c_int {{
+    keypair::<{k}, {params}>(pk, sk, core::ptr::null())
+}}
+
+/// # Safety
+///
+/// `ct`, `ss` and `pk` must point to buffers of the `{pqclean}_*` sizes.
+#[no_mangle]
+pub unsafe extern "C" fn {pqclean}_crypto_kem_enc(
+    ct: *mut u8,
+    ss: *mut u8,
+    pk: *const u8,
+) -> c_int {{
+    enc::<{k}, {params}>(ct, ss, pk, core::ptr::null())
+}}
+
+/// # Safety
+///
+/// `ss`, `ct` and `sk` must point to buffers of the `{pqclean}_*` sizes.
+#[no_mangle]
+pub unsafe extern "C" fn {pqclean}_crypto_kem_dec(
+    ss: *mut u8,
+    ct: *const u8,
+    sk: *const u8,
+) -> c_int {{
+    dec::<{k}, {params}>(ss, ct, sk)
+}}
+
+/// # Safety
+///
+/// `public_key` and `secret_key` must point to buffers of the `{oqs}_length_*`
+/// sizes.
+#[no_mangle]
+pub unsafe extern "C" fn {oqs}_keypair(
+    public_key: *mut u8,
+    secret_key: *mut u8,
+) -> c_int {{
+    keypair::<{k}, {params}>(public_key, secret_key, core::ptr::null())
+}}
+
+/// # Safety
+///
+/// `public_key`, `secret_key` and `seed` (`d || z`) must point to buffers of the
+/// `{oqs}_length_*` sizes.
+#[no_mangle]
+pub unsafe extern "C" fn {oqs}_keypair_derand(
+    public_key: *mut u8,
+    secret_key: *mut u8,
+    seed: *const u8,
+) -> c_int {{
+    if seed.is_null() {{
+        return KYBER_NZ_ERROR_NULL_POINTER;
+    }}
+    keypair::<{k}, {params}>(public_key, secret_key, seed)
+}}
+
+/// # Safety
+///
+/// `ciphertext`, `shared_secret` and `public_key` must point to buffers of the
+/// `{oqs}_length_*` sizes.
+#[no_mangle]
+pub unsafe extern "C" fn {oqs}_encaps(
+    ciphertext: *mut u8,
+    shared_secret: *mut u8,
+    public_key: *const u8,
+) -> c_int {{
+    enc::<{k}, {params}>(ciphertext, shared_secret, public_key, core::ptr::null())
+}}
+
+/// # Safety
+///
+/// `ciphertext`, `shared_secret`, `public_key` and `seed` (`m`) must point to buffers of the
+/// `{oqs}_length_*` sizes.
+#[no_mangle]
+pub unsafe extern "C" fn {oqs}_encaps_derand(
+    ciphertext: *mut u8,
+    shared_secret: *mut u8,
+    public_key: *const u8,
+    seed: *const u8,
+) -> c_int {{
+    if seed.is_null() {{
+        return KYBER_NZ_ERROR_NULL_POINTER;
+    }}
+    enc::<{k}, {params}>(ciphertext, shared_secret, public_key, seed)
+}}
+
+/// # Safety
+///
+/// `shared_secret`, `ciphertext` and `secret_key` must point to buffers of the
+/// `{oqs}_length_*` sizes.
+#[no_mangle]
+pub unsafe extern "C" fn {oqs}_decaps(
+    shared_secret: *mut u8,
+    ciphertext: *const u8,
+    secret_key: *const u8,
+) -> c_int {{
+    dec::<{k}, {params}>(shared_secret, ciphertext, secret_key)
+}}
+"#
+        ));
+    }
+    exports
+}
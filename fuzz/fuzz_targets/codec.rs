@@ -0,0 +1,59 @@
+//! ByteEncode_d / ByteDecode_d with every d, including out-of-range ones, and arbitrary lengths.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use kyber_nz::constants::{KyberParams, PolyParams};
+use kyber_nz::conversion::{byte_decode, byte_encode, compress, decompress};
+use kyber_nz::errors::Error;
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Arbitrary, Debug)]
+struct Input<'a> {
+    d: u8,
+    out_len: u16,
+    coeffs: Vec<i16>,
+    bytes: &'a [u8],
+}
+
+fuzz_target!(|input: Input<'_>| {
+    let d = input.d as usize;
+    let q = KyberParams::Q;
+    let valid_d = (1..=12).contains(&d);
+
+    let mut decoded = vec![0i16; input.out_len as usize];
+    match byte_decode(input.bytes, d, q, &mut decoded) {
+        Ok(()) => {
+            assert!(valid_d);
+            let m = if d == 12 { q } else { 1 << d };
+            assert!(decoded.iter().all(|&c| (0..m).contains(&c)));
+
+            // ByteDecode_d is a bijection for d < 12 (on whole numbers of d-bit integers)
+            if (input.bytes.len() * 8).is_multiple_of(d) {
+                let mut encoded = vec![0u8; input.bytes.len()];
+                byte_encode(&decoded, d, &mut encoded).unwrap();
+                if d < 12 {
+                    assert_eq!(encoded, input.bytes);
+                }
+            }
+        }
+        Err(Error::InvalidBitWidth) => assert!(!valid_d),
+        Err(e) => assert_eq!(e, Error::InvalidInputLength),
+    }
+
+    let mut encoded = vec![0u8; input.out_len as usize];
+    match byte_encode(&input.coeffs, d, &mut encoded) {
+        Ok(()) => assert!(valid_d && encoded.len() * 8 == input.coeffs.len() * d),
+        Err(Error::InvalidBitWidth) => assert!(!valid_d),
+        Err(e) => assert_eq!(e, Error::InvalidInputLength),
+    }
+
+    if (1..12).contains(&d) {
+        for &x in input.coeffs.iter() {
+            let x = x.rem_euclid(q);
+            let y = compress(x, d, q);
+            assert!((0..1 << d).contains(&y));
+            assert_eq!(compress(decompress(y, d, q), d, q), y);
+        }
+    }
+});
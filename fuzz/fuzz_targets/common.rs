@@ -0,0 +1,38 @@
+//! Shared helpers of the fuzz targets.
+
+use arbitrary::Arbitrary;
+
+/// ML-KEM parameter set chosen by the fuzzer.
+#[derive(Arbitrary, Clone, Copy, Debug)]
+pub enum Level {
+    MlKem512,
+    MlKem768,
+    MlKem1024,
+}
+
+/// Runs `$body` with `$kem` bound to the `MlKem` type of `$level` and `$k` to its module rank.
+#[macro_export]
+macro_rules! with_level {
+    ($level:expr, |$kem:ident| $body:block) => {
+        $crate::with_level!($level, |$kem, _K| $body)
+    };
+    ($level:expr, |$kem:ident, $k:ident| $body:block) => {
+        match $level {
+            $crate::common::Level::MlKem512 => {
+                type $kem = kyber_nz::Kyber512;
+                const $k: usize = 2;
+                $body
+            }
+            $crate::common::Level::MlKem768 => {
+                type $kem = kyber_nz::Kyber768;
+                const $k: usize = 3;
+                $body
+            }
+            $crate::common::Level::MlKem1024 => {
+                type $kem = kyber_nz::Kyber1024;
+                const $k: usize = 4;
+                $body
+            }
+        }
+    };
+}
@@ -0,0 +1,41 @@
+//! Decapsulation with arbitrary decapsulation key bytes and arbitrary-length ciphertexts.
+
+#![no_main]
+
+mod common;
+
+use arbitrary::Arbitrary;
+use kyber_nz::kem_scheme::KemDecapsKey;
+use kyber_nz::traits::KemScheme;
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Arbitrary, Debug)]
+struct Input<'a> {
+    level: common::Level,
+    /// Split point between the key and the ciphertext in `bytes`.
+    dk_len: u16,
+    bytes: &'a [u8],
+}
+
+fuzz_target!(|input: Input<'_>| {
+    let split = (input.dk_len as usize).min(input.bytes.len());
+    let (dk, ct) = input.bytes.split_at(split);
+
+    with_level!(input.level, |Kem, K| {
+        let Ok(dk) = KemDecapsKey::<K>::from_bytes(dk) else {
+            assert_ne!(split, KemDecapsKey::<K>::len());
+            return;
+        };
+        let kem = Kem::new();
+
+        let checked = kem.decaps(&dk, ct);
+        let unchecked = kem.decaps_internal(&dk, ct);
+        if ct.len() != Kem::ciphertext_len() {
+            assert!(checked.is_err() && unchecked.is_err());
+        } else if let Ok(shared_secret) = checked {
+            assert_eq!(shared_secret, unchecked.unwrap());
+        } else {
+            assert!(Kem::check_decaps_key(&dk).is_err());
+        }
+    });
+});
@@ -0,0 +1,40 @@
+//! Encapsulation with arbitrary encapsulation key bytes.
+
+#![no_main]
+
+mod common;
+
+use arbitrary::Arbitrary;
+use kyber_nz::kem_scheme::KemEncapsKey;
+use kyber_nz::traits::KemScheme;
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Arbitrary, Debug)]
+struct Input<'a> {
+    level: common::Level,
+    m: [u8; 32],
+    ek: &'a [u8],
+}
+
+fuzz_target!(|input: Input<'_>| {
+    with_level!(input.level, |Kem, K| {
+        let Ok(ek) = KemEncapsKey::<K>::from_bytes(input.ek) else {
+            assert_ne!(input.ek.len(), KemEncapsKey::<K>::len());
+            return;
+        };
+        assert_eq!(ek.to_bytes(), input.ek);
+
+        let kem = Kem::new();
+        let (_, ct) = kem.encaps_internal(&ek, &input.m).unwrap();
+        assert_eq!(ct.len(), Kem::ciphertext_len());
+
+        if Kem::check_encaps_key(&ek).is_ok() {
+            // A key passing the modulus check is a valid ByteEncode_12 output
+            let mut coeffs = [0i16; 256];
+            for chunk in ek.t_hat() {
+                kyber_nz::conversion::byte_decode(chunk, 12, 3329, &mut coeffs).unwrap();
+                assert!(coeffs.iter().all(|&c| (0..3329).contains(&c)));
+            }
+        }
+    });
+});
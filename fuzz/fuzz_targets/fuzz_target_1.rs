@@ -20,7 +20,7 @@ fuzz_target!(|data: &[u8]| {
     let (shared_secret_bob, ciphertext) = kem.encaps_internal(&ek, m).unwrap();
 
     let shared_secret_alice = kem.decaps_internal(&dk, &ciphertext).unwrap();
-    assert_eq!(shared_secret_bob.0, shared_secret_alice.0);
+    assert_eq!(shared_secret_bob, shared_secret_alice);
     
     let mut bad_ciphertext = ciphertext.clone();
     if let Some(byte_to_change) = bad_ciphertext.get_mut(0) {
@@ -29,7 +29,7 @@ fuzz_target!(|data: &[u8]| {
     
     let shared_secret_corrupted = kem.decaps_internal(&dk, &bad_ciphertext).unwrap();
     assert_ne!(
-        shared_secret_bob.0, 
-        shared_secret_corrupted.0
+        shared_secret_bob, 
+        shared_secret_corrupted
     );
 });
\ No newline at end of file
@@ -0,0 +1,52 @@
+//! Differential target : the NTT-domain arithmetic against the schoolbook one in R_q, and the
+//! checked KEM entry points against the internal ones.
+//!
+//! The crate has a single (scalar) implementation, so the NTT path is checked against the
+//! direct definitions it optimizes.
+
+#![no_main]
+
+mod common;
+
+use arbitrary::Arbitrary;
+use kyber_nz::constants::{KyberParams, PolyParams};
+use kyber_nz::traits::KemScheme;
+use kyber_nz::KyberPoly;
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    f: [i16; 256],
+    g: [i16; 256],
+    level: common::Level,
+    d: [u8; 32],
+    z: [u8; 32],
+    m: [u8; 32],
+}
+
+fuzz_target!(|input: Input| {
+    let q = KyberParams::Q;
+    let f = KyberPoly::from(input.f.map(|c| c.rem_euclid(q)));
+    let g = KyberPoly::from(input.g.map(|c| c.rem_euclid(q)));
+
+    assert_eq!(KyberPoly::from_ntt(&f.to_ntt()).coeffs, f.coeffs);
+    assert_eq!(
+        KyberPoly::from_ntt(&(&f.to_ntt() * &g.to_ntt())).coeffs,
+        (&f * &g).coeffs
+    );
+    assert_eq!(
+        KyberPoly::from_ntt(&(&f.to_ntt() + &g.to_ntt())).coeffs,
+        (&f + &g).coeffs
+    );
+
+    with_level!(input.level, |Kem| {
+        let kem = Kem::new();
+        let (ek, dk) = kem.key_gen_internal(&input.d, &input.z).unwrap();
+        assert!(Kem::check_encaps_key(&ek).is_ok());
+        assert!(Kem::check_decaps_key(&dk).is_ok());
+
+        let (shared_secret, ct) = kem.encaps_internal(&ek, &input.m).unwrap();
+        assert_eq!(kem.decaps(&dk, &ct).unwrap(), shared_secret);
+        assert_eq!(kem.decaps_internal(&dk, &ct).unwrap(), shared_secret);
+    });
+});
@@ -0,0 +1,36 @@
+//! SamplePolyCBD_eta and SampleNTT with malformed inputs.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use kyber_nz::constants::{KyberParams, PolyParams};
+use kyber_nz::polynomial::PolynomialNTT;
+use kyber_nz::KyberPoly;
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Arbitrary, Debug)]
+struct Input<'a> {
+    eta: u8,
+    bytes: &'a [u8],
+}
+
+fuzz_target!(|input: Input<'_>| {
+    let eta = input.eta as usize;
+    let q = KyberParams::Q;
+
+    match KyberPoly::sample_poly_cbd(input.bytes, eta) {
+        Ok(f) => {
+            assert!((eta == 2 || eta == 3) && input.bytes.len() == 64 * eta);
+            for &c in f.coeffs.iter() {
+                assert!(c <= eta as i16 || c >= q - eta as i16);
+                assert!((0..q).contains(&c));
+            }
+        }
+        Err(_) => assert!(!((eta == 2 || eta == 3) && input.bytes.len() == 64 * eta)),
+    }
+
+    if let Some(seed) = input.bytes.first_chunk::<34>() {
+        let a = PolynomialNTT::<KyberParams>::sample_ntt(seed);
+        assert!(a.coeffs.iter().all(|c| (0..q).contains(c)));
+    }
+});
@@ -0,0 +1,124 @@
+"""Tests of the kyber-nz Python bindings (build them first with `maturin develop`)."""
+
+import hashlib
+import os
+from pathlib import Path
+
+import pytest
+
+import kyber_nz
+
+LEVELS = [
+    (kyber_nz.MlKem512, "kat_MLKEM_512.rsp"),
+    (kyber_nz.MlKem768, "kat_MLKEM_768.rsp"),
+    (kyber_nz.MlKem1024, "kat_MLKEM_1024.rsp"),
+]
+
+TEST_VECTORS = Path(__file__).resolve().parents[2] / "tests" / "test_vectors"
+
+
+def read_kat(path):
+    """Yields the test cases of a `key = value` KAT file as dicts of bytes."""
+    case = {}
+    for line in path.read_text().splitlines():
+        key, sep, value = line.partition("=")
+        if not sep:
+            continue
+        key, value = key.strip(), value.strip()
+        if key == "count":
+            case = {"count": int(value)}
+        elif key in ("d", "z", "msg", "pk", "sk", "ct", "ss"):
+            case[key] = bytes.fromhex(value)
+        if len(case) == 8:
+            yield case
+            case = {}
+
+
+@pytest.mark.parametrize("kem_class, filename", LEVELS)
+def test_kat(kem_class, filename):
+    path = TEST_VECTORS / filename
+    if not path.exists():
+        if not os.environ.get("KYBER_NZ_SKIP_MISSING_VECTORS"):
+            pytest.fail(f"{path} not found, run scripts/fetch-test-vectors.sh")
+        pytest.skip(f"{path} not found")
+
+    kem = kem_class()
+    cases = 0
+    for case in read_kat(path):
+        ek, dk = kem.keygen_derand(case["d"], case["z"])
+        assert ek == case["pk"], f"count {case['count']}: pk mismatch"
+        assert dk == case["sk"], f"count {case['count']}: sk mismatch"
+
+        ss, ct = kem.encaps_derand(ek, case["msg"])
+        assert ct == case["ct"], f"count {case['count']}: ct mismatch"
+        assert ss == case["ss"], f"count {case['count']}: ss mismatch"
+        assert kem.decaps(dk, ct) == case["ss"], f"count {case['count']}: decaps mismatch"
+        cases += 1
+    assert cases > 0
+
+
+def test_matches_rust_unit_test():
+    """Same inputs as the `basics` test of `kem_scheme.rs`."""
+    kem = kyber_nz.MlKem768()
+    d = hashlib.sha3_256(b"randomness d").digest()
+    z = hashlib.shake_256(b"randomness z").digest(32)
+    m = hashlib.sha3_256(b"seed permettant l encapsulation").digest()
+
+    ek, dk = kem.keygen_derand(d, z)
+    ss, ct = kem.encaps_derand(ek, m)
+    assert (
+        hashlib.sha3_256(ct).hexdigest()
+        == "118a28e1eaf15b7759548dfa9d39a70e439a95142970e58278e4bd3861a6b92f"
+    )
+    assert kem.decaps(dk, ct) == ss
+
+
+@pytest.mark.parametrize("kem_class, _filename", LEVELS)
+def test_round_trip(kem_class, _filename):
+    kem = kem_class()
+    ek, dk = kem.keygen()
+    assert len(ek) == kem_class.ENCAPS_KEY_SIZE
+    assert len(dk) == kem_class.DECAPS_KEY_SIZE
+
+    ss, ct = kem.encaps(ek)
+    assert len(ct) == kem_class.CIPHERTEXT_SIZE
+    assert len(ss) == kem_class.SHARED_SECRET_SIZE
+    assert kem.decaps(dk, ct) == ss
+
+    corrupted = bytes([ct[0] ^ 1]) + ct[1:]
+    assert kem.decaps(dk, corrupted) != ss
+
+
+@pytest.mark.parametrize("kem_class, _filename", LEVELS)
+def test_invalid_lengths(kem_class, _filename):
+    kem = kem_class()
+    ek, dk = kem.keygen()
+    _, ct = kem.encaps(ek)
+
+    with pytest.raises(kyber_nz.InvalidInputLength):
+        kem.keygen_derand(bytes(31), bytes(32))
+    with pytest.raises(kyber_nz.InvalidKeyLength):
+        kem.encaps(ek[:-1])
+    with pytest.raises(kyber_nz.InvalidInputLength):
+        kem.encaps_derand(ek, bytes(33))
+    with pytest.raises(kyber_nz.InvalidKeyLength):
+        kem.decaps(dk + b"\x00", ct)
+    with pytest.raises(kyber_nz.InvalidCiphertextLength):
+        kem.decaps(dk, ct[:-1])
+    with pytest.raises(ValueError):
+        kem.decaps(dk, ct + b"\x00")
+    with pytest.raises(TypeError):
+        kem.encaps("not bytes")
+
+
+@pytest.mark.parametrize("kem_class, _filename", LEVELS)
+def test_modulus_check(kem_class, _filename):
+    kem = kem_class()
+    ek, _ = kem.keygen_derand(bytes(32), bytes(32))
+    # First coefficient of t-hat set to 4095, which is not reduced modulo q.
+    bad = bytes([0xFF, ek[1] | 0x0F]) + ek[2:]
+
+    with pytest.raises(kyber_nz.ModulusCheckFailed):
+        kem.encaps(bad)
+    with pytest.raises(kyber_nz.ModulusCheckFailed):
+        kem.encaps_derand(bad, bytes(32))
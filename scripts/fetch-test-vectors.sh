@@ -0,0 +1,47 @@
+#!/usr/bin/env sh
+# Downloads the published test vectors into tests/, where `cargo test` and pytest pick them up:
+#
+# - tests/acvp/: the ML-KEM keyGen and encapDecap vector sets of usnistgov/ACVP-Server
+#   (`internalProjection.json`, inputs and expected results together);
+# - tests/wycheproof/: the ML-KEM test vectors of C2SP/wycheproof;
+# - tests/drbg/: the CTR_DRBG.rsp files of the CAVP DRBG test vectors;
+# - tests/test_vectors/: the KAT files of post-quantum-cryptography/KAT.
+#
+# These files are not committed. Run the script from the repository root before `cargo test` or
+# pytest, as CI does: a missing file fails its test unless KYBER_NZ_SKIP_MISSING_VECTORS is set.
+
+set -eu
+
+ACVP=https://raw.githubusercontent.com/usnistgov/ACVP-Server/master/gen-val/json-files
+WYCHEPROOF=https://raw.githubusercontent.com/C2SP/wycheproof/main/testvectors_v1
+KAT=https://raw.githubusercontent.com/post-quantum-cryptography/KAT/main/MLKEM
+DRBG=https://csrc.nist.gov/CSRC/media/Projects/Cryptographic-Algorithm-Validation-Program/documents/drbg/drbgtestvectors.zip
+
+fetch() {
+    echo "$2"
+    curl -fsSL --retry 3 -o "$2" "$1"
+}
+
+for mode in keyGen encapDecap; do
+    fetch "$ACVP/ML-KEM-$mode-FIPS203/internalProjection.json" "tests/acvp/ML-KEM-$mode-FIPS203.json"
+done
+
+for level in 512 768 1024; do
+    for kind in "" _encaps _semi_expanded_decaps; do
+        fetch "$WYCHEPROOF/mlkem_${level}${kind}_test.json" \
+            "tests/wycheproof/mlkem_${level}${kind}_test.json"
+    done
+done
+
+mkdir -p tests/test_vectors
+for level in 512 768 1024; do
+    fetch "$KAT/kat_MLKEM_$level.rsp" "tests/test_vectors/kat_MLKEM_$level.rsp"
+done
+
+archive=$(mktemp)
+trap 'rm -f "$archive"' EXIT
+fetch "$DRBG" "$archive"
+for kind in pr_true pr_false no_reseed; do
+    echo "tests/drbg/CTR_DRBG_$kind.rsp"
+    unzip -p "$archive" "drbgvectors_$kind/CTR_DRBG.rsp" >"tests/drbg/CTR_DRBG_$kind.rsp"
+done
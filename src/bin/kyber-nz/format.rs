@@ -0,0 +1,240 @@
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use clap::ValueEnum;
+use zeroize::Zeroizing;
+
+/// Encoding of the files and values read by the command-line tool.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum InputFormat {
+    /// PEM armor, hex, base64 or raw binary, detected from the content.
+    Auto,
+    Hex,
+    Base64,
+    Bin,
+}
+
+/// Encoding of the values printed by the command-line tool.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Hex,
+    Base64,
+}
+
+impl OutputFormat {
+    pub fn encode(self, bytes: &[u8]) -> String {
+        match self {
+            OutputFormat::Hex => hex::encode(bytes),
+            OutputFormat::Base64 => STANDARD.encode(bytes),
+        }
+    }
+}
+
+/// Content of an input file once its transport encoding has been removed.
+pub struct Decoded {
+    /// PEM label, when the input was PEM armored.
+    pub label: Option<String>,
+    /// Decoded bytes, wiped on drop as they may be a decapsulation key or a seed.
+    pub bytes: Zeroizing<Vec<u8>>,
+}
+
+#[derive(Debug)]
+pub struct FormatError(pub String);
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+fn is_hex(text: &str) -> bool {
+    !text.is_empty() && text.len().is_multiple_of(2) && text.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+fn is_base64(text: &str) -> bool {
+    !text.is_empty()
+        && text
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'+' || b == b'/' || b == b'=')
+}
+
+fn strip_whitespace(data: &[u8]) -> Result<Zeroizing<String>, FormatError> {
+    let text = std::str::from_utf8(data)
+        .map_err(|_| FormatError("input is not valid UTF-8 text".to_string()))?;
+    Ok(Zeroizing::new(text.split_whitespace().collect()))
+}
+
+fn decode_hex(data: &[u8]) -> Result<Zeroizing<Vec<u8>>, FormatError> {
+    hex::decode(strip_whitespace(data)?.as_str())
+        .map(Zeroizing::new)
+        .map_err(|e| FormatError(format!("invalid hex: {}", e)))
+}
+
+fn decode_base64(data: &[u8]) -> Result<Zeroizing<Vec<u8>>, FormatError> {
+    STANDARD
+        .decode(strip_whitespace(data)?.as_str())
+        .map(Zeroizing::new)
+        .map_err(|e| FormatError(format!("invalid base64: {}", e)))
+}
+
+/// Removes the PEM armor `-----BEGIN <label>-----` / `-----END <label>-----`.
+pub fn pem_decode(text: &str) -> Result<Decoded, FormatError> {
+    let mut lines = text.lines().map(str::trim).filter(|l| !l.is_empty());
+
+    let label = lines
+        .next()
+        .and_then(|l| l.strip_prefix("-----BEGIN "))
+        .and_then(|l| l.strip_suffix("-----"))
+        .ok_or_else(|| FormatError("missing PEM header".to_string()))?
+        .to_string();
+    let footer = format!("-----END {}-----", label);
+
+    let mut body = Zeroizing::new(String::new());
+    for line in lines.by_ref() {
+        if line == footer {
+            let bytes = STANDARD
+                .decode(body.as_str())
+                .map_err(|e| FormatError(format!("invalid PEM body: {}", e)))?;
+            return Ok(Decoded {
+                label: Some(label),
+                bytes: Zeroizing::new(bytes),
+            });
+        }
+        body.push_str(line);
+    }
+
+    Err(FormatError(format!("missing PEM footer for {}", label)))
+}
+
+/// Adds a PEM armor around `bytes`, with 64-character base64 lines.
+pub fn pem_encode(label: &str, bytes: &[u8]) -> String {
+    let body = STANDARD.encode(bytes);
+    let mut out = format!("-----BEGIN {}-----\n", label);
+    for line in body.as_bytes().chunks(64) {
+        out.push_str(std::str::from_utf8(line).unwrap());
+        out.push('\n');
+    }
+    out.push_str(&format!("-----END {}-----\n", label));
+    out
+}
+
+/// Decodes `data` according to `format`.
+pub fn decode(data: &[u8], format: InputFormat) -> Result<Decoded, FormatError> {
+    let bytes = match format {
+        InputFormat::Hex => decode_hex(data)?,
+        InputFormat::Base64 => decode_base64(data)?,
+        InputFormat::Bin => Zeroizing::new(data.to_vec()),
+        InputFormat::Auto => {
+            if data.starts_with(b"-----BEGIN ") {
+                let text = std::str::from_utf8(data)
+                    .map_err(|_| FormatError("PEM input is not valid UTF-8".to_string()))?;
+                return pem_decode(text);
+            }
+            match strip_whitespace(data) {
+                Ok(text) if is_hex(&text) => decode_hex(data)?,
+                Ok(text) if is_base64(&text) => decode_base64(data)?,
+                _ => Zeroizing::new(data.to_vec()),
+            }
+        }
+    };
+
+    Ok(Decoded { label: None, bytes })
+}
+
+/// Reads and decodes the file at `path` (`-` reads the standard input).
+pub fn read_input(path: &Path, format: InputFormat) -> Result<Decoded, Box<dyn std::error::Error>> {
+    let data = if path.as_os_str() == "-" {
+        let mut buf = Zeroizing::new(Vec::new());
+        std::io::Read::read_to_end(&mut std::io::stdin(), &mut buf)?;
+        buf
+    } else {
+        Zeroizing::new(
+            fs::read(path).map_err(|e| FormatError(format!("{}: {}", path.display(), e)))?,
+        )
+    };
+    Ok(decode(&data, format)?)
+}
+
+/// Writes `contents` to `path`, readable by the owner only when `secret` is set.
+///
+/// The mode given to `open` only applies to a new file, so the permissions of an existing
+/// secret file are reset before anything is written to it.
+pub fn write_file(path: &Path, contents: &[u8], secret: bool) -> std::io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+
+    #[cfg(unix)]
+    if secret {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(path)?;
+
+    #[cfg(unix)]
+    if secret {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    #[cfg(not(unix))]
+    let _ = secret;
+
+    file.write_all(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn basics() -> Result<(), FormatError> {
+        let bytes = b"kyber-nz command line tool".to_vec();
+
+        let pem = pem_encode("ML-KEM-768 ENCAPSULATION KEY", &bytes);
+        let decoded = decode(pem.as_bytes(), InputFormat::Auto)?;
+        assert_eq!(
+            decoded.label.as_deref(),
+            Some("ML-KEM-768 ENCAPSULATION KEY")
+        );
+        assert_eq!(*decoded.bytes, bytes);
+
+        let hex_text = format!("{}\n", hex::encode(&bytes));
+        assert_eq!(
+            *decode(hex_text.as_bytes(), InputFormat::Auto)?.bytes,
+            bytes
+        );
+
+        let b64_text = STANDARD.encode(&bytes);
+        assert_eq!(
+            *decode(b64_text.as_bytes(), InputFormat::Auto)?.bytes,
+            bytes
+        );
+
+        let raw = [0u8, 0xff, 0x10, 0x80];
+        assert_eq!(*decode(&raw, InputFormat::Auto)?.bytes, raw);
+        assert_eq!(*decode(b"abcd", InputFormat::Bin)?.bytes, b"abcd");
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn secret_file_permissions() -> std::io::Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!("kyber-nz-secret-{}", std::process::id()));
+        fs::write(&path, b"public")?;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644))?;
+
+        write_file(&path, b"secret", true)?;
+        let mode = fs::metadata(&path)?.permissions().mode();
+        fs::remove_file(&path)?;
+        assert_eq!(mode & 0o777, 0o600);
+        Ok(())
+    }
+}
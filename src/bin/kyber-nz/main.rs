@@ -0,0 +1,513 @@
+//! # kyber-nz command-line tool
+//!
+//! Generates ML-KEM keys, encapsulates and decapsulates shared secrets, inspects key files
+//! and regenerates known-answer outputs, on top of the [`KemScheme`] API.
+//!
+//! Inputs can be given as PEM, hex, base64 or raw binary (see `--input-format`).
+
+mod format;
+
+use std::error::Error;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use kyber_nz::constants::KyberParams;
+use kyber_nz::hash::H;
+use kyber_nz::kem_scheme::{KemDecapsKey, KemEncapsKey, MlKem};
+use kyber_nz::params::{Kyber1024Params, Kyber512Params, Kyber768Params, SecurityLevel};
+use kyber_nz::traits::KemScheme;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use zeroize::Zeroizing;
+
+use crate::format::{pem_encode, read_input, write_file, FormatError, InputFormat, OutputFormat};
+
+#[derive(Parser)]
+#[command(
+    name = "kyber-nz",
+    version,
+    about = "ML-KEM (FIPS 203) key encapsulation tool"
+)]
+struct Cli {
+    /// Encoding of the input files.
+    #[arg(long, global = true, value_enum, default_value_t = InputFormat::Auto)]
+    input_format: InputFormat,
+
+    /// Encoding of the printed values.
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Hex)]
+    output_format: OutputFormat,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generates a key pair and writes it to `<out>.ek` / `<out>.dk` (or `<out>.seed`).
+    Keygen {
+        /// ML-KEM parameter set.
+        #[arg(long, value_enum)]
+        level: Level,
+        /// Key file format.
+        #[arg(long, value_enum, default_value_t = KeyFormat::Pem)]
+        format: KeyFormat,
+        /// Prefix of the written files.
+        #[arg(long, default_value = "mlkem")]
+        out: PathBuf,
+    },
+    /// Encapsulates a fresh shared secret to an encapsulation key.
+    Encaps {
+        /// Encapsulation key file.
+        ek: PathBuf,
+        /// ML-KEM parameter set, when it cannot be deduced from the key.
+        #[arg(long, value_enum)]
+        level: Option<Level>,
+    },
+    /// Decapsulates a ciphertext with a decapsulation key (or seed).
+    Decaps {
+        /// Decapsulation key or seed file.
+        dk: PathBuf,
+        /// Ciphertext file.
+        ct: PathBuf,
+        /// ML-KEM parameter set, when it cannot be deduced from the key.
+        #[arg(long, value_enum)]
+        level: Option<Level>,
+    },
+    /// Shows the parameter set and H(ek) fingerprint of a key file.
+    Inspect {
+        /// Encapsulation key, decapsulation key or seed file.
+        key: PathBuf,
+        /// ML-KEM parameter set, when it cannot be deduced from the key.
+        #[arg(long, value_enum)]
+        level: Option<Level>,
+    },
+    /// Regenerates known-answer outputs (pk, sk, ct, ss) from a file of `d`, `z`, `msg` seeds.
+    Kat {
+        /// ML-KEM parameter set.
+        #[arg(long, value_enum)]
+        level: Level,
+        /// File of `count = `, `d = `, `z = `, `msg = ` records (hex encoded).
+        seeds: PathBuf,
+    },
+}
+
+/// Storage format of the key files written by `keygen`.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum KeyFormat {
+    /// Raw binary ek and dk.
+    Raw,
+    /// PEM armored ek and dk.
+    Pem,
+    /// PEM armored ek and 64-byte seed (d || z) instead of the expanded dk.
+    Seed,
+}
+
+/// ML-KEM parameter set.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Level {
+    #[value(name = "512")]
+    MlKem512,
+    #[value(name = "768")]
+    MlKem768,
+    #[value(name = "1024")]
+    MlKem1024,
+}
+
+impl Level {
+    const ALL: [Level; 3] = [Level::MlKem512, Level::MlKem768, Level::MlKem1024];
+
+    fn k(self) -> usize {
+        match self {
+            Level::MlKem512 => 2,
+            Level::MlKem768 => 3,
+            Level::MlKem1024 => 4,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Level::MlKem512 => "ML-KEM-512",
+            Level::MlKem768 => "ML-KEM-768",
+            Level::MlKem1024 => "ML-KEM-1024",
+        }
+    }
+
+    fn ek_len(self) -> usize {
+        384 * self.k() + 32
+    }
+
+    fn dk_len(self) -> usize {
+        768 * self.k() + 96
+    }
+}
+
+/// Kind of key material contained in a file.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum KeyKind {
+    Encaps,
+    Decaps,
+    Seed,
+}
+
+impl KeyKind {
+    fn pem_suffix(self) -> &'static str {
+        match self {
+            KeyKind::Encaps => "ENCAPSULATION KEY",
+            KeyKind::Decaps => "DECAPSULATION KEY",
+            KeyKind::Seed => "SEED",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            KeyKind::Encaps => "encapsulation key",
+            KeyKind::Decaps => "decapsulation key",
+            KeyKind::Seed => "seed (d || z)",
+        }
+    }
+}
+
+const SEED_LEN: usize = 64;
+
+fn pem_label(level: Level, kind: KeyKind) -> String {
+    format!("{} {}", level.name(), kind.pem_suffix())
+}
+
+/// A key file with its parameter set and kind resolved.
+struct KeyFile {
+    level: Level,
+    kind: KeyKind,
+    bytes: Zeroizing<Vec<u8>>,
+}
+
+impl KeyFile {
+    /// Reads a key file, deducing the parameter set from the PEM label or the key length.
+    fn read(
+        path: &Path,
+        format: InputFormat,
+        requested: Option<Level>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let decoded = read_input(path, format)?;
+
+        if let Some(label) = decoded.label {
+            for level in Level::ALL {
+                for kind in [KeyKind::Encaps, KeyKind::Decaps, KeyKind::Seed] {
+                    if label == pem_label(level, kind) {
+                        if let Some(requested) = requested.filter(|&r| r != level) {
+                            return Err(error(format!(
+                                "{}: {} key used as {}",
+                                path.display(),
+                                level.name(),
+                                requested.name()
+                            )));
+                        }
+                        return Self::checked(path, level, kind, decoded.bytes);
+                    }
+                }
+            }
+            return Err(error(format!(
+                "{}: unknown PEM label '{}'",
+                path.display(),
+                label
+            )));
+        }
+
+        let len = decoded.bytes.len();
+        let candidates: Vec<(Level, KeyKind)> = Level::ALL
+            .into_iter()
+            .filter(|&l| requested.is_none_or(|r| r == l))
+            .flat_map(|l| {
+                [
+                    (l.ek_len() == len).then_some((l, KeyKind::Encaps)),
+                    (l.dk_len() == len).then_some((l, KeyKind::Decaps)),
+                    (SEED_LEN == len).then_some((l, KeyKind::Seed)),
+                ]
+            })
+            .flatten()
+            .collect();
+
+        match candidates.as_slice() {
+            [(level, kind)] => Self::checked(path, *level, *kind, decoded.bytes),
+            [] => Err(error(format!(
+                "{}: {} bytes is not a valid key length{}",
+                path.display(),
+                len,
+                requested.map_or(String::new(), |l| format!(" for {}", l.name()))
+            ))),
+            _ => Err(error(format!(
+                "{}: ambiguous {} byte key, use --level",
+                path.display(),
+                len
+            ))),
+        }
+    }
+
+    fn checked(
+        path: &Path,
+        level: Level,
+        kind: KeyKind,
+        bytes: Zeroizing<Vec<u8>>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let expected = match kind {
+            KeyKind::Encaps => level.ek_len(),
+            KeyKind::Decaps => level.dk_len(),
+            KeyKind::Seed => SEED_LEN,
+        };
+        if bytes.len() != expected {
+            return Err(error(format!(
+                "{}: {} {} must be {} bytes, got {}",
+                path.display(),
+                level.name(),
+                kind.description(),
+                expected,
+                bytes.len()
+            )));
+        }
+        Ok(KeyFile { level, kind, bytes })
+    }
+}
+
+fn error(message: String) -> Box<dyn Error> {
+    Box::new(FormatError(message))
+}
+
+/// The 64-byte seed d || z of a seed file, whose length was checked when it was read.
+fn seed(key: &KeyFile) -> &[u8; SEED_LEN] {
+    key.bytes[..]
+        .try_into()
+        .expect("seed length checked by KeyFile::read")
+}
+
+fn fingerprint(ek: &[u8]) -> [u8; 32] {
+    H::evaluate(ek)
+}
+
+/// Calls `$func::<K, S>($args)` for the parameter set `$level`.
+macro_rules! dispatch {
+    ($level:expr, $func:ident($($args:expr),*)) => {
+        match $level {
+            Level::MlKem512 => $func::<2, Kyber512Params>($($args),*),
+            Level::MlKem768 => $func::<3, Kyber768Params>($($args),*),
+            Level::MlKem1024 => $func::<4, Kyber1024Params>($($args),*),
+        }
+    };
+}
+
+fn keygen<const K: usize, S: SecurityLevel>(
+    level: Level,
+    format: KeyFormat,
+    out: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let kem = MlKem::<K, S, KyberParams>::new();
+
+    // The seed is only drawn when it is written out; otherwise the key pair comes from `key_gen`.
+    let mut seed = Zeroizing::new([0u8; SEED_LEN]);
+    let (ek, dk) = if format == KeyFormat::Seed {
+        OsRng
+            .try_fill_bytes(seed.as_mut_slice())
+            .map_err(|e| error(format!("cannot draw the seed: {}", e)))?;
+        kem.key_gen_from_entropy(&seed)?
+    } else {
+        kem.key_gen(&mut OsRng)?
+    };
+
+    let ek_bytes = ek.to_bytes();
+    let with_ext = |ext: &str| {
+        let mut path = out.as_os_str().to_owned();
+        path.push(ext);
+        PathBuf::from(path)
+    };
+
+    let written = match format {
+        KeyFormat::Raw => {
+            write_file(&with_ext(".ek"), &ek_bytes, false)?;
+            write_file(&with_ext(".dk"), &dk.to_bytes(), true)?;
+            [with_ext(".ek"), with_ext(".dk")]
+        }
+        KeyFormat::Pem => {
+            let ek_pem = pem_encode(&pem_label(level, KeyKind::Encaps), &ek_bytes);
+            let dk_pem = Zeroizing::new(pem_encode(
+                &pem_label(level, KeyKind::Decaps),
+                &dk.to_bytes(),
+            ));
+            write_file(&with_ext(".ek"), ek_pem.as_bytes(), false)?;
+            write_file(&with_ext(".dk"), dk_pem.as_bytes(), true)?;
+            [with_ext(".ek"), with_ext(".dk")]
+        }
+        KeyFormat::Seed => {
+            let ek_pem = pem_encode(&pem_label(level, KeyKind::Encaps), &ek_bytes);
+            let seed_pem = Zeroizing::new(pem_encode(&pem_label(level, KeyKind::Seed), &*seed));
+            write_file(&with_ext(".ek"), ek_pem.as_bytes(), false)?;
+            write_file(&with_ext(".seed"), seed_pem.as_bytes(), true)?;
+            [with_ext(".ek"), with_ext(".seed")]
+        }
+    };
+
+    println!("parameter set: {}", level.name());
+    println!("H(ek): {}", hex::encode(fingerprint(&ek_bytes)));
+    for path in written {
+        println!("wrote {}", path.display());
+    }
+    Ok(())
+}
+
+fn encaps<const K: usize, S: SecurityLevel>(
+    key: &KeyFile,
+    output: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    if key.kind != KeyKind::Encaps {
+        return Err(error(format!(
+            "expected an encapsulation key, got a {}",
+            key.kind.description()
+        )));
+    }
+
+    let kem = MlKem::<K, S, KyberParams>::new();
+    let ek = KemEncapsKey::<K>::from_bytes(&key.bytes)?;
+    let (shared_secret, ciphertext) = kem.encaps(&ek, &mut OsRng)?;
+
+    println!("ct: {}", output.encode(&ciphertext));
+    println!("ss: {}", output.encode(shared_secret.as_bytes()));
+    Ok(())
+}
+
+fn decaps<const K: usize, S: SecurityLevel>(
+    key: &KeyFile,
+    ciphertext: &[u8],
+    output: OutputFormat,
+) -> Result<(), Box<dyn Error>> {
+    let kem = MlKem::<K, S, KyberParams>::new();
+    let dk = match key.kind {
+        KeyKind::Decaps => KemDecapsKey::<K>::from_bytes(&key.bytes)?,
+        KeyKind::Seed => kem.key_gen_from_entropy(seed(key))?.1,
+        KeyKind::Encaps => {
+            return Err(error(
+                "expected a decapsulation key or seed, got an encapsulation key".to_string(),
+            ))
+        }
+    };
+
+    let shared_secret = kem.decaps(&dk, ciphertext)?;
+    println!("ss: {}", output.encode(shared_secret.as_bytes()));
+    Ok(())
+}
+
+fn inspect<const K: usize, S: SecurityLevel>(key: &KeyFile) -> Result<(), Box<dyn Error>> {
+    println!("parameter set: {}", key.level.name());
+    println!("type: {}", key.kind.description());
+    println!("length: {} bytes", key.bytes.len());
+
+    match key.kind {
+        KeyKind::Encaps => {
+            println!("H(ek): {}", hex::encode(fingerprint(&key.bytes)));
+        }
+        KeyKind::Decaps => {
+            let ek = &key.bytes[384 * K..768 * K + 32];
+            let stored = &key.bytes[768 * K + 32..768 * K + 64];
+            let computed = fingerprint(ek);
+            println!("H(ek): {}", hex::encode(stored));
+            if stored == computed {
+                println!("H(ek) check: ok");
+            } else {
+                println!("H(ek) check: MISMATCH (computed {})", hex::encode(computed));
+            }
+        }
+        KeyKind::Seed => {
+            let kem = MlKem::<K, S, KyberParams>::new();
+            let (ek, _dk) = kem.key_gen_from_entropy(seed(key))?;
+            println!("H(ek): {}", hex::encode(fingerprint(&ek.to_bytes())));
+        }
+    }
+    Ok(())
+}
+
+fn kat<const K: usize, S: SecurityLevel>(seeds: &Path) -> Result<(), Box<dyn Error>> {
+    let kem = MlKem::<K, S, KyberParams>::new();
+    let file = fs::File::open(seeds).map_err(|e| error(format!("{}: {}", seeds.display(), e)))?;
+
+    let seed = |value: &Option<Vec<u8>>, name: &str, count: &str| -> Result<[u8; 32], _> {
+        value
+            .as_deref()
+            .and_then(|v| <[u8; 32]>::try_from(v).ok())
+            .ok_or_else(|| error(format!("count = {}: '{}' must be 32 bytes", count, name)))
+    };
+
+    let mut count = String::new();
+    let (mut d, mut z, mut msg) = (None, None, None);
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let (key, value) = (key.trim(), value.trim());
+
+        match key {
+            "count" => {
+                count = value.to_string();
+                (d, z, msg) = (None, None, None);
+            }
+            "d" => d = Some(hex::decode(value)?),
+            "z" => z = Some(hex::decode(value)?),
+            "msg" => msg = Some(hex::decode(value)?),
+            _ => {}
+        }
+
+        if d.is_some() && z.is_some() && msg.is_some() {
+            let (d_seed, z_seed, m_seed) = (
+                seed(&d, "d", &count)?,
+                seed(&z, "z", &count)?,
+                seed(&msg, "msg", &count)?,
+            );
+            let (ek, dk) = kem.key_gen_internal(&d_seed, &z_seed)?;
+            let (shared_secret, ciphertext) = kem.encaps_internal(&ek, &m_seed)?;
+
+            println!("count = {}", count);
+            println!("d = {}", hex::encode(d_seed));
+            println!("z = {}", hex::encode(z_seed));
+            println!("msg = {}", hex::encode(m_seed));
+            println!("pk = {}", hex::encode(ek.to_bytes()));
+            println!("sk = {}", hex::encode(dk.to_bytes()));
+            println!("ct = {}", hex::encode(&ciphertext));
+            println!("ss = {}", hex::encode(shared_secret.as_bytes()));
+            println!();
+            (d, z, msg) = (None, None, None);
+        }
+    }
+    Ok(())
+}
+
+fn run(cli: Cli) -> Result<(), Box<dyn Error>> {
+    let input = cli.input_format;
+    let output = cli.output_format;
+
+    match cli.command {
+        Command::Keygen { level, format, out } => dispatch!(level, keygen(level, format, &out)),
+        Command::Encaps { ek, level } => {
+            let key = KeyFile::read(&ek, input, level)?;
+            dispatch!(key.level, encaps(&key, output))
+        }
+        Command::Decaps { dk, ct, level } => {
+            let key = KeyFile::read(&dk, input, level)?;
+            let ciphertext = read_input(&ct, input)?.bytes;
+            dispatch!(key.level, decaps(&key, &ciphertext, output))
+        }
+        Command::Inspect { key, level } => {
+            let key = KeyFile::read(&key, input, level)?;
+            dispatch!(key.level, inspect(&key))
+        }
+        Command::Kat { level, seeds } => dispatch!(level, kat(&seeds)),
+    }
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("kyber-nz: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
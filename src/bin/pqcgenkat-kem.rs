@@ -0,0 +1,67 @@
+//! # pqcgenkat-kem
+//!
+//! Rust counterpart of the NIST `PQCgenKAT_kem` tool : writes `PQCkemKAT_<sk bytes>.req` and
+//! `PQCkemKAT_<sk bytes>.rsp` for ML-KEM-512, ML-KEM-768 and ML-KEM-1024.
+//!
+//! Usage : `pqcgenkat-kem [output directory] [--req <file>]`. With `--req`, the seeds are read
+//! from an existing `.req` file instead of being derived from the NIST entropy input.
+
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use kyber_nz::kat::{kat_file_name, kat_seeds, read_seeds, write_req, write_rsp, KAT_COUNT};
+use kyber_nz::params::{Kyber1024Params, Kyber512Params, Kyber768Params, SecurityLevel};
+
+fn generate<const K: usize, S: SecurityLevel>(dir: &Path, seeds: &[[u8; 48]]) -> io::Result<()> {
+    let name = kat_file_name::<K>();
+
+    let mut req = BufWriter::new(File::create(dir.join(format!("{}.req", name)))?);
+    write_req(&mut req, seeds)?;
+    req.flush()?;
+
+    let mut rsp = BufWriter::new(File::create(dir.join(format!("{}.rsp", name)))?);
+    write_rsp::<K, S, _>(&mut rsp, seeds)?;
+    rsp.flush()?;
+
+    println!("{}", dir.join(name).display());
+    Ok(())
+}
+
+fn run() -> io::Result<()> {
+    let mut dir = PathBuf::from(".");
+    let mut req = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--req" => req = args.next().map(PathBuf::from),
+            "-h" | "--help" => {
+                println!("Usage: pqcgenkat-kem [output directory] [--req <file>]");
+                return Ok(());
+            }
+            _ => dir = PathBuf::from(arg),
+        }
+    }
+
+    let seeds = match req {
+        Some(path) => read_seeds(BufReader::new(File::open(path)?))?,
+        None => kat_seeds(KAT_COUNT),
+    };
+
+    fs::create_dir_all(&dir)?;
+    generate::<2, Kyber512Params>(&dir, &seeds)?;
+    generate::<3, Kyber768Params>(&dir, &seeds)?;
+    generate::<4, Kyber1024Params>(&dir, &seeds)?;
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("pqcgenkat-kem: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,316 @@
+//! # C API (feature `capi`)
+//!
+//! `extern "C"` entry points with static-buffer semantics: every key, ciphertext and shared
+//! secret is a caller-allocated buffer of the fixed size given by the matching `#define`.
+//!
+//! Two families are exported for each parameter set:
+//!
+//! * PQClean-compatible: `PQCLEAN_MLKEM768_crypto_kem_keypair` / `_enc` / `_dec`.
+//! * liboqs-compatible: `OQS_KEM_ml_kem_768_keypair` / `_encaps` / `_decaps`, with the
+//!   derandomized `_keypair_derand` (64-byte seed `d || z`) and `_encaps_derand`
+//!   (32-byte seed `m`) variants.
+//!
+//! All functions return `KYBER_NZ_OK` (0) on success and a negative `KYBER_NZ_ERROR_*` code
+//! otherwise. The header `kyber_nz.h` is generated at build time next to the compiled library
+//! (`target/<profile>/include/kyber_nz.h`).
+
+#![allow(non_snake_case, non_upper_case_globals)]
+
+use core::ffi::{c_char, c_int};
+use core::slice;
+
+use rand::rngs::OsRng;
+
+use crate::constants::KyberParams;
+use crate::errors::Error;
+use crate::kem_scheme::{KemDecapsKey, KemEncapsKey, MlKem};
+use crate::params::{Kyber1024Params, Kyber512Params, Kyber768Params, SecurityLevel};
+use crate::service_indicator::{self, ServiceIndicator};
+use crate::traits::KemScheme;
+
+pub const KYBER_NZ_OK: c_int = 0;
+pub const KYBER_NZ_ERROR_NULL_POINTER: c_int = -1;
+pub const KYBER_NZ_ERROR_INVALID_INPUT_LENGTH: c_int = -2;
+pub const KYBER_NZ_ERROR_INVALID_ETA: c_int = -3;
+pub const KYBER_NZ_ERROR_INVALID_ENVELOPE: c_int = -4;
+pub const KYBER_NZ_ERROR_DECRYPTION_FAILED: c_int = -5;
+pub const KYBER_NZ_ERROR_MODULUS_CHECK_FAILED: c_int = -6;
+pub const KYBER_NZ_ERROR_HASH_CHECK_FAILED: c_int = -7;
+pub const KYBER_NZ_ERROR_INVALID_BIT_WIDTH: c_int = -8;
+pub const KYBER_NZ_ERROR_SELF_TEST_FAILED: c_int = -9;
+pub const KYBER_NZ_ERROR_PAIRWISE_CONSISTENCY_FAILED: c_int = -10;
+pub const KYBER_NZ_ERROR_NOT_APPROVED: c_int = -11;
+pub const KYBER_NZ_ERROR_FAULT_DETECTED: c_int = -12;
+pub const KYBER_NZ_ERROR_MEMORY_LOCK_FAILED: c_int = -13;
+pub const KYBER_NZ_ERROR_INVALID_CIPHERTEXT_LENGTH: c_int = -14;
+pub const KYBER_NZ_ERROR_INVALID_KEY_LENGTH: c_int = -15;
+pub const KYBER_NZ_ERROR_PARAMETER_SET_MISMATCH: c_int = -16;
+pub const KYBER_NZ_ERROR_RNG_FAILURE: c_int = -17;
+pub const KYBER_NZ_ERROR_UNSUPPORTED_PARAMETERS: c_int = -18;
+pub const KYBER_NZ_ERROR_ENTROPY_HEALTH_FAILED: c_int = -19;
+
+/// Values of [`kyber_nz_service_indicator`].
+pub const KYBER_NZ_SERVICE_NONE: c_int = -1;
+pub const KYBER_NZ_SERVICE_NOT_APPROVED: c_int = 0;
+pub const KYBER_NZ_SERVICE_APPROVED: c_int = 1;
+
+pub const PQCLEAN_MLKEM512_CRYPTO_PUBLICKEYBYTES: usize = 800;
+pub const PQCLEAN_MLKEM512_CRYPTO_SECRETKEYBYTES: usize = 1632;
+pub const PQCLEAN_MLKEM512_CRYPTO_CIPHERTEXTBYTES: usize = 768;
+pub const PQCLEAN_MLKEM512_CRYPTO_BYTES: usize = 32;
+
+pub const PQCLEAN_MLKEM768_CRYPTO_PUBLICKEYBYTES: usize = 1184;
+pub const PQCLEAN_MLKEM768_CRYPTO_SECRETKEYBYTES: usize = 2400;
+pub const PQCLEAN_MLKEM768_CRYPTO_CIPHERTEXTBYTES: usize = 1088;
+pub const PQCLEAN_MLKEM768_CRYPTO_BYTES: usize = 32;
+
+pub const PQCLEAN_MLKEM1024_CRYPTO_PUBLICKEYBYTES: usize = 1568;
+pub const PQCLEAN_MLKEM1024_CRYPTO_SECRETKEYBYTES: usize = 3168;
+pub const PQCLEAN_MLKEM1024_CRYPTO_CIPHERTEXTBYTES: usize = 1568;
+pub const PQCLEAN_MLKEM1024_CRYPTO_BYTES: usize = 32;
+
+pub const OQS_KEM_ml_kem_512_length_public_key: usize = 800;
+pub const OQS_KEM_ml_kem_512_length_secret_key: usize = 1632;
+pub const OQS_KEM_ml_kem_512_length_ciphertext: usize = 768;
+pub const OQS_KEM_ml_kem_512_length_shared_secret: usize = 32;
+pub const OQS_KEM_ml_kem_512_length_keypair_seed: usize = 64;
+pub const OQS_KEM_ml_kem_512_length_encaps_seed: usize = 32;
+
+pub const OQS_KEM_ml_kem_768_length_public_key: usize = 1184;
+pub const OQS_KEM_ml_kem_768_length_secret_key: usize = 2400;
+pub const OQS_KEM_ml_kem_768_length_ciphertext: usize = 1088;
+pub const OQS_KEM_ml_kem_768_length_shared_secret: usize = 32;
+pub const OQS_KEM_ml_kem_768_length_keypair_seed: usize = 64;
+pub const OQS_KEM_ml_kem_768_length_encaps_seed: usize = 32;
+
+pub const OQS_KEM_ml_kem_1024_length_public_key: usize = 1568;
+pub const OQS_KEM_ml_kem_1024_length_secret_key: usize = 3168;
+pub const OQS_KEM_ml_kem_1024_length_ciphertext: usize = 1568;
+pub const OQS_KEM_ml_kem_1024_length_shared_secret: usize = 32;
+pub const OQS_KEM_ml_kem_1024_length_keypair_seed: usize = 64;
+pub const OQS_KEM_ml_kem_1024_length_encaps_seed: usize = 32;
+
+fn status(result: Result<(), Error>) -> c_int {
+    match result {
+        Ok(()) => KYBER_NZ_OK,
+        Err(e) => e.code(),
+    }
+}
+
+/// Returns a static, NUL-terminated description of a `KYBER_NZ_*` code.
+#[no_mangle]
+pub extern "C" fn kyber_nz_error_string(code: c_int) -> *const c_char {
+    let message: &'static [u8] = match code {
+        KYBER_NZ_OK => b"Success\0",
+        KYBER_NZ_ERROR_NULL_POINTER => b"Null pointer argument\0",
+        KYBER_NZ_ERROR_INVALID_INPUT_LENGTH => b"Input length is invalid\0",
+        KYBER_NZ_ERROR_INVALID_ETA => b"Invalid value for Eta\0",
+        KYBER_NZ_ERROR_INVALID_ENVELOPE => b"Envelope is invalid\0",
+        KYBER_NZ_ERROR_DECRYPTION_FAILED => b"Envelope authentication failed\0",
+        KYBER_NZ_ERROR_MODULUS_CHECK_FAILED => b"Encapsulation key failed the modulus check\0",
+        KYBER_NZ_ERROR_HASH_CHECK_FAILED => b"Decapsulation key failed the hash check\0",
+        KYBER_NZ_ERROR_INVALID_BIT_WIDTH => b"Invalid value for d\0",
+        KYBER_NZ_ERROR_SELF_TEST_FAILED => b"Self-tests failed, the module refuses service\0",
+        KYBER_NZ_ERROR_PAIRWISE_CONSISTENCY_FAILED => {
+            b"Key pair failed the pairwise consistency test\0"
+        }
+        KYBER_NZ_ERROR_NOT_APPROVED => b"Service is not approved in FIPS mode\0",
+        KYBER_NZ_ERROR_FAULT_DETECTED => b"A fault was detected, the output was wiped\0",
+        KYBER_NZ_ERROR_MEMORY_LOCK_FAILED => b"Secret memory could not be locked\0",
+        KYBER_NZ_ERROR_INVALID_CIPHERTEXT_LENGTH => b"Ciphertext length is invalid\0",
+        KYBER_NZ_ERROR_INVALID_KEY_LENGTH => b"Key length is invalid\0",
+        KYBER_NZ_ERROR_PARAMETER_SET_MISMATCH => b"Parameter set mismatch\0",
+        KYBER_NZ_ERROR_RNG_FAILURE => b"The random number generator failed\0",
+        KYBER_NZ_ERROR_UNSUPPORTED_PARAMETERS => b"Parameters are not supported\0",
+        KYBER_NZ_ERROR_ENTROPY_HEALTH_FAILED => {
+            b"Entropy source failed its health tests, the module refuses service\0"
+        }
+        _ => b"Unknown error\0",
+    };
+    message.as_ptr().cast()
+}
+
+/// Approved-service indicator of the last call on this thread (`fips` feature):
+/// `KYBER_NZ_SERVICE_APPROVED`, `KYBER_NZ_SERVICE_NOT_APPROVED`, or `KYBER_NZ_SERVICE_NONE`
+/// when nothing was recorded.
+#[no_mangle]
+pub extern "C" fn kyber_nz_service_indicator() -> c_int {
+    match service_indicator::last() {
+        Some(ServiceIndicator::Approved) => KYBER_NZ_SERVICE_APPROVED,
+        Some(ServiceIndicator::NotApproved) => KYBER_NZ_SERVICE_NOT_APPROVED,
+        None => KYBER_NZ_SERVICE_NONE,
+    }
+}
+
+/// Generates a key pair into `pk` and `sk`, from `seed` (`d || z`) when it is not null, with the
+/// same checks either way.
+///
+/// # Safety
+///
+/// `pk` and `sk` must be valid for writes of the encapsulation and decapsulation key sizes,
+/// and `seed`, when not null, valid for reads of 64 bytes.
+unsafe fn keypair<const K: usize, S: SecurityLevel>(
+    pk: *mut u8,
+    sk: *mut u8,
+    seed: *const u8,
+) -> c_int {
+    if pk.is_null() || sk.is_null() {
+        return KYBER_NZ_ERROR_NULL_POINTER;
+    }
+
+    let kem = MlKem::<K, S, KyberParams>::new();
+    let result = if seed.is_null() {
+        kem.key_gen(&mut OsRng)
+    } else {
+        kem.key_gen_from_entropy(&*seed.cast::<[u8; 64]>())
+    };
+
+    status(result.map(|(ek, dk)| {
+        slice::from_raw_parts_mut(pk, KemEncapsKey::<K>::len()).copy_from_slice(&ek.to_bytes());
+        slice::from_raw_parts_mut(sk, KemDecapsKey::<K>::len()).copy_from_slice(&dk.to_bytes());
+    }))
+}
+
+/// Encapsulates to `pk` into `ct` and `ss`, from `seed` (`m`) when it is not null, checking `pk`
+/// (FIPS 203, 7.2) either way.
+///
+/// # Safety
+///
+/// `ct` and `ss` must be valid for writes of the ciphertext and shared secret sizes, `pk` for
+/// reads of the encapsulation key size and `seed`, when not null, for reads of 32 bytes.
+unsafe fn enc<const K: usize, S: SecurityLevel>(
+    ct: *mut u8,
+    ss: *mut u8,
+    pk: *const u8,
+    seed: *const u8,
+) -> c_int {
+    if ct.is_null() || ss.is_null() || pk.is_null() {
+        return KYBER_NZ_ERROR_NULL_POINTER;
+    }
+
+    let kem = MlKem::<K, S, KyberParams>::new();
+    let result = KemEncapsKey::<K>::from_bytes(slice::from_raw_parts(pk, KemEncapsKey::<K>::len()))
+        .and_then(|ek| {
+            if seed.is_null() {
+                kem.encaps(&ek, &mut OsRng)
+            } else {
+                kem.encaps_from_entropy(&ek, &*seed.cast::<[u8; 32]>())
+            }
+        });
+
+    status(result.map(|(shared_secret, c)| {
+        slice::from_raw_parts_mut(ct, c.len()).copy_from_slice(&c);
+        slice::from_raw_parts_mut(ss, 32).copy_from_slice(&shared_secret.0);
+    }))
+}
+
+/// Decapsulates `ct` with `sk` into `ss`.
+///
+/// # Safety
+///
+/// `ss` must be valid for writes of 32 bytes, `ct` for reads of the ciphertext size and `sk`
+/// for reads of the decapsulation key size.
+unsafe fn dec<const K: usize, S: SecurityLevel>(
+    ss: *mut u8,
+    ct: *const u8,
+    sk: *const u8,
+) -> c_int {
+    if ss.is_null() || ct.is_null() || sk.is_null() {
+        return KYBER_NZ_ERROR_NULL_POINTER;
+    }
+
+    let kem = MlKem::<K, S, KyberParams>::new();
+    let c = slice::from_raw_parts(ct, 32 * (S::DU * K + S::DV));
+    let result = KemDecapsKey::<K>::from_bytes(slice::from_raw_parts(sk, KemDecapsKey::<K>::len()))
+        .and_then(|dk| kem.decaps(&dk, c));
+
+    status(result.map(|shared_secret| {
+        slice::from_raw_parts_mut(ss, 32).copy_from_slice(&shared_secret.0);
+    }))
+}
+
+// PQClean- and liboqs-compatible entry points of each parameter set, generated by `build.rs`
+// from one template around [`keypair`], [`enc`] and [`dec`]. cbindgen reads the same file to
+// write their declarations into `kyber_nz.h`.
+include!(concat!(env!("OUT_DIR"), "/capi_levels.rs"));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::ffi::CStr;
+
+    use crate::params::ParameterSet;
+
+    /// `KYBER_NZ_ERROR_*` constant of each variant. There is no wildcard arm, so a new variant
+    /// does not compile until it is listed here and in `errors` below.
+    fn constant(error: &Error) -> c_int {
+        match error {
+            Error::InvalidInputLength => KYBER_NZ_ERROR_INVALID_INPUT_LENGTH,
+            Error::InvalidEta => KYBER_NZ_ERROR_INVALID_ETA,
+            Error::InvalidEnvelope => KYBER_NZ_ERROR_INVALID_ENVELOPE,
+            Error::DecryptionFailed => KYBER_NZ_ERROR_DECRYPTION_FAILED,
+            Error::ModulusCheckFailed => KYBER_NZ_ERROR_MODULUS_CHECK_FAILED,
+            Error::HashCheckFailed => KYBER_NZ_ERROR_HASH_CHECK_FAILED,
+            Error::InvalidBitWidth => KYBER_NZ_ERROR_INVALID_BIT_WIDTH,
+            Error::SelfTestFailed => KYBER_NZ_ERROR_SELF_TEST_FAILED,
+            Error::PairwiseConsistencyFailed => KYBER_NZ_ERROR_PAIRWISE_CONSISTENCY_FAILED,
+            Error::NotApproved => KYBER_NZ_ERROR_NOT_APPROVED,
+            Error::FaultDetected => KYBER_NZ_ERROR_FAULT_DETECTED,
+            Error::MemoryLockFailed => KYBER_NZ_ERROR_MEMORY_LOCK_FAILED,
+            Error::InvalidCiphertextLength { .. } => KYBER_NZ_ERROR_INVALID_CIPHERTEXT_LENGTH,
+            Error::InvalidKeyLength { .. } => KYBER_NZ_ERROR_INVALID_KEY_LENGTH,
+            Error::ParameterSetMismatch { .. } => KYBER_NZ_ERROR_PARAMETER_SET_MISMATCH,
+            Error::RngFailure => KYBER_NZ_ERROR_RNG_FAILURE,
+            Error::UnsupportedParameters => KYBER_NZ_ERROR_UNSUPPORTED_PARAMETERS,
+            Error::EntropyHealthFailed => KYBER_NZ_ERROR_ENTROPY_HEALTH_FAILED,
+        }
+    }
+
+    #[test]
+    fn error_codes_and_strings() {
+        let errors = [
+            Error::InvalidInputLength,
+            Error::InvalidEta,
+            Error::InvalidEnvelope,
+            Error::DecryptionFailed,
+            Error::ModulusCheckFailed,
+            Error::HashCheckFailed,
+            Error::InvalidBitWidth,
+            Error::SelfTestFailed,
+            Error::PairwiseConsistencyFailed,
+            Error::NotApproved,
+            Error::FaultDetected,
+            Error::MemoryLockFailed,
+            Error::InvalidCiphertextLength {
+                expected: 1088,
+                got: 1087,
+            },
+            Error::InvalidKeyLength {
+                expected: 1184,
+                got: 0,
+            },
+            Error::ParameterSetMismatch {
+                expected: ParameterSet::MlKem768,
+                got: ParameterSet::MlKem512,
+            },
+            Error::RngFailure,
+            Error::UnsupportedParameters,
+            Error::EntropyHealthFailed,
+        ];
+
+        for error in &errors {
+            assert_eq!(error.code(), constant(error), "{error}");
+
+            let message = unsafe { CStr::from_ptr(kyber_nz_error_string(error.code())) }
+                .to_str()
+                .unwrap();
+            assert_ne!(message, "Unknown error", "{error}");
+            // Variants with context append it to the message after a colon.
+            let display = error.to_string();
+            assert!(
+                display == message || display.starts_with(&format!("{message}: ")),
+                "{display} / {message}"
+            );
+        }
+    }
+}
@@ -38,9 +38,7 @@ pub fn bits_to_bytes(bits: &[u8], out: &mut [u8]) -> Result<(), Error> {
     }
 
     for (i, &bit) in bits.iter().enumerate() {
-        if bit == 1 {
-            out[i / 8] |= 1u8 << (i % 8);
-        }
+        out[i / 8] |= (bit & 1) << (i % 8);
     }
     Ok(())
 }
@@ -69,6 +67,10 @@ pub fn bytes_to_bits(bytes: &[u8], out: &mut [u8]) -> Result<(), Error> {
 /// Input : integer array F in Z_m^N, where m = 2^d if d < 12, and m = Q if d = 12
 /// Output : B in B^(32*d)
 pub fn byte_encode(f: &[i16], d: usize, out: &mut [u8]) -> Result<(), Error> {
+    if !(1..=12).contains(&d) {
+        return Err(Error::InvalidBitWidth);
+    }
+
     let mut bits = vec![0u8; f.len() * d];
     for (i, coeff) in f.iter().enumerate() {
         for j in 0..d {
@@ -86,6 +88,10 @@ pub fn byte_encode(f: &[i16], d: usize, out: &mut [u8]) -> Result<(), Error> {
 /// Input : B in B^(32*d)
 /// Output : integer array F in Z_m^N, where m = 2^d if d < 12, and m = Q if d = 12
 pub fn byte_decode(bytes: &[u8], d: usize, q: i16, out: &mut [i16]) -> Result<(), Error> {
+    if !(1..=12).contains(&d) {
+        return Err(Error::InvalidBitWidth);
+    }
+
     let m = match d {
         12 => q,
         _ => 1i16 << d,
@@ -109,6 +115,70 @@ pub fn byte_decode(bytes: &[u8], d: usize, q: i16, out: &mut [i16]) -> Result<()
     Ok(())
 }
 
+/// Kani proofs : output ranges of the compression and decoding functions, for the bit widths
+/// used by ML-KEM, and in-bounds `get_bit` at its call sites.
+#[cfg(kani)]
+mod verification {
+    use super::*;
+    use crate::constants::{KyberParams, PolyParams};
+
+    const Q: i16 = KyberParams::Q;
+
+    #[kani::proof]
+    fn compress_output_range() {
+        let d: usize = kani::any_where(|d: &usize| (1..12).contains(d));
+        let x: i16 = kani::any_where(|x: &i16| (0..Q).contains(x));
+        let y = compress(x, d, Q);
+        assert!(0 <= y && (y as i32) < (1 << d));
+    }
+
+    #[kani::proof]
+    fn decompress_output_range() {
+        let d: usize = kani::any_where(|d: &usize| (1..12).contains(d));
+        let y: i16 = kani::any_where(|y: &i16| 0 <= *y && (*y as i32) < (1 << d));
+        let x = decompress(y, d, Q);
+        assert!((0..Q).contains(&x));
+    }
+
+    #[kani::proof]
+    #[kani::unwind(97)]
+    fn byte_decode_output_below_m() {
+        let d: usize = kani::any_where(|d: &usize| (1..=12).contains(d));
+        let bytes: [u8; 12] = kani::any();
+        let mut out = vec![0i16; 96 / d];
+        byte_decode(&bytes, d, Q, &mut out).unwrap();
+        let m = if d == 12 { Q as i32 } else { 1 << d };
+        assert!(out.iter().all(|&f| 0 <= f && (f as i32) < m));
+    }
+
+    /// `get_bit` as called by `SamplePolyCBD_eta`, plain and masked : bit 2*i*eta + j of the
+    /// 64*eta bytes of PRF_eta, for i < 256 and j < 2*eta.
+    #[kani::proof]
+    fn get_bit_in_bounds_in_sample_poly_cbd() {
+        let eta: usize = kani::any_where(|eta: &usize| *eta == 2 || *eta == 3);
+        let prf: [u8; 192] = kani::any();
+        let bytes = &prf[..64 * eta];
+        let i: usize = kani::any_where(|i: &usize| *i < 256);
+        let j: usize = kani::any_where(|j: &usize| *j < 2 * eta);
+        let index = 2 * i * eta + j;
+        assert!(index < 8 * bytes.len());
+        let bit = get_bit(bytes, index);
+        assert!(bit == 0 || bit == 1);
+    }
+
+    /// `get_bit` as called by the masked decryption : bit 12*i + j of a 384-byte secret
+    /// polynomial for j < 12, and bit i of a 32-byte message.
+    #[kani::proof]
+    fn get_bit_in_bounds_in_masked_decode() {
+        let secret: [u8; 384] = kani::any();
+        let message: [u8; 32] = kani::any();
+        let i: usize = kani::any_where(|i: &usize| *i < 256);
+        let j: usize = kani::any_where(|j: &usize| *j < 12);
+        let bits = [get_bit(&secret, 12 * i + j), get_bit(&message, i)];
+        assert!(bits.iter().all(|&bit| bit == 0 || bit == 1));
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -166,6 +236,13 @@ mod tests {
             res
         };
         assert_eq!(f, f_rev);
+
+        let mut out = [0u8; 0];
+        assert_eq!(byte_encode(&[], 0, &mut out), Err(Error::InvalidBitWidth));
+        assert_eq!(
+            byte_decode(&[0u8; 32], 13, q, &mut [0i16; 19]),
+            Err(Error::InvalidBitWidth)
+        );
         Ok(())
     }
 }
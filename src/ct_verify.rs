@@ -0,0 +1,91 @@
+//! # Secret-dependence verification (feature `ct-verify`)
+//!
+//! ctgrind-style checking with valgrind's memcheck: [`poison`] marks secret bytes as undefined,
+//! so that memcheck reports every conditional branch, memory index or system call depending on
+//! them, i.e. every constant-time violation reachable from the secrets.
+//!
+//! Values that are public by design are marked defined again with [`declassify`]:
+//! - `rho` and the encapsulation key, computed from d by key generation;
+//! - the ciphertext, computed from m by encapsulation;
+//! - the copy of ek and H(ek) embedded in the decapsulation key.
+//!
+//! The result of the ciphertext comparison in decapsulation is not declassified: it only feeds
+//! `conditional_assign`, and memcheck checks that it never reaches a branch.
+//!
+//! The client requests are no-ops outside valgrind. `tests/ct_verify.rs` runs key generation,
+//! encapsulation and decapsulation under memcheck.
+
+/// `VG_USERREQ__RUNNING_ON_VALGRIND`
+const RUNNING_ON_VALGRIND: usize = 0x1001;
+/// `VG_USERREQ__MAKE_MEM_UNDEFINED` / `VG_USERREQ__MAKE_MEM_DEFINED` (tool base 'M', 'C')
+const MAKE_MEM_UNDEFINED: usize = 0x4d43_0001;
+const MAKE_MEM_DEFINED: usize = 0x4d43_0002;
+
+/// `VALGRIND_DO_CLIENT_REQUEST_EXPR` : returns `default` when not running under valgrind.
+#[cfg(target_arch = "x86_64")]
+fn client_request(default: usize, args: &[usize; 6]) -> usize {
+    let result;
+    // SAFETY: the special instruction preamble rotates rdi by 128 bits (a no-op), and valgrind
+    // only reads `args`.
+    unsafe {
+        core::arch::asm!(
+            "rol rdi, 3",
+            "rol rdi, 13",
+            "rol rdi, 61",
+            "rol rdi, 51",
+            "xchg rbx, rbx",
+            inout("rdx") default => result,
+            in("rax") args.as_ptr(),
+            options(nostack),
+        );
+    }
+    result
+}
+
+/// `VALGRIND_DO_CLIENT_REQUEST_EXPR` : returns `default` when not running under valgrind.
+#[cfg(target_arch = "aarch64")]
+fn client_request(default: usize, args: &[usize; 6]) -> usize {
+    let result;
+    // SAFETY: the special instruction preamble rotates x12 by 128 bits (a no-op), and valgrind
+    // only reads `args`.
+    unsafe {
+        core::arch::asm!(
+            "ror x12, x12, #3",
+            "ror x12, x12, #13",
+            "ror x12, x12, #51",
+            "ror x12, x12, #61",
+            "orr x10, x10, x10",
+            inout("x3") default => result,
+            in("x4") args.as_ptr(),
+            options(nostack),
+        );
+    }
+    result
+}
+
+/// Valgrind does not support other architectures.
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn client_request(default: usize, _args: &[usize; 6]) -> usize {
+    default
+}
+
+/// Returns true when running under valgrind.
+pub fn running_on_valgrind() -> bool {
+    client_request(0, &[RUNNING_ON_VALGRIND, 0, 0, 0, 0, 0]) != 0
+}
+
+/// Sets the memcheck definedness of `bytes`.
+fn mem_request(request: usize, bytes: &[u8]) {
+    let args = [request, bytes.as_ptr() as usize, bytes.len(), 0, 0, 0];
+    client_request(0, &args);
+}
+
+/// Marks `bytes` as secret : memcheck reports any branch or memory index that depends on them.
+pub fn poison(bytes: &[u8]) {
+    mem_request(MAKE_MEM_UNDEFINED, bytes);
+}
+
+/// Marks `bytes` as public.
+pub fn declassify(bytes: &[u8]) {
+    mem_request(MAKE_MEM_DEFINED, bytes);
+}
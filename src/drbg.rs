@@ -0,0 +1,294 @@
+//! # CTR_DRBG (SP 800-90A Rev. 1, 10.2) (feature `drbg`)
+//!
+//! [`CtrDrbg`] is the AES-256 CTR_DRBG of SP 800-90A, an approved deterministic random bit
+//! generator. It implements [`RngCore`] and [`CryptoRng`], so it can be passed to `key_gen`
+//! and `encaps` wherever the approved randomness source must be a DRBG rather than `OsRng`.
+//!
+//! The DRBG takes its entropy input from a source `E` of its own, an [`RngCore`] reading the
+//! platform's entropy source (`OsRng`, or a noise source behind
+//! [`EntropyHealth`](crate::entropy_health::EntropyHealth)):
+//! - on instantiation, 32 bytes of entropy input and a 16-byte nonce, or 48 bytes of
+//!   full-entropy input without derivation function;
+//! - on every reseed: explicit ([`CtrDrbg::reseed`]), after [`RESEED_INTERVAL`] requests, or
+//!   before every request with prediction resistance.
+//!
+//! A failure of the source is returned as [`Error::RngFailure`], or as
+//! [`Error::EntropyHealthFailed`] when its health tests failed. The state is wiped on drop.
+//!
+//! Both variants of the standard are available: with the Block_Cipher_df derivation function
+//! ([`CtrDrbg::instantiate`]), and without ([`CtrDrbg::instantiate_no_df`]), where the
+//! personalization string and additional inputs are at most 48 bytes.
+
+use aes::cipher::{BlockEncrypt, KeyInit};
+use aes::Aes256;
+use rand::{CryptoRng, RngCore};
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::entropy_health::rng_error;
+use crate::errors::Error;
+
+const KEY_LEN: usize = 32;
+const BLOCK_LEN: usize = 16;
+/// seedlen = keylen + blocklen
+const SEED_LEN: usize = KEY_LEN + BLOCK_LEN;
+/// Entropy input of the 256-bit security strength
+const ENTROPY_LEN: usize = 32;
+/// Nonce of half the security strength
+const NONCE_LEN: usize = 16;
+/// max_number_of_bits_per_request = 2^19
+const MAX_REQUEST_LEN: usize = 1 << 16;
+
+/// Maximum number of requests between reseeds (SP 800-90A, Table 3)
+pub const RESEED_INTERVAL: u64 = 1 << 48;
+
+/// AES-256 CTR_DRBG (see the [module documentation](self)).
+pub struct CtrDrbg<E: RngCore> {
+    key: [u8; KEY_LEN],
+    v: [u8; BLOCK_LEN],
+    reseed_counter: u64,
+    derivation_function: bool,
+    prediction_resistance: bool,
+    source: E,
+}
+
+impl<E: RngCore> CtrDrbg<E> {
+    /// CTR_DRBG_Instantiate_algorithm with derivation function (10.2.1.3.2) : draws the
+    /// entropy input and the nonce from `source`.
+    pub fn instantiate(
+        source: E,
+        personalization_string: &[u8],
+        prediction_resistance: bool,
+    ) -> Result<Self, Error> {
+        let mut drbg = Self::new(source, true, prediction_resistance);
+        let mut entropy_input = Zeroizing::new([0u8; ENTROPY_LEN + NONCE_LEN]);
+        drbg.get_entropy(&mut entropy_input[..ENTROPY_LEN])?;
+        drbg.get_entropy(&mut entropy_input[ENTROPY_LEN..])?;
+
+        let seed_material = Zeroizing::new([&entropy_input[..], personalization_string].concat());
+        drbg.update(&block_cipher_df(&seed_material));
+        drbg.reseed_counter = 1;
+        Ok(drbg)
+    }
+
+    /// CTR_DRBG_Instantiate_algorithm without derivation function (10.2.1.3.1) : draws 48
+    /// bytes of full-entropy input from `source`. The personalization string is at most 48
+    /// bytes.
+    pub fn instantiate_no_df(
+        source: E,
+        personalization_string: &[u8],
+        prediction_resistance: bool,
+    ) -> Result<Self, Error> {
+        let mut seed_material = padded(personalization_string)?;
+        let mut drbg = Self::new(source, false, prediction_resistance);
+        let mut entropy_input = Zeroizing::new([0u8; SEED_LEN]);
+        drbg.get_entropy(&mut entropy_input[..])?;
+
+        for (s, e) in seed_material.iter_mut().zip(entropy_input.iter()) {
+            *s ^= e;
+        }
+        drbg.update(&seed_material);
+        drbg.reseed_counter = 1;
+        Ok(drbg)
+    }
+
+    fn new(source: E, derivation_function: bool, prediction_resistance: bool) -> Self {
+        CtrDrbg {
+            key: [0u8; KEY_LEN],
+            v: [0u8; BLOCK_LEN],
+            reseed_counter: 0,
+            derivation_function,
+            prediction_resistance,
+            source,
+        }
+    }
+
+    /// Number of requests since the last (re)seeding, plus one
+    pub fn reseed_counter(&self) -> u64 {
+        self.reseed_counter
+    }
+
+    pub fn prediction_resistance(&self) -> bool {
+        self.prediction_resistance
+    }
+
+    /// CTR_DRBG_Reseed_algorithm (10.2.1.4) : draws fresh entropy input from the source.
+    pub fn reseed(&mut self, additional_input: &[u8]) -> Result<(), Error> {
+        let seed_material = if self.derivation_function {
+            let mut entropy_input = Zeroizing::new([0u8; ENTROPY_LEN]);
+            self.get_entropy(&mut entropy_input[..])?;
+            let input = Zeroizing::new([&entropy_input[..], additional_input].concat());
+            block_cipher_df(&input)
+        } else {
+            let mut seed_material = padded(additional_input)?;
+            let mut entropy_input = Zeroizing::new([0u8; SEED_LEN]);
+            self.get_entropy(&mut entropy_input[..])?;
+            for (s, e) in seed_material.iter_mut().zip(entropy_input.iter()) {
+                *s ^= e;
+            }
+            seed_material
+        };
+
+        self.update(&seed_material);
+        self.reseed_counter = 1;
+        Ok(())
+    }
+
+    /// CTR_DRBG_Generate_algorithm (10.2.1.5) : fills `out`, at most 2^16 bytes, reseeding
+    /// first with prediction resistance or once the reseed interval is reached.
+    pub fn generate(&mut self, out: &mut [u8], additional_input: &[u8]) -> Result<(), Error> {
+        if out.len() > MAX_REQUEST_LEN {
+            return Err(Error::InvalidInputLength);
+        }
+
+        let mut additional_input = additional_input;
+        if self.prediction_resistance || self.reseed_counter > RESEED_INTERVAL {
+            self.reseed(additional_input)?;
+            additional_input = &[];
+        }
+
+        let additional = if additional_input.is_empty() {
+            Zeroizing::new([0u8; SEED_LEN])
+        } else {
+            let additional = if self.derivation_function {
+                block_cipher_df(additional_input)
+            } else {
+                padded(additional_input)?
+            };
+            self.update(&additional);
+            additional
+        };
+
+        let cipher = Aes256::new(&self.key.into());
+        for chunk in out.chunks_mut(BLOCK_LEN) {
+            increment(&mut self.v);
+            let mut block = self.v.into();
+            cipher.encrypt_block(&mut block);
+            chunk.copy_from_slice(&block[..chunk.len()]);
+            block.zeroize();
+        }
+
+        self.update(&additional);
+        self.reseed_counter += 1;
+        Ok(())
+    }
+
+    /// CTR_DRBG_Update (10.2.1.2)
+    fn update(&mut self, provided_data: &[u8; SEED_LEN]) {
+        let cipher = Aes256::new(&self.key.into());
+        let mut temp = Zeroizing::new([0u8; SEED_LEN]);
+        for chunk in temp.chunks_exact_mut(BLOCK_LEN) {
+            increment(&mut self.v);
+            let mut block = self.v.into();
+            cipher.encrypt_block(&mut block);
+            chunk.copy_from_slice(&block);
+            block.zeroize();
+        }
+
+        for (t, d) in temp.iter_mut().zip(provided_data) {
+            *t ^= d;
+        }
+        self.key.copy_from_slice(&temp[..KEY_LEN]);
+        self.v.copy_from_slice(&temp[KEY_LEN..]);
+    }
+
+    fn get_entropy(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.source.try_fill_bytes(dest).map_err(rng_error)
+    }
+}
+
+impl<E: RngCore> Drop for CtrDrbg<E> {
+    fn drop(&mut self) {
+        self.key.zeroize();
+        self.v.zeroize();
+    }
+}
+
+impl<E: RngCore> RngCore for CtrDrbg<E> {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    /// Panics if the entropy source fails, use `try_fill_bytes` to handle it.
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        if let Err(e) = self.try_fill_bytes(dest) {
+            panic!("{e}");
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        for chunk in dest.chunks_mut(MAX_REQUEST_LEN) {
+            self.generate(chunk, &[]).map_err(rand::Error::new)?;
+        }
+        Ok(())
+    }
+}
+
+impl<E: RngCore + CryptoRng> CryptoRng for CtrDrbg<E> {}
+
+/// Increments V as a 128-bit big-endian counter.
+fn increment(v: &mut [u8; BLOCK_LEN]) {
+    for byte in v.iter_mut().rev() {
+        *byte = byte.wrapping_add(1);
+        if *byte != 0 {
+            break;
+        }
+    }
+}
+
+/// Input of at most seedlen bytes, padded with zeros (without derivation function)
+fn padded(input: &[u8]) -> Result<Zeroizing<[u8; SEED_LEN]>, Error> {
+    if input.len() > SEED_LEN {
+        return Err(Error::InvalidInputLength);
+    }
+    let mut out = Zeroizing::new([0u8; SEED_LEN]);
+    out[..input.len()].copy_from_slice(input);
+    Ok(out)
+}
+
+/// Block_Cipher_df (10.3.2), returning seedlen bytes
+fn block_cipher_df(input: &[u8]) -> Zeroizing<[u8; SEED_LEN]> {
+    // S = L || N || input_string || 0x80, padded with zeros to a multiple of the block length
+    let mut s = Zeroizing::new(Vec::with_capacity(input.len() + 2 * BLOCK_LEN));
+    s.extend_from_slice(&(input.len() as u32).to_be_bytes());
+    s.extend_from_slice(&(SEED_LEN as u32).to_be_bytes());
+    s.extend_from_slice(input);
+    s.push(0x80);
+    let padded_len = s.len().div_ceil(BLOCK_LEN) * BLOCK_LEN;
+    s.resize(padded_len, 0);
+
+    let df_key: [u8; KEY_LEN] = core::array::from_fn(|i| i as u8);
+    let cipher = Aes256::new(&df_key.into());
+    let mut temp = Zeroizing::new([0u8; SEED_LEN]);
+    for (i, chunk) in temp.chunks_exact_mut(BLOCK_LEN).enumerate() {
+        // BCC(K, IV || S), IV = i padded with zeros to a block
+        let mut chaining_value = aes::Block::default();
+        let mut iv = [0u8; BLOCK_LEN];
+        iv[..4].copy_from_slice(&(i as u32).to_be_bytes());
+        for block in core::iter::once(&iv[..]).chain(s.chunks_exact(BLOCK_LEN)) {
+            for (c, b) in chaining_value.iter_mut().zip(block) {
+                *c ^= b;
+            }
+            cipher.encrypt_block(&mut chaining_value);
+        }
+        chunk.copy_from_slice(&chaining_value);
+        chaining_value.zeroize();
+    }
+
+    let cipher = Aes256::new_from_slice(&temp[..KEY_LEN]).unwrap();
+    let mut x = aes::Block::clone_from_slice(&temp[KEY_LEN..]);
+    let mut out = Zeroizing::new([0u8; SEED_LEN]);
+    for chunk in out.chunks_exact_mut(BLOCK_LEN) {
+        cipher.encrypt_block(&mut x);
+        chunk.copy_from_slice(&x);
+    }
+    x.zeroize();
+    out
+}
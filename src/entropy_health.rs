@@ -0,0 +1,285 @@
+//! # Continuous health tests of a noise source (SP 800-90B, 4.4)
+//!
+//! [`EntropyHealth`] wraps an [`RngCore`] reading a noise source, and runs on every byte it
+//! returns, before `key_gen` or `encaps` can use it:
+//!
+//! - the Repetition Count Test, which fails when a byte repeats [`HealthCutoffs::repetition`]
+//!   times in a row (4.4.1);
+//! - the Adaptive Proportion Test, which fails when the first byte of a window of 512 comes
+//!   back [`HealthCutoffs::adaptive_proportion`] times within the window (4.4.2).
+//!
+//! Before its first output, the wrapper runs both tests on 1024 start-up samples that it
+//! discards (4.3).
+//!
+//! The cutoffs follow from the min-entropy per byte claimed for the source, with a false
+//! positive probability α = 2^-20 ([`HealthCutoffs::for_min_entropy`]), or can be set
+//! directly.
+//!
+//! A failure wipes the output, and `try_fill_bytes` returns [`Error::EntropyHealthFailed`],
+//! which `key_gen` and `encaps` return as is. The failure is also permanent for the whole
+//! module: from then on `key_gen`, `encaps`, `decaps` and their `_from_entropy` variants
+//! refuse service with the same error until the process restarts ([`failed`]).
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use rand::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+use crate::errors::Error;
+
+/// Window of the Adaptive Proportion Test for non-binary samples
+const WINDOW: u32 = 512;
+/// Number of start-up samples
+const STARTUP_SAMPLES: usize = 1024;
+/// False positive probability α = 2^-20, as -log2(α)
+const ALPHA_LOG2: f64 = 20.0;
+
+static FAILED: AtomicBool = AtomicBool::new(false);
+
+/// Whether a noise source has failed its health tests, putting the module in its error state.
+pub fn failed() -> bool {
+    FAILED.load(Ordering::Acquire)
+}
+
+/// Refuses service once a noise source has failed its health tests.
+pub(crate) fn ensure_healthy() -> Result<(), Error> {
+    if failed() {
+        Err(Error::EntropyHealthFailed)
+    } else {
+        Ok(())
+    }
+}
+
+/// Error of a failed `try_fill_bytes` : [`Error::EntropyHealthFailed`] from an
+/// [`EntropyHealth`], [`Error::RngFailure`] otherwise.
+pub(crate) fn rng_error(error: rand::Error) -> Error {
+    match error.inner().downcast_ref::<Error>() {
+        Some(&Error::EntropyHealthFailed) => Error::EntropyHealthFailed,
+        _ => Error::RngFailure,
+    }
+}
+
+/// Cutoffs of the health tests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HealthCutoffs {
+    /// Number of identical consecutive bytes failing the Repetition Count Test
+    pub repetition: u32,
+    /// Number of occurrences of the first byte of a 512-byte window failing the Adaptive
+    /// Proportion Test
+    pub adaptive_proportion: u32,
+}
+
+impl HealthCutoffs {
+    /// Cutoffs for a source claimed to provide `h` bits of min-entropy per byte,
+    /// 0 < h <= 8, with α = 2^-20 : C = 1 + ⌈20 / h⌉ for the Repetition Count Test, and
+    /// C = 1 + CRITBINOM(512, 2^-h, 1 - α) for the Adaptive Proportion Test.
+    pub fn for_min_entropy(h: f64) -> Result<Self, Error> {
+        if !(h > 0.0 && h <= 8.0) {
+            return Err(Error::UnsupportedParameters);
+        }
+
+        // smallest k with P(B <= k) >= 1 - α, B ~ Binomial(512, p), pmf computed in log space
+        let p = (-h).exp2();
+        let (ln_p, ln_q) = (p.ln(), (1.0 - p).ln());
+        let mut ln_pmf = WINDOW as f64 * ln_q;
+        let mut cdf = 0.0;
+        let mut k = 0;
+        loop {
+            cdf += ln_pmf.exp();
+            if cdf >= 1.0 - ALPHA_LOG2.exp2().recip() || k == WINDOW {
+                break;
+            }
+            ln_pmf += ((WINDOW - k) as f64 / (k + 1) as f64).ln() + ln_p - ln_q;
+            k += 1;
+        }
+
+        Ok(HealthCutoffs {
+            repetition: 1 + (ALPHA_LOG2 / h).ceil() as u32,
+            adaptive_proportion: (1 + k).min(WINDOW),
+        })
+    }
+}
+
+/// State of the two continuous tests
+struct HealthTests {
+    cutoffs: HealthCutoffs,
+    last: u8,
+    repetitions: u32,
+    window_first: u8,
+    window_len: u32,
+    window_count: u32,
+}
+
+impl HealthTests {
+    fn new(cutoffs: HealthCutoffs) -> Self {
+        HealthTests {
+            cutoffs,
+            last: 0,
+            repetitions: 0,
+            window_first: 0,
+            window_len: 0,
+            window_count: 0,
+        }
+    }
+
+    /// Runs both tests on one sample, false on failure.
+    fn feed(&mut self, sample: u8) -> bool {
+        if self.repetitions > 0 && sample == self.last {
+            self.repetitions += 1;
+        } else {
+            self.last = sample;
+            self.repetitions = 1;
+        }
+
+        if self.window_len == 0 {
+            self.window_first = sample;
+            self.window_count = 1;
+        } else if sample == self.window_first {
+            self.window_count += 1;
+        }
+        self.window_len = (self.window_len + 1) % WINDOW;
+
+        self.repetitions < self.cutoffs.repetition
+            && self.window_count < self.cutoffs.adaptive_proportion
+    }
+}
+
+/// [`RngCore`] wrapper running the SP 800-90B continuous health tests on the bytes of a noise
+/// source (see the [module documentation](self)).
+pub struct EntropyHealth<R: RngCore> {
+    source: R,
+    tests: HealthTests,
+    started: bool,
+}
+
+impl<R: RngCore> EntropyHealth<R> {
+    pub fn new(source: R, cutoffs: HealthCutoffs) -> Self {
+        EntropyHealth {
+            source,
+            tests: HealthTests::new(cutoffs),
+            started: false,
+        }
+    }
+
+    /// Draws from the source and tests every byte, wiping `dest` on failure.
+    fn draw(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.source.try_fill_bytes(dest).map_err(rng_error)?;
+        let mut healthy = true;
+        for &sample in dest.iter() {
+            healthy &= self.tests.feed(sample);
+        }
+        if healthy {
+            Ok(())
+        } else {
+            dest.zeroize();
+            FAILED.store(true, Ordering::Release);
+            Err(Error::EntropyHealthFailed)
+        }
+    }
+}
+
+impl<R: RngCore> RngCore for EntropyHealth<R> {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    /// Panics if the source fails, use `try_fill_bytes` to handle it.
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        if let Err(e) = self.try_fill_bytes(dest) {
+            panic!("{e}");
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        ensure_healthy().map_err(rand::Error::new)?;
+        if !self.started {
+            let mut startup = [0u8; STARTUP_SAMPLES];
+            self.draw(&mut startup).map_err(rand::Error::new)?;
+            startup.zeroize();
+            self.started = true;
+        }
+        self.draw(dest).map_err(rand::Error::new)
+    }
+}
+
+impl<R: RngCore + CryptoRng> CryptoRng for EntropyHealth<R> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cutoffs() -> Result<(), Error> {
+        // SP 800-90B, 4.4.1 and Table 2
+        for (h, repetition, adaptive_proportion) in [
+            (0.5, 41, 410),
+            (1.0, 21, 311),
+            (2.0, 11, 177),
+            (4.0, 6, 62),
+            (8.0, 4, 13),
+        ] {
+            assert_eq!(
+                HealthCutoffs::for_min_entropy(h)?,
+                HealthCutoffs {
+                    repetition,
+                    adaptive_proportion
+                },
+                "h = {h}"
+            );
+        }
+        assert_eq!(
+            HealthCutoffs::for_min_entropy(0.0).err(),
+            Some(Error::UnsupportedParameters)
+        );
+        assert_eq!(
+            HealthCutoffs::for_min_entropy(9.0).err(),
+            Some(Error::UnsupportedParameters)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn repetition_count() -> Result<(), Error> {
+        let mut tests = HealthTests::new(HealthCutoffs::for_min_entropy(4.0)?);
+        assert!((0..=255).all(|b| tests.feed(b)));
+        assert!((0..5).all(|_| tests.feed(7)));
+        assert!(!tests.feed(7));
+        Ok(())
+    }
+
+    #[test]
+    fn adaptive_proportion() -> Result<(), Error> {
+        let cutoffs = HealthCutoffs::for_min_entropy(8.0)?;
+        let mut tests = HealthTests::new(cutoffs);
+        // 12 zeros per window pass, then the window restarts
+        for _ in 0..2 {
+            for i in 0..WINDOW {
+                let sample = if i % 43 == 0 { 0 } else { 1 + (i % 200) as u8 };
+                assert!(tests.feed(sample), "i = {i}");
+            }
+        }
+
+        let mut tests = HealthTests::new(cutoffs);
+        let samples = (0..WINDOW).map(|i| if i % 20 == 0 { 0 } else { 1 + (i % 200) as u8 });
+        assert!(!samples.into_iter().all(|sample| tests.feed(sample)));
+        Ok(())
+    }
+
+    #[test]
+    fn healthy_source() -> Result<(), Error> {
+        let mut rng = EntropyHealth::new(rand::rngs::OsRng, HealthCutoffs::for_min_entropy(1.0)?);
+        let mut bytes = [0u8; 4096];
+        rng.try_fill_bytes(&mut bytes).unwrap();
+        assert!(rng.started);
+        assert!(!failed());
+        Ok(())
+    }
+}
@@ -1,9 +1,124 @@
 use core::fmt;
 
-#[derive(Debug, PartialEq)]
+use crate::params::ParameterSet;
+
+/// Errors of the library.
+///
+/// New variants may be added in minor releases. Each variant has a stable numeric [`code`],
+/// used as is by the C API, and a [`kind`] telling malformed input apart from misuse and
+/// from failures of the module itself.
+///
+/// [`code`]: Error::code
+/// [`kind`]: Error::kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum Error {
+    /// A seed, message or buffer has the wrong length
     InvalidInputLength,
+    /// η is not one of the values of SamplePolyCBD (2 or 3)
     InvalidEta,
+    /// A bit width d is outside of the range of ByteEncode / ByteDecode (1 to 12)
+    InvalidBitWidth,
+    /// An envelope is malformed (unknown version, parameter set, AEAD or chunk size) or a message
+    /// does not fit in one (too many chunks, or a chunk the AEAD refuses to encrypt)
+    InvalidEnvelope,
+    /// An envelope failed authentication: it was forged, truncated or sealed to another key
+    DecryptionFailed,
+    /// An encapsulation key has a coefficient not reduced modulo q (FIPS 203, 7.2)
+    ModulusCheckFailed,
+    /// The H(ek) stored in a decapsulation key does not match its ek (FIPS 203, 7.3)
+    HashCheckFailed,
+    /// The self-tests failed, the module refuses service
+    SelfTestFailed,
+    /// A new key pair failed the pairwise consistency test, both keys were wiped
+    PairwiseConsistencyFailed,
+    /// The service or parameter set is not approved in FIPS mode
+    NotApproved,
+    /// A fault-injection countermeasure detected an inconsistency, the output was wiped
+    FaultDetected,
+    /// Secret memory could not be mapped, locked or protected
+    MemoryLockFailed,
+    /// A ciphertext has the wrong length for the parameter set
+    InvalidCiphertextLength { expected: usize, got: usize },
+    /// A serialized key has the wrong length, and is not a key of another parameter set either
+    InvalidKeyLength { expected: usize, got: usize },
+    /// A key or an envelope belongs to another parameter set
+    ParameterSetMismatch {
+        expected: ParameterSet,
+        got: ParameterSet,
+    },
+    /// The random number generator failed
+    RngFailure,
+    /// The parameters are not one of the parameter sets of FIPS 203
+    UnsupportedParameters,
+    /// A noise source failed its health tests, the module refuses service
+    EntropyHealthFailed,
+}
+
+/// Broad class of an [`Error`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// Input supplied by the caller or received from a peer is malformed: wrong length,
+    /// failed key check, invalid or forged envelope.
+    MalformedInput,
+    /// The library is used with parameters or in a mode it does not support.
+    Unsupported,
+    /// The module itself failed: self-test, pairwise consistency test or fault detection.
+    /// Points to a bug, a hardware fault or an attack, never to bad input.
+    Internal,
+    /// The environment failed: random number generator, noise source, locked memory.
+    Environment,
+}
+
+impl Error {
+    /// Stable numeric code of the error, the `KYBER_NZ_ERROR_*` value of the C API. Codes are
+    /// negative, and are never renumbered or reused.
+    pub fn code(&self) -> i32 {
+        match self {
+            Error::InvalidInputLength => -2,
+            Error::InvalidEta => -3,
+            Error::InvalidEnvelope => -4,
+            Error::DecryptionFailed => -5,
+            Error::ModulusCheckFailed => -6,
+            Error::HashCheckFailed => -7,
+            Error::InvalidBitWidth => -8,
+            Error::SelfTestFailed => -9,
+            Error::PairwiseConsistencyFailed => -10,
+            Error::NotApproved => -11,
+            Error::FaultDetected => -12,
+            Error::MemoryLockFailed => -13,
+            Error::InvalidCiphertextLength { .. } => -14,
+            Error::InvalidKeyLength { .. } => -15,
+            Error::ParameterSetMismatch { .. } => -16,
+            Error::RngFailure => -17,
+            Error::UnsupportedParameters => -18,
+            Error::EntropyHealthFailed => -19,
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::InvalidInputLength
+            | Error::InvalidEnvelope
+            | Error::DecryptionFailed
+            | Error::ModulusCheckFailed
+            | Error::HashCheckFailed
+            | Error::InvalidCiphertextLength { .. }
+            | Error::InvalidKeyLength { .. }
+            | Error::ParameterSetMismatch { .. } => ErrorKind::MalformedInput,
+            Error::InvalidEta
+            | Error::InvalidBitWidth
+            | Error::NotApproved
+            | Error::UnsupportedParameters => ErrorKind::Unsupported,
+            Error::SelfTestFailed | Error::PairwiseConsistencyFailed | Error::FaultDetected => {
+                ErrorKind::Internal
+            }
+            Error::MemoryLockFailed | Error::RngFailure | Error::EntropyHealthFailed => {
+                ErrorKind::Environment
+            }
+        }
+    }
 }
 
 impl fmt::Display for Error {
@@ -11,8 +126,94 @@ impl fmt::Display for Error {
         match self {
             Error::InvalidInputLength => write!(f, "Input length is invalid"),
             Error::InvalidEta => write!(f, "Invalid value for Eta"),
+            Error::InvalidBitWidth => write!(f, "Invalid value for d"),
+            Error::InvalidEnvelope => write!(f, "Envelope is invalid"),
+            Error::DecryptionFailed => write!(f, "Envelope authentication failed"),
+            Error::ModulusCheckFailed => write!(f, "Encapsulation key failed the modulus check"),
+            Error::HashCheckFailed => write!(f, "Decapsulation key failed the hash check"),
+            Error::SelfTestFailed => write!(f, "Self-tests failed, the module refuses service"),
+            Error::PairwiseConsistencyFailed => {
+                write!(f, "Key pair failed the pairwise consistency test")
+            }
+            Error::NotApproved => write!(f, "Service is not approved in FIPS mode"),
+            Error::FaultDetected => write!(f, "A fault was detected, the output was wiped"),
+            Error::MemoryLockFailed => write!(f, "Secret memory could not be locked"),
+            Error::InvalidCiphertextLength { expected, got } => write!(
+                f,
+                "Ciphertext length is invalid: expected {expected} bytes, got {got}"
+            ),
+            Error::InvalidKeyLength { expected, got } => {
+                write!(
+                    f,
+                    "Key length is invalid: expected {expected} bytes, got {got}"
+                )
+            }
+            Error::ParameterSetMismatch { expected, got } => write!(
+                f,
+                "Parameter set mismatch: expected {}, got {}",
+                expected.name(),
+                got.name()
+            ),
+            Error::RngFailure => write!(f, "The random number generator failed"),
+            Error::UnsupportedParameters => write!(f, "Parameters are not supported"),
+            Error::EntropyHealthFailed => write!(
+                f,
+                "Entropy source failed its health tests, the module refuses service"
+            ),
         }
     }
 }
 
 impl core::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_are_stable() {
+        let errors = [
+            Error::InvalidInputLength,
+            Error::InvalidEta,
+            Error::InvalidEnvelope,
+            Error::DecryptionFailed,
+            Error::ModulusCheckFailed,
+            Error::HashCheckFailed,
+            Error::InvalidBitWidth,
+            Error::SelfTestFailed,
+            Error::PairwiseConsistencyFailed,
+            Error::NotApproved,
+            Error::FaultDetected,
+            Error::MemoryLockFailed,
+            Error::InvalidCiphertextLength {
+                expected: 1088,
+                got: 1087,
+            },
+            Error::InvalidKeyLength {
+                expected: 1184,
+                got: 0,
+            },
+            Error::ParameterSetMismatch {
+                expected: ParameterSet::MlKem768,
+                got: ParameterSet::MlKem512,
+            },
+            Error::RngFailure,
+            Error::UnsupportedParameters,
+            Error::EntropyHealthFailed,
+        ];
+        for (error, code) in errors.iter().zip((-19..=-2).rev()) {
+            assert_eq!(error.code(), code, "{error}");
+        }
+
+        assert_eq!(
+            errors[12].to_string(),
+            "Ciphertext length is invalid: expected 1088 bytes, got 1087"
+        );
+        assert_eq!(
+            errors[14].to_string(),
+            "Parameter set mismatch: expected ML-KEM-768, got ML-KEM-512"
+        );
+        assert_eq!(errors[13].kind(), ErrorKind::MalformedInput);
+        assert_eq!(Error::FaultDetected.kind(), ErrorKind::Internal);
+    }
+}
@@ -0,0 +1,161 @@
+//! # Fault-injection countermeasures (feature `hardened`)
+//!
+//! A single skipped or corrupted instruction in decapsulation can turn implicit rejection
+//! into a decryption oracle: skipping the ciphertext comparison, or the `conditional_assign`
+//! loop, returns K' for a modified ciphertext. With the `hardened` feature,
+//! `MlKem::decaps_internal`:
+//! - checks the hash H(ek) of the decapsulation key before and after use;
+//! - compares the ciphertexts a second time, through their J (SHAKE256) digests under a fresh
+//!   random key ([`keyed_eq`]), and requires both comparisons to agree;
+//! - recomputes the selection between K' and K̄ under a fresh random mask
+//!   ([`masked_select`]) and requires both results to agree;
+//! - counts the steps it went through ([`FlowCounter`]).
+//!
+//! Any failed check wipes the intermediate secrets and returns [`Error::FaultDetected`]; if
+//! the key or the mask cannot be drawn from `OsRng`, decapsulation returns [`Error::RngFailure`].
+//!
+//! The re-encrypted ciphertext c' itself is computed once. Only the comparison and the
+//! selection are recomputed under fresh randomness: a fault during re-encryption yields a c'
+//! unrelated to c, hence an implicit rejection, and the fault would have to produce exactly
+//! the 768 to 1568 bytes of c to be accepted. The faults that turn a rejection into an
+//! acceptance hit the comparison of c and c' or the selection of K' over K̄, and both are
+//! computed twice, the second time from values randomized differently on every call.
+//! Key generation checks the new key pair against itself, then runs the pairwise consistency
+//! test (the feature implies `pct`).
+//!
+//! The checks only branch on values that are constant in the absence of a fault.
+
+use core::hint::black_box;
+
+use rand::rngs::OsRng;
+use rand::RngCore;
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+
+use crate::errors::Error;
+use crate::hash::J;
+
+/// Control-flow counter : each step of a computation increments it, and the total is checked
+/// once at the end.
+pub(crate) struct FlowCounter(u32);
+
+impl FlowCounter {
+    pub(crate) fn new() -> Self {
+        FlowCounter(0)
+    }
+
+    pub(crate) fn step(&mut self) {
+        self.0 = black_box(self.0 + 1);
+    }
+
+    /// Fails unless exactly `steps` steps ran.
+    pub(crate) fn check(&self, steps: u32) -> Result<(), Error> {
+        if black_box(self.0) == steps {
+            Ok(())
+        } else {
+            Err(Error::FaultDetected)
+        }
+    }
+}
+
+/// Fails unless two independently computed conditions agree.
+pub(crate) fn agree(a: Choice, b: Choice) -> Result<(), Error> {
+    if black_box((a ^ b).unwrap_u8()) == 0 {
+        Ok(())
+    } else {
+        Err(Error::FaultDetected)
+    }
+}
+
+/// Constant-time comparison of `a` and `b` independent of [`ConstantTimeEq`] : compares
+/// J(key || a) and J(key || b) under a fresh random key, so that no byte of `a` meets the
+/// matching byte of `b`, and the digests differ on every call.
+pub(crate) fn keyed_eq(a: &[u8], b: &[u8]) -> Result<Choice, Error> {
+    if a.len() != b.len() || a.is_empty() {
+        return Ok(Choice::from(0));
+    }
+
+    let mut key = [0u8; 32];
+    fresh_randomness(&mut key)?;
+    let digest = |data: &[u8]| {
+        let mut hasher = J::new();
+        hasher.absorb(&key);
+        hasher.absorb(data);
+        black_box(hasher.squeeze())
+    };
+    let (x, y) = (digest(a), digest(b));
+
+    let mut diff = 0u8;
+    for i in 0..32 {
+        diff |= x[i] ^ y[i];
+    }
+
+    // 1 when diff == 0
+    Ok(Choice::from(
+        (((diff as u16).wrapping_sub(1) >> 8) & 1) as u8,
+    ))
+}
+
+/// Recomputes `if reject { rejected } else { accepted }` under a fresh random mask.
+pub(crate) fn masked_select(
+    accepted: &[u8; 32],
+    rejected: &[u8; 32],
+    reject: Choice,
+) -> Result<[u8; 32], Error> {
+    let mut mask = [0u8; 32];
+    fresh_randomness(&mut mask)?;
+
+    let mut out = [0u8; 32];
+    for i in 0..32 {
+        let a = black_box(accepted[i] ^ mask[i]);
+        let r = black_box(rejected[i] ^ mask[i]);
+        out[i] = u8::conditional_select(&a, &r, reject) ^ mask[i];
+    }
+    Ok(out)
+}
+
+/// Keys and masks of the redundant computations, drawn from `OsRng` without panicking.
+fn fresh_randomness(dest: &mut [u8]) -> Result<(), Error> {
+    OsRng.try_fill_bytes(dest).map_err(|_| Error::RngFailure)
+}
+
+/// Fails unless `a` and `b` are equal.
+pub(crate) fn same(a: &[u8], b: &[u8]) -> Result<(), Error> {
+    if bool::from(black_box(a.ct_eq(b))) {
+        Ok(())
+    } else {
+        Err(Error::FaultDetected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn basics() -> Result<(), Error> {
+        let a = [7u8; 100];
+        let mut b = a;
+        assert!(bool::from(keyed_eq(&a, &b)?));
+        b[99] ^= 0x80;
+        assert!(!bool::from(keyed_eq(&a, &b)?));
+        assert!(!bool::from(keyed_eq(&a, &b[1..])?));
+
+        let k = [1u8; 32];
+        let k_bar = [2u8; 32];
+        assert_eq!(masked_select(&k, &k_bar, Choice::from(0))?, k);
+        assert_eq!(masked_select(&k, &k_bar, Choice::from(1))?, k_bar);
+
+        assert_eq!(agree(Choice::from(1), Choice::from(1)), Ok(()));
+        assert_eq!(
+            agree(Choice::from(0), Choice::from(1)),
+            Err(Error::FaultDetected)
+        );
+
+        let mut counter = FlowCounter::new();
+        counter.step();
+        counter.step();
+        assert_eq!(counter.check(2), Ok(()));
+        assert_eq!(counter.check(3), Err(Error::FaultDetected));
+        Ok(())
+    }
+}
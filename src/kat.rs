@@ -0,0 +1,302 @@
+//! # NIST PQC known-answer tests (feature `kat`)
+//!
+//! Reproduces the AES-256-CTR DRBG of the NIST `rng.c` (`randombytes_init` / `randombytes`)
+//! used by `PQCgenKAT_kem` on top of [`CtrDrbg`] (the feature implies `drbg`), and writes
+//! byte-exact `PQCkemKAT_<sk bytes>.req` / `.rsp` files through [`KemScheme::key_gen`] and
+//! [`KemScheme::encaps`].
+//!
+//! As in the reference `crypto_kem_keypair`, the 64 bytes d || z are drawn with a single
+//! `randombytes` call, which [`KatRng`] replays to the two 32-byte requests of `key_gen`.
+
+use std::io::{self, BufRead, Write};
+
+use rand::{CryptoRng, RngCore};
+use zeroize::Zeroizing;
+
+use crate::constants::KyberParams;
+use crate::drbg::CtrDrbg;
+use crate::kem_scheme::{KemDecapsKey, MlKem};
+use crate::params::{ParameterSet, SecurityLevel};
+use crate::traits::KemScheme;
+
+/// Number of test cases written by `PQCgenKAT_kem`.
+pub const KAT_COUNT: usize = 100;
+
+/// AES-256-CTR DRBG of the NIST `rng.c` : the [`CtrDrbg`] without derivation function,
+/// instantiated from the 48-byte seed and never reseeded. The state is wiped on drop.
+pub struct KatDrbg(CtrDrbg<KatSeed>);
+
+impl KatDrbg {
+    /// `randombytes_init(entropy_input, personalization_string, 256)`
+    pub fn new(entropy_input: &[u8; 48], personalization_string: Option<&[u8; 48]>) -> Self {
+        let personalization_string = personalization_string.map_or(&[][..], |p| &p[..]);
+        let drbg = CtrDrbg::instantiate_no_df(
+            KatSeed(Some(Zeroizing::new(*entropy_input))),
+            personalization_string,
+            false,
+        )
+        .expect("48-byte entropy input and personalization string");
+        KatDrbg(drbg)
+    }
+
+    /// `randombytes(x, xlen)` : fills `x`, then updates the state. Unlike `rng.c`, requests
+    /// longer than 2^16 bytes are split.
+    pub fn randombytes(&mut self, x: &mut [u8]) {
+        self.0.fill_bytes(x);
+    }
+}
+
+impl RngCore for KatDrbg {
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.randombytes(dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.0.try_fill_bytes(dest)
+    }
+}
+
+impl CryptoRng for KatDrbg {}
+
+/// Entropy source of [`KatDrbg`] : hands out the seed of `randombytes_init` once, the DRBG is
+/// never reseeded.
+struct KatSeed(Option<Zeroizing<[u8; 48]>>);
+
+impl RngCore for KatSeed {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    /// Panics after the seed was handed out.
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        if let Err(e) = self.try_fill_bytes(dest) {
+            panic!("{e}");
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        let seed = self
+            .0
+            .take()
+            .ok_or_else(|| rand::Error::new("the KAT DRBG is never reseeded"))?;
+        dest.copy_from_slice(&seed[..]);
+        Ok(())
+    }
+}
+
+/// Draws `len` bytes with a single `randombytes` call and hands them out to successive
+/// `fill_bytes` requests, reproducing the call pattern of the reference implementation.
+pub struct KatRng {
+    bytes: Zeroizing<Vec<u8>>,
+    position: usize,
+}
+
+impl KatRng {
+    pub fn new(drbg: &mut KatDrbg, len: usize) -> Self {
+        let mut bytes = Zeroizing::new(vec![0u8; len]);
+        drbg.randombytes(&mut bytes);
+        Self { bytes, position: 0 }
+    }
+}
+
+impl RngCore for KatRng {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    /// Panics if more bytes are requested than were drawn.
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        if let Err(e) = self.try_fill_bytes(dest) {
+            panic!("{e}");
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        let end = self.position + dest.len();
+        let bytes = self
+            .bytes
+            .get(self.position..end)
+            .ok_or_else(|| rand::Error::new("more bytes requested than drawn by randombytes"))?;
+        dest.copy_from_slice(bytes);
+        self.position = end;
+        Ok(())
+    }
+}
+
+impl CryptoRng for KatRng {}
+
+/// Seeds of the `PQCgenKAT_kem` test cases : `randombytes_init` with the entropy input
+/// 0, 1, ..., 47, then one 48-byte `randombytes` call per test case.
+pub fn kat_seeds(count: usize) -> Vec<[u8; 48]> {
+    let mut entropy_input = [0u8; 48];
+    for (i, byte) in entropy_input.iter_mut().enumerate() {
+        *byte = i as u8;
+    }
+    let mut drbg = KatDrbg::new(&entropy_input, None);
+
+    (0..count)
+        .map(|_| {
+            let mut seed = [0u8; 48];
+            drbg.randombytes(&mut seed);
+            seed
+        })
+        .collect()
+}
+
+/// `fprintBstr` : uppercase hex, or "00" for an empty value.
+fn write_value<W: Write>(out: &mut W, name: &str, value: &[u8]) -> io::Result<()> {
+    if value.is_empty() {
+        writeln!(out, "{} = 00", name)
+    } else {
+        writeln!(out, "{} = {}", name, hex::encode_upper(value))
+    }
+}
+
+/// Writes the `.req` file of the given seeds.
+pub fn write_req<W: Write>(out: &mut W, seeds: &[[u8; 48]]) -> io::Result<()> {
+    for (count, seed) in seeds.iter().enumerate() {
+        writeln!(out, "count = {}", count)?;
+        write_value(out, "seed", seed)?;
+        writeln!(out, "pk =")?;
+        writeln!(out, "sk =")?;
+        writeln!(out, "ct =")?;
+        writeln!(out, "ss =")?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// Reads the seeds of a `.req` (or `.rsp`) file.
+pub fn read_seeds<R: BufRead>(input: R) -> io::Result<Vec<[u8; 48]>> {
+    let invalid = |line: &str| io::Error::new(io::ErrorKind::InvalidData, line.to_string());
+    let mut seeds = Vec::new();
+    for line in input.lines() {
+        let line = line?;
+        if let Some(value) = line.trim().strip_prefix("seed = ") {
+            let seed = hex::decode(value).map_err(|_| invalid(&line))?;
+            seeds.push(seed.try_into().map_err(|_| invalid(&line))?);
+        }
+    }
+    Ok(seeds)
+}
+
+/// Writes the `.rsp` file of the given seeds : for each seed, `randombytes_init(seed)` then
+/// `crypto_kem_keypair`, `crypto_kem_enc` and `crypto_kem_dec`, which must agree.
+pub fn write_rsp<const K: usize, S: SecurityLevel, W: Write>(
+    out: &mut W,
+    seeds: &[[u8; 48]],
+) -> io::Result<()> {
+    let kem = MlKem::<K, S, KyberParams>::new();
+    let failure = |count: usize| io::Error::other(format!("count = {} : KEM failure", count));
+    let name = ParameterSet::from_params::<K, S>().map_or("ML-KEM", |set| set.name());
+
+    writeln!(out, "# {}", name)?;
+    writeln!(out)?;
+    for (count, seed) in seeds.iter().enumerate() {
+        let mut drbg = KatDrbg::new(seed, None);
+        let (ek, dk) = kem
+            .key_gen(&mut KatRng::new(&mut drbg, 64))
+            .map_err(|_| failure(count))?;
+        let (ss, ct) = kem
+            .encaps(&ek, &mut KatRng::new(&mut drbg, 32))
+            .map_err(|_| failure(count))?;
+        let ss_prime = kem.decaps(&dk, &ct).map_err(|_| failure(count))?;
+        if ss.0 != ss_prime.0 {
+            return Err(failure(count));
+        }
+
+        writeln!(out, "count = {}", count)?;
+        write_value(out, "seed", seed)?;
+        write_value(out, "pk", &ek.to_bytes())?;
+        write_value(out, "sk", &dk.to_bytes())?;
+        write_value(out, "ct", &ct)?;
+        write_value(out, "ss", &ss.0)?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// `PQCkemKAT_<CRYPTO_SECRETKEYBYTES>`, the base name of the KAT files.
+pub fn kat_file_name<const K: usize>() -> String {
+    format!("PQCkemKAT_{}", KemDecapsKey::<K>::len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::params::Kyber768Params;
+    use sha2::{Digest, Sha256};
+
+    #[test]
+    fn basics() -> io::Result<()> {
+        let seeds = kat_seeds(2);
+        assert_eq!(
+            hex::encode_upper(seeds[0]),
+            "061550234D158C5EC95595FE04EF7A25767F2E24CC2BC479D09D86DC9ABCFDE7056A8C266F9EF97ED08541DBD2E1FFA1"
+        );
+
+        let mut req = Vec::new();
+        write_req(&mut req, &seeds)?;
+        assert_eq!(read_seeds(&req[..])?, seeds);
+
+        let mut rsp = Vec::new();
+        write_rsp::<3, Kyber768Params, _>(&mut rsp, &seeds)?;
+        let rsp = String::from_utf8(rsp).unwrap();
+        assert!(rsp.starts_with("# ML-KEM-768\n\ncount = 0\nseed = 061550234D"));
+        assert_eq!(read_seeds(rsp.as_bytes())?, seeds);
+        // Start of the first public key of the published ML-KEM-768 KAT
+        assert!(
+            rsp.contains("\npk = A8E651A1E685F22478A8954F007BC7711B930772C78F092E82878E3E937F3679")
+        );
+        assert_eq!(kat_file_name::<3>(), "PQCkemKAT_2400");
+
+        let mut rng = KatRng::new(&mut KatDrbg::new(&seeds[0], None), 32);
+        let mut bytes = [0u8; 32];
+        assert!(rng.try_fill_bytes(&mut bytes).is_ok());
+        assert!(rng.try_fill_bytes(&mut bytes[..1]).is_err());
+
+        Ok(())
+    }
+
+    /// The 100 test cases of `PQCkemKAT_2400.rsp`, hashed as liboqs publishes them in
+    /// `tests/KATs/kem/kats.json` : SHA-256 of the records, without the `# ML-KEM-768` header
+    /// and the final newline.
+    #[test]
+    fn published_kat() -> io::Result<()> {
+        let mut rsp = Vec::new();
+        write_rsp::<3, Kyber768Params, _>(&mut rsp, &kat_seeds(KAT_COUNT))?;
+        let header = b"# ML-KEM-768\n\n";
+        assert!(rsp.starts_with(header) && rsp.ends_with(b"\n\n"));
+
+        let records = &rsp[header.len()..rsp.len() - 1];
+        assert_eq!(
+            hex::encode(Sha256::digest(records)),
+            "e5d6911117c708d8342702db222f66bef5ac5b0314fb8bc36eb22a536c9b34fb"
+        );
+        Ok(())
+    }
+}
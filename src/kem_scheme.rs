@@ -1,11 +1,16 @@
+use core::fmt;
+
 use rand::{CryptoRng, RngCore};
-use subtle::{ConditionallySelectable, ConstantTimeEq};
-use zeroize::{Zeroize, ZeroizeOnDrop};
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
+use crate::conversion::{byte_decode, byte_encode};
 use crate::errors::Error;
 use crate::hash::{G, H, J};
-use crate::params::SecurityLevel;
+use crate::params::{ParameterSet, SecurityLevel};
 use crate::pke_scheme::{PkeDecryptKey, PkeEncryptKey};
+#[cfg(feature = "secure-memory")]
+use crate::secure_memory::Locked;
 use crate::traits::KemScheme;
 use crate::{constants::PolyParams, pke_scheme::KPke, traits::PkeScheme};
 
@@ -17,31 +22,431 @@ impl<const K: usize, S: SecurityLevel, P: PolyParams> MlKem<K, S, P> {
     }
 }
 
+impl<const K: usize, S: SecurityLevel, P: PolyParams> MlKem<K, S, P> {
+    /// Length of a ciphertext c in B^(32 * (d_u*k + d_v))
+    pub fn ciphertext_len() -> usize {
+        32 * (S::DU * K + S::DV)
+    }
+
+    /// Encapsulation key check (FIPS 203, 7.2)
+    /// Modulus check : ByteEncode_12(ByteDecode_12(ek[0 : 384k])) == ek[0 : 384k]
+    /// (the type check is enforced by [`KemEncapsKey::from_bytes`])
+    pub fn check_encaps_key(ek: &KemEncapsKey<K>) -> Result<(), Error> {
+        let mut coeffs = [0i16; 256];
+        for chunk in &ek.0 {
+            let mut encoded = [0u8; 384];
+            byte_decode(chunk, 12, P::Q, &mut coeffs)?;
+            byte_encode(&coeffs, 12, &mut encoded)?;
+            if encoded != *chunk {
+                return Err(Error::ModulusCheckFailed);
+            }
+        }
+        Ok(())
+    }
+
+    /// Decapsulation key check (FIPS 203, 7.3)
+    /// Hash check : H(dk[384k : 768k + 32]) == dk[768k + 32 : 768k + 64]
+    /// (the type check is enforced by [`KemDecapsKey::from_bytes`])
+    pub fn check_decaps_key(dk: &KemDecapsKey<K>) -> Result<(), Error> {
+        let h_res = {
+            let mut h_hasher = H::default();
+            for slice in &dk.1 {
+                h_hasher.absorb(slice);
+            }
+            h_hasher.absorb(&dk.2[..32]);
+            h_hasher.squeeze()
+        };
+
+        if bool::from(h_res.ct_eq(&dk.2[32..64])) {
+            Ok(())
+        } else {
+            Err(Error::HashCheckFailed)
+        }
+    }
+
+    /// Consistency of a freshly generated key pair (feature `hardened`) : the copy of ek in dk
+    /// must match ek, and both keys must pass their input checks.
+    /// On failure, both keys are wiped and [`Error::FaultDetected`] is returned.
+    #[cfg(feature = "hardened")]
+    fn check_key_pair(
+        (mut ek, mut dk): (KemEncapsKey<K>, KemDecapsKey<K>),
+    ) -> Result<(KemEncapsKey<K>, KemDecapsKey<K>), Error> {
+        let consistent = crate::hardening::same(ek.0.as_flattened(), dk.1.as_flattened())
+            .and_then(|()| crate::hardening::same(&ek.1, &dk.2[..32]))
+            .and_then(|()| Self::check_encaps_key(&ek))
+            .and_then(|()| Self::check_decaps_key(&dk));
+
+        match consistent {
+            Ok(()) => Ok((ek, dk)),
+            Err(_) => {
+                ek.0.zeroize();
+                ek.1.zeroize();
+                dk.zeroize();
+                Err(Error::FaultDetected)
+            }
+        }
+    }
+
+    /// Pairwise consistency test (FIPS 140-3 IG 10.3.A) of a freshly generated key pair :
+    /// Decaps(dk, c) must recover the shared secret K of (K, c) = Encaps(ek).
+    /// On failure, both keys are wiped and [`Error::PairwiseConsistencyFailed`] is returned.
+    ///
+    /// The message m is a public constant : K and c are discarded and only the comparison
+    /// matters, and key generation draws no more randomness than d and z. Decapsulation goes
+    /// through [`Self::decaps_checked`], so a fresh dk is never used on the unmasked path when
+    /// `masked` is enabled.
+    #[cfg(feature = "pct")]
+    fn pairwise_consistency_test(
+        &self,
+        (mut ek, mut dk): (KemEncapsKey<K>, KemDecapsKey<K>),
+    ) -> Result<(KemEncapsKey<K>, KemDecapsKey<K>), Error> {
+        #[allow(unused_mut)]
+        let (k, mut c) = self.encaps_internal(&ek, &[0u8; 32])?;
+        #[cfg(feature = "pct-fault-injection")]
+        if PCT_FAULT.with(|fault| fault.replace(false)) {
+            c[0] ^= 1;
+        }
+        let k_prime = self.decaps_checked(&dk, &c)?;
+
+        if bool::from(k.0.ct_eq(&k_prime.0)) {
+            Ok((ek, dk))
+        } else {
+            ek.0.zeroize();
+            ek.1.zeroize();
+            dk.zeroize();
+            Err(Error::PairwiseConsistencyFailed)
+        }
+    }
+
+    /// ML-KEM.KeyGen() with randomness `d || z` taken from the caller, for platforms whose
+    /// entropy comes from an external TRNG or an HSM rather than an [`RngCore`].
+    ///
+    /// Unlike `key_gen_internal`, which is meant for testing, this is the full key generation
+    /// service: with the `fips` feature it runs the self-tests, refuses unapproved parameters
+    /// and is approved, and the `hardened` and `pct` checks apply. The 64 bytes must come from
+    /// an approved random bit generator, be used once and then wiped by the caller.
+    pub fn key_gen_from_entropy(
+        &self,
+        entropy: &[u8; 64],
+    ) -> Result<(KemEncapsKey<K>, KemDecapsKey<K>), Error> {
+        #[cfg(feature = "fips")]
+        crate::service_indicator::enter::<K, S, P>()?;
+        crate::entropy_health::ensure_healthy()?;
+
+        self.key_gen_checked(entropy)
+    }
+
+    /// ML-KEM.Encaps(ek) with the randomness m taken from the caller, for platforms whose
+    /// entropy comes from an external TRNG or an HSM rather than an [`RngCore`].
+    ///
+    /// Unlike `encaps_internal`, which is meant for testing, this is the full encapsulation
+    /// service: it checks ek (FIPS 203, 7.2) and, with the `fips` feature, runs the self-tests,
+    /// refuses unapproved parameters and is approved. m must come from an approved random bit
+    /// generator, be used once and then wiped by the caller.
+    pub fn encaps_from_entropy(
+        &self,
+        ek: &KemEncapsKey<K>,
+        entropy: &[u8; 32],
+    ) -> Result<(KemSharedSecret, Vec<u8>), Error> {
+        #[cfg(feature = "fips")]
+        crate::service_indicator::enter::<K, S, P>()?;
+        crate::entropy_health::ensure_healthy()?;
+
+        Self::check_encaps_key(ek)?;
+        let result = self.encaps_internal(ek, entropy)?;
+        #[cfg(feature = "fips")]
+        crate::service_indicator::approved();
+        Ok(result)
+    }
+
+    /// Key generation from `d || z`, with the checks of the enabled features.
+    fn key_gen_checked(
+        &self,
+        entropy: &[u8; 64],
+    ) -> Result<(KemEncapsKey<K>, KemDecapsKey<K>), Error> {
+        let (d, z) = entropy.split_at(32);
+        let keys = self.key_gen_internal(d.try_into().unwrap(), z.try_into().unwrap())?;
+        #[cfg(feature = "hardened")]
+        let keys = Self::check_key_pair(keys)?;
+        #[cfg(feature = "pct")]
+        let keys = self.pairwise_consistency_test(keys)?;
+        #[cfg(feature = "fips")]
+        crate::service_indicator::approved();
+        Ok(keys)
+    }
+
+    /// Decapsulation with the countermeasures of the enabled features : masked with `masked`,
+    /// else `decaps_internal`, hardened with `hardened`. The self-tests and the pairwise
+    /// consistency test run the same path.
+    pub(crate) fn decaps_checked(
+        &self,
+        dk: &KemDecapsKey<K>,
+        c: &[u8],
+    ) -> Result<KemSharedSecret, Error> {
+        #[cfg(not(feature = "masked"))]
+        let k = self.decaps_internal(dk, c)?;
+        #[cfg(feature = "masked")]
+        let k = KemSharedSecret(crate::masked::decaps::<K, S, P>(dk, c)?);
+        Ok(k)
+    }
+
+    /// ML-KEM.KeyGen(), with the decapsulation key moved into locked, guarded memory.
+    #[cfg(feature = "secure-memory")]
+    pub fn key_gen_locked<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+    ) -> Result<(KemEncapsKey<K>, Locked<KemDecapsKey<K>>), Error> {
+        let (ek, dk) = self.key_gen(rng)?;
+        Ok((ek, Locked::new(dk)?))
+    }
+
+    /// ML-KEM.Decaps(dk, c) on a locked decapsulation key, with the shared secret moved into
+    /// locked, guarded memory.
+    #[cfg(feature = "secure-memory")]
+    pub fn decaps_locked(
+        &self,
+        dk: &Locked<KemDecapsKey<K>>,
+        c: &[u8],
+    ) -> Result<Locked<KemSharedSecret>, Error> {
+        let k = dk.with(|dk| self.decaps(dk, c))?;
+        Locked::new(k)
+    }
+}
+
 impl<const K: usize, S: SecurityLevel, P: PolyParams> Default for MlKem<K, S, P> {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// Decapsulation key dk = dk_PKE || ek || H(ek) || z
+///
+/// `Debug` is redacted, `==` is constant time, and copies are made with [`Self::clone_secret`].
 #[derive(Zeroize, ZeroizeOnDrop)]
-pub struct KemDecapsKey<const K: usize>(pub [[u8; 384]; K], pub [[u8; 384]; K], pub [u8; 96]);
+pub struct KemDecapsKey<const K: usize>(
+    pub(crate) [[u8; 384]; K],
+    pub(crate) [[u8; 384]; K],
+    pub(crate) [u8; 96],
+);
 
 impl<const K: usize> KemDecapsKey<K> {
     pub fn len() -> usize {
         768 * K + 96
     }
+
+    /// dk_PKE = ByteEncode_12(ŝ)
+    pub fn dk_pke(&self) -> &[[u8; 384]; K] {
+        &self.0
+    }
+
+    /// t̂ part of the encapsulation key ek = ByteEncode_12(t̂) || ρ
+    pub fn t_hat(&self) -> &[[u8; 384]; K] {
+        &self.1
+    }
+
+    /// ρ part of the encapsulation key
+    pub fn rho(&self) -> &[u8; 32] {
+        self.2[..32].try_into().unwrap()
+    }
+
+    /// H(ek)
+    pub fn h(&self) -> &[u8; 32] {
+        self.2[32..64].try_into().unwrap()
+    }
+
+    /// Implicit rejection value z
+    pub fn z(&self) -> &[u8; 32] {
+        self.2[64..].try_into().unwrap()
+    }
+
+    /// Encapsulation key ek carried by dk
+    pub fn encaps_key(&self) -> KemEncapsKey<K> {
+        KemEncapsKey(self.1, *self.rho())
+    }
+
+    /// Copy of the key, wiped on drop like the original.
+    pub fn clone_secret(&self) -> Self {
+        KemDecapsKey(self.0, self.1, self.2)
+    }
+
+    /// Serializes the decapsulation key as dk in B^(768*k + 96), wiped on drop
+    pub fn to_bytes(&self) -> Zeroizing<Vec<u8>> {
+        let mut out = Zeroizing::new(Vec::with_capacity(Self::len()));
+        for chunk in &self.0 {
+            out.extend_from_slice(chunk);
+        }
+        for chunk in &self.1 {
+            out.extend_from_slice(chunk);
+        }
+        out.extend_from_slice(&self.2);
+        out
+    }
+
+    /// Parses a decapsulation key from dk in B^(768*k + 96)
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != Self::len() {
+            return Err(key_length_error::<K>(|k| 768 * k + 96, bytes.len()));
+        }
+
+        let mut dk = KemDecapsKey::<K>([[0u8; 384]; K], [[0u8; 384]; K], [0u8; 96]);
+        let (dk_pke, rest) = bytes.split_at(384 * K);
+        let (ek_pke, tail) = rest.split_at(384 * K);
+        for (dst, src) in dk.0.iter_mut().zip(dk_pke.chunks_exact(384)) {
+            dst.copy_from_slice(src);
+        }
+        for (dst, src) in dk.1.iter_mut().zip(ek_pke.chunks_exact(384)) {
+            dst.copy_from_slice(src);
+        }
+        dk.2.copy_from_slice(tail);
+        Ok(dk)
+    }
 }
 
-pub struct KemEncapsKey<const K: usize>(pub [[u8; 384]; K], pub [u8; 32]);
+/// Encapsulation key ek = ByteEncode_12(t̂) || ρ
+///
+/// `Debug` shows the first 8 bytes of H(ek).
+#[derive(Clone, PartialEq, Eq)]
+pub struct KemEncapsKey<const K: usize>(pub(crate) [[u8; 384]; K], pub(crate) [u8; 32]);
 
 impl<const K: usize> KemEncapsKey<K> {
     pub fn len() -> usize {
         384 * K + 32
     }
+
+    /// ByteEncode_12(t̂)
+    pub fn t_hat(&self) -> &[[u8; 384]; K] {
+        &self.0
+    }
+
+    /// Seed ρ of the matrix Â
+    pub fn rho(&self) -> &[u8; 32] {
+        &self.1
+    }
+
+    /// H(ek), as stored in the decapsulation key
+    pub fn hash(&self) -> [u8; 32] {
+        let mut hasher = H::new();
+        for chunk in &self.0 {
+            hasher.absorb(chunk);
+        }
+        hasher.absorb(&self.1);
+        hasher.squeeze()
+    }
+
+    /// Serializes the encapsulation key as ek in B^(384*k + 32)
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(Self::len());
+        for chunk in &self.0 {
+            out.extend_from_slice(chunk);
+        }
+        out.extend_from_slice(&self.1);
+        out
+    }
+
+    /// Parses an encapsulation key from ek in B^(384*k + 32)
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != Self::len() {
+            return Err(key_length_error::<K>(|k| 384 * k + 32, bytes.len()));
+        }
+
+        let mut ek = KemEncapsKey::<K>([[0u8; 384]; K], [0u8; 32]);
+        let (t, rho) = bytes.split_at(384 * K);
+        for (dst, src) in ek.0.iter_mut().zip(t.chunks_exact(384)) {
+            dst.copy_from_slice(src);
+        }
+        ek.1.copy_from_slice(rho);
+        Ok(ek)
+    }
 }
 
+/// Error for a serialized key of `got` bytes, keys of rank k having `len(k)` bytes : a key of
+/// another parameter set of FIPS 203 is reported as such.
+fn key_length_error<const K: usize>(len: fn(usize) -> usize, got: usize) -> Error {
+    let other = (2..=4).find(|&k| len(k) == got);
+    match (
+        ParameterSet::from_k(K),
+        other.and_then(ParameterSet::from_k),
+    ) {
+        (Some(expected), Some(got)) => Error::ParameterSetMismatch { expected, got },
+        _ => Error::InvalidKeyLength {
+            expected: len(K),
+            got,
+        },
+    }
+}
+
+/// Shared secret key K
+///
+/// `Debug` is redacted, `==` is constant time, and copies are made with [`Self::clone_secret`].
 #[derive(Zeroize, ZeroizeOnDrop)]
-pub struct KemSharedSecret(pub [u8; 32]);
+pub struct KemSharedSecret(pub(crate) [u8; 32]);
+
+impl KemSharedSecret {
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// Parses a shared secret from K in B^32
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let k: [u8; 32] = bytes.try_into().map_err(|_| Error::InvalidInputLength)?;
+        Ok(KemSharedSecret(k))
+    }
+
+    /// Copy of the secret, wiped on drop like the original.
+    pub fn clone_secret(&self) -> Self {
+        KemSharedSecret(self.0)
+    }
+}
+
+impl<const K: usize> ConstantTimeEq for KemDecapsKey<K> {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.as_flattened().ct_eq(other.0.as_flattened())
+            & self.1.as_flattened().ct_eq(other.1.as_flattened())
+            & self.2.ct_eq(&other.2)
+    }
+}
+
+impl<const K: usize> PartialEq for KemDecapsKey<K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl<const K: usize> Eq for KemDecapsKey<K> {}
+
+impl<const K: usize> fmt::Debug for KemDecapsKey<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("KemDecapsKey(<redacted>)")
+    }
+}
+
+impl<const K: usize> fmt::Debug for KemEncapsKey<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KemEncapsKey")
+            .field("fingerprint", &hex::encode(&self.hash()[..8]))
+            .finish()
+    }
+}
+
+impl ConstantTimeEq for KemSharedSecret {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+impl PartialEq for KemSharedSecret {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for KemSharedSecret {}
+
+impl fmt::Debug for KemSharedSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("KemSharedSecret(<redacted>)")
+    }
+}
 
 impl<const K: usize, S: SecurityLevel, P: PolyParams> KemScheme for MlKem<K, S, P> {
     type DecapsKey = KemDecapsKey<K>;
@@ -60,7 +465,11 @@ impl<const K: usize, S: SecurityLevel, P: PolyParams> KemScheme for MlKem<K, S,
         d: &[u8; 32],
         z: &[u8; 32],
     ) -> Result<(Self::EncapsKey, Self::DecapsKey), Error> {
+        #[cfg(feature = "fips")]
+        crate::service_indicator::not_approved();
+
         let (pke_encrypt_key, pke_decrypt_key) = self.0.key_gen(d)?;
+        declassify!(pke_encrypt_key.0.as_flattened());
         let mut decaps_2_content = [0u8; 96];
         decaps_2_content[..32].copy_from_slice(&pke_encrypt_key.1);
 
@@ -94,6 +503,9 @@ impl<const K: usize, S: SecurityLevel, P: PolyParams> KemScheme for MlKem<K, S,
         ek: &Self::EncapsKey,
         m: &[u8; 32],
     ) -> Result<(Self::SharedSecret, Vec<u8>), Error> {
+        #[cfg(feature = "fips")]
+        crate::service_indicator::not_approved();
+
         let (k, r) = {
             let mut g_hasher = G::default();
             g_hasher.absorb(m);
@@ -112,6 +524,7 @@ impl<const K: usize, S: SecurityLevel, P: PolyParams> KemScheme for MlKem<K, S,
         };
 
         let c = self.0.encrypt(&PkeEncryptKey::<K>(ek.0, ek.1), m, &r)?;
+        declassify!(&c);
 
         Ok((KemSharedSecret(k), c))
     }
@@ -123,6 +536,18 @@ impl<const K: usize, S: SecurityLevel, P: PolyParams> KemScheme for MlKem<K, S,
     /// Input : ciphertext c in B^(32 * (d_u*k + d_v))
     /// Output : shared secret key K in B^32
     fn decaps_internal(&self, dk: &Self::DecapsKey, c: &[u8]) -> Result<Self::SharedSecret, Error> {
+        #[cfg(feature = "fips")]
+        crate::service_indicator::not_approved();
+
+        declassify!(dk.1.as_flattened(), &dk.2[..64]);
+        #[cfg(feature = "hardened")]
+        let mut flow = crate::hardening::FlowCounter::new();
+        #[cfg(feature = "hardened")]
+        {
+            Self::check_decaps_key(dk).map_err(|_| Error::FaultDetected)?;
+            flow.step();
+        }
+
         let dk_pke = PkeDecryptKey(dk.0);
 
         let mut ek_pke_1 = [0u8; 32];
@@ -130,6 +555,8 @@ impl<const K: usize, S: SecurityLevel, P: PolyParams> KemScheme for MlKem<K, S,
         let ek_pke = PkeEncryptKey(dk.1, ek_pke_1);
 
         let m_prime = self.0.decrypt(&dk_pke, c)?;
+        #[cfg(feature = "hardened")]
+        flow.step();
 
         let (mut k_prime, r_prime) = {
             let mut g_hasher = G::default();
@@ -146,13 +573,39 @@ impl<const K: usize, S: SecurityLevel, P: PolyParams> KemScheme for MlKem<K, S,
         };
 
         let c_prime = self.0.encrypt(&ek_pke, &m_prime, &r_prime)?;
+        #[cfg(feature = "hardened")]
+        flow.step();
 
         let cond = !(c.ct_eq(&c_prime));
 
+        #[cfg(feature = "hardened")]
+        let mut accepted = k_prime;
         for (dst, src) in k_prime.iter_mut().zip(k_bar.iter()) {
             dst.conditional_assign(src, cond);
         }
 
+        #[cfg(feature = "hardened")]
+        {
+            use crate::hardening::{agree, keyed_eq, masked_select, same};
+
+            flow.step();
+            let mut checks = || -> Result<(), Error> {
+                agree(cond, !keyed_eq(c, &c_prime)?)?;
+                flow.step();
+                same(&masked_select(&accepted, &k_bar, cond)?, &k_prime)?;
+                flow.step();
+                Self::check_decaps_key(dk).map_err(|_| Error::FaultDetected)?;
+                flow.step();
+                flow.check(7)
+            };
+            let result = checks();
+            accepted.zeroize();
+            if let Err(e) = result {
+                k_prime.zeroize();
+                return Err(e);
+            }
+        }
+
         Ok(KemSharedSecret(k_prime))
     }
 
@@ -161,50 +614,110 @@ impl<const K: usize, S: SecurityLevel, P: PolyParams> KemScheme for MlKem<K, S,
     ///
     /// Output : encapsulation key ek in B^(384*k + 32)
     /// Output : decapsulation key dk in B^(768*k + 96)
+    ///
+    /// d and z are drawn with `try_fill_bytes` : a failure of `rng` returns
+    /// [`Error::RngFailure`]. See [`MlKem::key_gen_from_entropy`] for entropy from another source.
     fn key_gen<R: RngCore + CryptoRng>(
         &self,
         rng: &mut R,
     ) -> Result<(Self::EncapsKey, Self::DecapsKey), Error> {
-        let mut d = [0u8; 32];
-        rng.fill_bytes(&mut d);
-
-        let mut z = [0u8; 32];
-        rng.fill_bytes(&mut z);
-
-        self.key_gen_internal(&d, &z)
+        #[cfg(feature = "fips")]
+        crate::service_indicator::enter::<K, S, P>()?;
+        crate::entropy_health::ensure_healthy()?;
+
+        // d, then z
+        let mut entropy = [0u8; 64];
+        let result = rng
+            .try_fill_bytes(&mut entropy[..32])
+            .and_then(|()| rng.try_fill_bytes(&mut entropy[32..]))
+            .map_err(crate::entropy_health::rng_error)
+            .and_then(|()| self.key_gen_checked(&entropy));
+        entropy.zeroize();
+        result
     }
 
     /// Algorithm 20 (FIPS 203) : ML-KEM.Encaps(ek)
     /// Uses the encapsulation key to generate a shared secret key and an associated ciphertext
+    /// Performs the input check of FIPS 203 (7.2) first.
     ///
     /// Input : encapsulation key ek in B^(384*k + 32)
     /// Output : shared secret key K in B^32
     /// Output : ciphertext c in B^(32 * (d_u*k + d_v))
+    ///
+    /// m is drawn with `try_fill_bytes` : a failure of `rng` returns [`Error::RngFailure`].
+    /// See [`MlKem::encaps_from_entropy`] for entropy from another source.
     fn encaps<R: RngCore + CryptoRng>(
         &self,
         ek: &Self::EncapsKey,
         rng: &mut R,
     ) -> Result<(Self::SharedSecret, Vec<u8>), Error> {
-        let mut m = [0u8; 32];
-        rng.fill_bytes(&mut m);
+        #[cfg(feature = "fips")]
+        crate::service_indicator::enter::<K, S, P>()?;
+        crate::entropy_health::ensure_healthy()?;
+
+        Self::check_encaps_key(ek)?;
 
-        self.encaps_internal(ek, &m)
+        let mut m = [0u8; 32];
+        let result = rng
+            .try_fill_bytes(&mut m)
+            .map_err(crate::entropy_health::rng_error)
+            .and_then(|()| self.encaps_internal(ek, &m));
+        m.zeroize();
+        let result = result?;
+        #[cfg(feature = "fips")]
+        crate::service_indicator::approved();
+        Ok(result)
     }
 
     /// Algorithm 21 (FIPS 203) : ML-KEM.Decaps(dk, c)
     /// Uses the decapsulation key to produce a shared secret key from a ciphertext.
+    /// Performs the input checks of FIPS 203 (7.3) first.
     ///
     /// Input : decapsulation key dk in B^(768*k + 96)
     /// Input : ciphertext c in B^(32 * (d_u*k + d_v))
     /// Output : shared secret key K in B^32
     fn decaps(&self, dk: &Self::DecapsKey, c: &[u8]) -> Result<Self::SharedSecret, Error> {
-        self.decaps_internal(dk, c)
+        #[cfg(feature = "fips")]
+        crate::service_indicator::enter::<K, S, P>()?;
+        crate::entropy_health::ensure_healthy()?;
+
+        if c.len() != Self::ciphertext_len() {
+            return Err(Error::InvalidCiphertextLength {
+                expected: Self::ciphertext_len(),
+                got: c.len(),
+            });
+        }
+        declassify!(dk.1.as_flattened(), &dk.2[..64]);
+        Self::check_decaps_key(dk)?;
+
+        let k = self.decaps_checked(dk, c)?;
+        #[cfg(feature = "fips")]
+        crate::service_indicator::approved();
+        Ok(k)
     }
 }
 
+#[cfg(feature = "pct-fault-injection")]
+std::thread_local! {
+    static PCT_FAULT: core::cell::Cell<bool> = const { core::cell::Cell::new(false) };
+}
+
+/// Test hook (feature `pct-fault-injection`) : corrupts the ciphertext of the next pairwise
+/// consistency test on the calling thread, as a fault during key generation would. The next
+/// `key_gen` (or `key_gen_from_entropy`) on this thread then wipes its keys and returns
+/// [`Error::PairwiseConsistencyFailed`]; later calls are unaffected.
+///
+/// This lets integrators exercise their handling of a PCT failure. Never enable the feature
+/// in production builds.
+#[cfg(feature = "pct-fault-injection")]
+pub fn inject_pct_fault() {
+    PCT_FAULT.with(|fault| fault.set(true));
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::params::Kyber768Params;
     use crate::{constants::KyberParams, hash::H};
     use rand::rngs::OsRng;
 
@@ -238,6 +751,188 @@ mod tests {
 
         let k_decaps = kem_scheme.decaps(&dk, &c)?;
         assert_eq!(k_decaps.0, k.0);
+
+        assert_eq!(
+            kem_scheme.decaps(&dk, &c[1..]).err(),
+            Some(Error::InvalidCiphertextLength {
+                expected: 1088,
+                got: 1087
+            })
+        );
+        assert_eq!(
+            KemEncapsKey::<3>::from_bytes(&[0u8; 800]).err(),
+            Some(Error::ParameterSetMismatch {
+                expected: ParameterSet::MlKem768,
+                got: ParameterSet::MlKem512
+            })
+        );
+        assert_eq!(
+            KemDecapsKey::<3>::from_bytes(&dk.to_bytes()[1..]).err(),
+            Some(Error::InvalidKeyLength {
+                expected: 2400,
+                got: 2399
+            })
+        );
+
+        let mut bad_ek = KemEncapsKey::<3>::from_bytes(&ek.to_bytes())?;
+        bad_ek.0[0][0] = 0xff;
+        bad_ek.0[0][1] |= 0x0f;
+        assert_eq!(
+            kem_scheme.encaps(&bad_ek, &mut OsRng).err(),
+            Some(Error::ModulusCheckFailed)
+        );
+
+        let mut bad_dk = KemDecapsKey::<3>::from_bytes(&dk.to_bytes())?;
+        bad_dk.2[32] ^= 1;
+        assert_eq!(
+            kem_scheme.decaps(&bad_dk, &c).err(),
+            Some(Error::HashCheckFailed)
+        );
+        Ok(())
+    }
+
+    /// Entropy source that always fails
+    struct FailingRng;
+
+    impl RngCore for FailingRng {
+        fn next_u32(&mut self) -> u32 {
+            unreachable!()
+        }
+        fn next_u64(&mut self) -> u64 {
+            unreachable!()
+        }
+        fn fill_bytes(&mut self, _: &mut [u8]) {
+            unreachable!()
+        }
+        fn try_fill_bytes(&mut self, _: &mut [u8]) -> Result<(), rand::Error> {
+            Err(rand::Error::new("entropy source failed"))
+        }
+    }
+
+    impl CryptoRng for FailingRng {}
+
+    #[test]
+    fn entropy() -> Result<(), Error> {
+        let kem_scheme = MlKem::<3, Kyber768Params, KyberParams>::new();
+        let (d, z) = ([1u8; 32], [2u8; 32]);
+        let entropy: [u8; 64] = [d, z].concat().try_into().unwrap();
+
+        let (ek, dk) = kem_scheme.key_gen_from_entropy(&entropy)?;
+        let (ek_internal, dk_internal) = kem_scheme.key_gen_internal(&d, &z)?;
+        assert_eq!(ek, ek_internal);
+        assert_eq!(dk, dk_internal);
+
+        let (k, c) = kem_scheme.encaps_from_entropy(&ek, &[3u8; 32])?;
+        let (k_internal, c_internal) = kem_scheme.encaps_internal(&ek, &[3u8; 32])?;
+        assert_eq!((k, c), (k_internal, c_internal));
+
+        let mut bad_ek = KemEncapsKey::<3>::from_bytes(&ek.to_bytes())?;
+        bad_ek.0[0][0] = 0xff;
+        bad_ek.0[0][1] |= 0x0f;
+        assert_eq!(
+            kem_scheme.encaps_from_entropy(&bad_ek, &[3u8; 32]).err(),
+            Some(Error::ModulusCheckFailed)
+        );
+
+        assert_eq!(
+            kem_scheme.key_gen(&mut FailingRng).err(),
+            Some(Error::RngFailure)
+        );
+        assert_eq!(
+            kem_scheme.encaps(&ek, &mut FailingRng).err(),
+            Some(Error::RngFailure)
+        );
+        #[cfg(feature = "fips")]
+        assert_eq!(
+            crate::service_indicator::last(),
+            Some(crate::service_indicator::ServiceIndicator::NotApproved)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn key_types() -> Result<(), Error> {
+        let kem_scheme = MlKem::<3, SecurityL, KyberParams>::new();
+        let (ek, dk) = kem_scheme.key_gen_internal(&[1u8; 32], &[2u8; 32])?;
+        let (k, c) = kem_scheme.encaps_internal(&ek, &[3u8; 32])?;
+
+        assert_eq!(dk.encaps_key(), ek);
+        assert_eq!(dk.h(), &ek.hash());
+        assert_eq!(dk.z(), &[2u8; 32]);
+        assert_eq!(
+            [dk.dk_pke().as_flattened(), &ek.to_bytes(), dk.h(), dk.z()].concat(),
+            *dk.to_bytes()
+        );
+
+        let dk_copy = dk.clone_secret();
+        assert_eq!(dk_copy, dk);
+        let mut other = KemDecapsKey::<3>::from_bytes(&dk.to_bytes())?;
+        other.2[95] ^= 1;
+        assert_ne!(other, dk);
+
+        let k_copy = k.clone_secret();
+        assert_eq!(kem_scheme.decaps(&dk_copy, &c)?, k_copy);
+        assert_eq!(KemSharedSecret::from_bytes(k.as_bytes())?, k);
+        assert_eq!(
+            KemSharedSecret::from_bytes(&[0u8; 31]).err(),
+            Some(Error::InvalidInputLength)
+        );
+
+        assert_eq!(format!("{dk:?}"), "KemDecapsKey(<redacted>)");
+        assert_eq!(format!("{k:?}"), "KemSharedSecret(<redacted>)");
+        assert_eq!(
+            format!("{ek:?}"),
+            format!(
+                "KemEncapsKey {{ fingerprint: {:?} }}",
+                hex::encode(&dk.h()[..8])
+            )
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "pct-fault-injection")]
+    #[test]
+    fn pairwise_consistency_test() -> Result<(), Error> {
+        let kem_scheme = MlKem::<3, SecurityL, KyberParams>::new();
+        let (ek, dk) = kem_scheme.key_gen(&mut OsRng)?;
+
+        let keys = kem_scheme.pairwise_consistency_test((ek, dk))?;
+
+        inject_pct_fault();
+        assert_eq!(
+            kem_scheme.pairwise_consistency_test(keys).err(),
+            Some(Error::PairwiseConsistencyFailed)
+        );
+
+        inject_pct_fault();
+        assert_eq!(
+            kem_scheme.key_gen(&mut OsRng).err(),
+            Some(Error::PairwiseConsistencyFailed)
+        );
+        assert!(kem_scheme.key_gen(&mut OsRng).is_ok());
+        Ok(())
+    }
+
+    #[cfg(feature = "hardened")]
+    #[test]
+    fn faults_are_detected() -> Result<(), Error> {
+        let kem_scheme = MlKem::<3, SecurityL, KyberParams>::new();
+        let (ek, dk) = kem_scheme.key_gen(&mut OsRng)?;
+        let (k, mut c) = kem_scheme.encaps(&ek, &mut OsRng)?;
+        assert_eq!(kem_scheme.decaps_internal(&dk, &c)?.0, k.0);
+        c[0] ^= 1;
+        assert_ne!(kem_scheme.decaps_internal(&dk, &c)?.0, k.0);
+
+        let mut faulty_dk = KemDecapsKey::<3>::from_bytes(&dk.to_bytes())?;
+        faulty_dk.1[1][7] ^= 4;
+        assert_eq!(
+            kem_scheme.decaps_internal(&faulty_dk, &c).err(),
+            Some(Error::FaultDetected)
+        );
+        assert_eq!(
+            MlKem::<3, SecurityL, KyberParams>::check_key_pair((ek, faulty_dk)).err(),
+            Some(Error::FaultDetected)
+        );
         Ok(())
     }
 }
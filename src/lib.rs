@@ -13,6 +13,9 @@
 //! * **Memory Clearing**: Structures containing secrets (`KemDecapsKey`, `KemSharedSecret`) implement
 //!   the [`zeroize::Zeroize`] and [`zeroize::ZeroizeOnDrop`] traits. They are automatically wiped from RAM
 //!   when they go out of scope.
+//! * **Safe Key Types**: Secret types have a redacted `Debug`, compare in constant time with `==`
+//!   ([`subtle::ConstantTimeEq`]) and are only copied through `clone_secret`. Public keys print a
+//!   fingerprint of H(ek). Keys are read through accessors (`t_hat`, `rho`, `as_bytes`, ...).
 //! * **Determinism**: Key generation and encapsulation functions accept an external random number generator
 //!   (implementing [`rand_core::RngCore`]), allowing for deterministic tests (Known Answer Tests).
 //!
@@ -37,8 +40,8 @@
 //! // 4. Decapsulation (Alice)
 //! let shared_secret_alice = kem.decaps(&dk, &ciphertext)?;
 //!
-//! // The secrets are identical
-//! assert_eq!(shared_secret_bob.0, shared_secret_alice.0);
+//! // The secrets are identical (`==` runs in constant time)
+//! assert_eq!(shared_secret_bob, shared_secret_alice);
 //! # Ok(())
 //! # }
 //! ```
@@ -51,19 +54,76 @@
 //! - [`pke_scheme`]: Implementation of the underlying Public Key Encryption (K-PKE).
 //! - [`polynomial`]: Polynomial arithmetic on the ring $R_q = \mathbb{Z}_q[X]/(X^{256}+1)$.
 //! - [`params`]: Definition of security parameters via the [`params::SecurityLevel`] trait.
+//! - `seal` (feature `seal`): KEM-DEM encryption of arbitrary-length messages with an AEAD.
+//! - `secure_memory` (feature `secure-memory`): locked, guarded pages for decapsulation keys
+//!   and shared secrets.
+//! - `drbg` (feature `drbg`): SP 800-90A CTR_DRBG, an approved randomness source for
+//!   `key_gen` and `encaps`.
+//! - [`entropy_health`]: SP 800-90B continuous health tests of a noise source.
+//! - [`self_test`]: FIPS 140-3 self-tests, run before first use with the `fips` feature.
+//! - `hardening` (feature `hardened`): fault-injection countermeasures in decapsulation and
+//!   key generation.
+//! - `masked` (feature `masked`): first-order masked decapsulation against power analysis.
+//! - [`service_indicator`]: FIPS 140-3 approved-service indicator, recorded with the `fips`
+//!   feature.
+//! - `kat` (feature `kat`): NIST `PQCgenKAT_kem` known-answer files and their AES-256-CTR DRBG.
+//! - `ct_verify` (feature `ct-verify`): valgrind client requests to check for secret-dependent
+//!   branches and memory accesses.
 
 use crate::params::{Kyber1024Params, Kyber512Params, Kyber768Params};
 use crate::{constants::KyberParams, kem_scheme::MlKem, polynomial::Polynomial};
 
+/// Marks secret-derived bytes that are public by design (see `ct_verify`, feature `ct-verify`).
+macro_rules! declassify {
+    ($($bytes:expr),+ $(,)?) => {
+        #[cfg(feature = "ct-verify")]
+        {
+            $(crate::ct_verify::declassify($bytes);)+
+        }
+    };
+}
+
+#[cfg(feature = "capi")]
+pub mod capi;
 pub mod constants;
 pub mod conversion;
+#[cfg(feature = "ct-verify")]
+pub mod ct_verify;
+#[cfg(feature = "drbg")]
+pub mod drbg;
+pub mod entropy_health;
 pub mod errors;
+#[cfg(feature = "hardened")]
+pub(crate) mod hardening;
 pub mod hash;
+#[cfg(feature = "kat")]
+pub mod kat;
 pub mod kem_scheme;
+#[cfg(feature = "masked")]
+pub mod masked;
 pub mod params;
 pub mod pke_scheme;
 pub mod polynomial;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "seal")]
+pub mod seal;
+#[cfg(all(feature = "secure-memory", unix))]
+pub mod secure_memory;
+pub mod self_test;
+pub mod service_indicator;
 pub mod traits;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(all(feature = "secure-memory", not(unix)))]
+compile_error!("the `secure-memory` feature needs a Unix target");
+
+#[cfg(all(feature = "masked", feature = "hardened"))]
+compile_error!(
+    "the `masked` decapsulation does not carry the `hardened` countermeasures: enable only one \
+     of the two features"
+);
 
 /// Type alias for a polynomial in the ring R_q with Kyber parameters.
 pub type KyberPoly = Polynomial<KyberParams>;
@@ -0,0 +1,660 @@
+//! # Masked decapsulation (feature `masked`)
+//!
+//! First-order masked K-PKE.Decrypt and re-encryption check, against power analysis: every
+//! value depending on the decapsulation key is split into two shares, refreshed with fresh
+//! randomness on each call, and no intermediate value depends on both shares of a secret.
+//!
+//! - ŝ is decoded directly into arithmetic shares modulo q, from its bytes masked as they are
+//!   read, bit by bit through a Boolean-to-arithmetic conversion. Base multiplication by a
+//!   public polynomial, NTT, NTT^-1 and additions are linear and run on each share, with the
+//!   existing [`Polynomial`] and [`PolynomialNTT`] types.
+//! - Compress_1 of w (decoding of m') converts the arithmetic shares into Boolean shares with
+//!   a masked Kogge-Stone adder, and compares the result to the bounds of the preimage of 1.
+//! - G, PRF and J run on a masked Keccak-f[1600], whose χ uses secure ANDs.
+//! - SamplePolyCBD converts each Boolean-shared bit into arithmetic shares.
+//! - The re-encryption is never compressed: each coefficient of u and v is compared, in the
+//!   same way, to the preimage of the received ciphertext coefficient. The results are ANDed
+//!   into a shared bit, which selects between K' and K̄ on the shares.
+//!
+//! Only the shared secret is unmasked. With this feature, `MlKem::decaps` takes this path
+//! (`decaps_internal` keeps the unmasked one). Masks are drawn from `OsRng` with
+//! `try_fill_bytes`, a block at a time: if they cannot be drawn, decapsulation returns
+//! [`Error::RngFailure`].
+//!
+//! The gadgets are first-order secure in the value-based leakage model. Shares are kept apart
+//! from the optimizer with [`black_box`], but the generated code still needs to be evaluated on
+//! the target device.
+
+use core::hint::black_box;
+use core::ops::{BitAnd, BitXor, Not, Shl, Shr};
+
+use rand::rngs::OsRng;
+use rand::{CryptoRng, RngCore};
+use zeroize::Zeroizing;
+
+use crate::constants::PolyParams;
+use crate::conversion::{byte_decode, decompress, get_bit};
+use crate::entropy_health::rng_error;
+use crate::errors::Error;
+use crate::kem_scheme::KemDecapsKey;
+use crate::params::SecurityLevel;
+use crate::pke_scheme::PkeDecryptKey;
+use crate::polynomial::{Polynomial, PolynomialNTT};
+
+mod keccak;
+
+use keccak::{sponge, SHA3_512, SHAKE256};
+
+trait Word:
+    Copy
+    + BitXor<Output = Self>
+    + BitAnd<Output = Self>
+    + Not<Output = Self>
+    + Shl<u32, Output = Self>
+    + Shr<u32, Output = Self>
+{
+    fn random<R: RngCore>(rng: &mut R) -> Result<Self, Error>;
+}
+
+impl Word for u16 {
+    fn random<R: RngCore>(rng: &mut R) -> Result<Self, Error> {
+        let mut bytes = [0u8; 2];
+        fill_random(&mut bytes, rng)?;
+        Ok(u16::from_le_bytes(bytes))
+    }
+}
+
+impl Word for u64 {
+    fn random<R: RngCore>(rng: &mut R) -> Result<Self, Error> {
+        let mut bytes = [0u8; 8];
+        fill_random(&mut bytes, rng)?;
+        Ok(u64::from_le_bytes(bytes))
+    }
+}
+
+/// Masks drawn with `try_fill_bytes`, so that a failing RNG is reported instead of panicking.
+fn fill_random<R: RngCore>(dest: &mut [u8], rng: &mut R) -> Result<(), Error> {
+    rng.try_fill_bytes(dest).map_err(rng_error)
+}
+
+/// Masks drawn from `OsRng` a block at a time, one system call per block instead of one per
+/// gadget.
+struct MaskSource {
+    block: Zeroizing<[u8; 1024]>,
+    used: usize,
+}
+
+impl MaskSource {
+    fn new() -> Self {
+        MaskSource {
+            block: Zeroizing::new([0u8; 1024]),
+            used: 1024,
+        }
+    }
+}
+
+impl RngCore for MaskSource {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    /// Panics if `OsRng` fails, the gadgets use `try_fill_bytes` to handle it.
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        if let Err(e) = self.try_fill_bytes(dest) {
+            panic!("{e}");
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        for byte in dest {
+            if self.used == self.block.len() {
+                OsRng.try_fill_bytes(self.block.as_mut_slice())?;
+                self.used = 0;
+            }
+            *byte = self.block[self.used];
+            self.used += 1;
+        }
+        Ok(())
+    }
+}
+
+impl CryptoRng for MaskSource {}
+
+/// Uniform mask in [0, q), by rejection sampling of 12-bit values
+fn random_mod<R: RngCore>(q: i16, rng: &mut R) -> Result<i16, Error> {
+    loop {
+        let r = (u16::random(rng)? & 0x0fff) as i16;
+        if r < q {
+            return Ok(r);
+        }
+    }
+}
+
+/// Boolean sharing : x = x.0 ^ x.1
+#[derive(Clone, Copy)]
+struct Bool<W>(W, W);
+
+impl<W: Word> Bool<W> {
+    fn mask<R: RngCore>(x: W, rng: &mut R) -> Result<Self, Error> {
+        let r = W::random(rng)?;
+        Ok(Bool(black_box(x ^ r), r))
+    }
+
+    fn unmask(self) -> W {
+        self.0 ^ self.1
+    }
+
+    /// New shares of the same value
+    fn refresh<R: RngCore>(self, rng: &mut R) -> Result<Self, Error> {
+        let r = W::random(rng)?;
+        Ok(Bool(black_box(self.0 ^ r), black_box(self.1 ^ r)))
+    }
+
+    fn xor(self, other: Self) -> Self {
+        Bool(self.0 ^ other.0, self.1 ^ other.1)
+    }
+
+    fn not(self) -> Self {
+        Bool(!self.0, self.1)
+    }
+
+    fn shl(self, n: u32) -> Self {
+        Bool(self.0 << n, self.1 << n)
+    }
+
+    fn shr(self, n: u32) -> Self {
+        Bool(self.0 >> n, self.1 >> n)
+    }
+}
+
+/// Secure AND (Trichina gate) : the fresh mask is added first, and `y` is refreshed so that
+/// both operands may come from the same sharing.
+fn sec_and<W: Word, R: RngCore>(x: Bool<W>, y: Bool<W>, rng: &mut R) -> Result<Bool<W>, Error> {
+    let y = y.refresh(rng)?;
+    let r = W::random(rng)?;
+    let mut z = black_box(r ^ (x.0 & y.0));
+    z = black_box(z ^ (x.0 & y.1));
+    z = black_box(z ^ (x.1 & y.0));
+    z = black_box(z ^ (x.1 & y.1));
+    Ok(Bool(r, z))
+}
+
+/// Boolean-masked addition modulo 2^16 (Kogge-Stone adder). Generate and propagate are never
+/// both set, so the OR of the carry recurrence is a XOR.
+fn sec_add<R: RngCore>(x: Bool<u16>, y: Bool<u16>, rng: &mut R) -> Result<Bool<u16>, Error> {
+    let mut p = x.xor(y);
+    let mut g = sec_and(x, y, rng)?;
+    for shift in [1, 2, 4, 8] {
+        g = g.xor(sec_and(p, g.shl(shift), rng)?);
+        if shift < 8 {
+            p = sec_and(p, p.shl(shift), rng)?;
+        }
+    }
+    Ok(x.xor(y).xor(g.shl(1)))
+}
+
+/// Preimage of y by Compress_d : the x in [lo, hi], cyclically modulo q (lo > hi when it
+/// wraps around 0).
+fn compress_preimage(y: i16, d: usize, q: i16) -> (i16, i16) {
+    let (q, two_pow_d) = (q as i32, 1i32 << d);
+    // smallest x with (2^d x + q/2) / q >= y
+    let first = |y: i32| (y * q - q / 2 + two_pow_d - 1).div_euclid(two_pow_d).max(0);
+
+    let lo = if y == 0 {
+        first(two_pow_d) % q
+    } else {
+        first(y as i32)
+    };
+    let hi = first(y as i32 + 1) - 1;
+    (lo as i16, hi as i16)
+}
+
+/// Shared bit (bit 0) : Compress_d(a0 + a1 mod q) == y, from arithmetic shares a0, a1 in
+/// [0, q) and a public y.
+///
+/// s = a0 + a1 is computed on Boolean shares, without reduction : s mod q is in the cyclic
+/// interval [lo, hi] iff an odd number of the thresholds lo, hi + 1, lo + q, hi + 1 + q are
+/// below s (an even number when the interval wraps around 0). Each threshold is compared
+/// through the sign of s - t.
+fn compress_eq<R: RngCore>(
+    a0: i16,
+    a1: i16,
+    y: i16,
+    d: usize,
+    q: i16,
+    rng: &mut R,
+) -> Result<Bool<u16>, Error> {
+    let (lo, hi) = compress_preimage(y, d, q);
+    let s = sec_add(
+        Bool::mask(a0 as u16, rng)?,
+        Bool::mask(a1 as u16, rng)?,
+        rng,
+    )?;
+
+    let mut bit = Bool(u16::from(lo > hi), 0);
+    for t in [lo, hi + 1, lo + q, hi + 1 + q] {
+        let sign = sec_add(s, Bool((t as u16).wrapping_neg(), 0), rng)?.shr(15);
+        bit = bit.xor(Bool(sign.0 ^ 1, sign.1));
+    }
+    Ok(bit)
+}
+
+/// Arithmetic shares modulo q of the bit b0 ^ b1 = b1 + (1 - 2 b1) b0
+fn b2a_bit<R: RngCore>(b0: i16, b1: i16, q: i16, rng: &mut R) -> Result<(i16, i16), Error> {
+    let r = random_mod(q, rng)? as i32;
+    let sign = 1 - 2 * b1 as i32;
+    let a0 = black_box(sign * (b0 as i32 - r)) + b1 as i32;
+    let a1 = sign * r;
+    Ok((
+        a0.rem_euclid(q as i32) as i16,
+        a1.rem_euclid(q as i32) as i16,
+    ))
+}
+
+/// ByteDecode_12 directly into arithmetic shares modulo q, for the NTT-domain polynomial
+/// encoded in the 384 bytes of a secret : the bytes are masked as they are read, and each bit
+/// of a coefficient goes from Boolean to arithmetic shares, so no coefficient is ever
+/// computed in the clear.
+fn decode_shares<P: PolyParams, R: RngCore>(
+    bytes: &[u8],
+    rng: &mut R,
+) -> Result<[PolynomialNTT<P>; 2], Error> {
+    let mut mask = Zeroizing::new([0u8; 384]);
+    fill_random(mask.as_mut_slice(), rng)?;
+    let masked: Zeroizing<[u8; 384]> =
+        Zeroizing::new(core::array::from_fn(|i| black_box(bytes[i] ^ mask[i])));
+
+    let (mut share_0, mut share_1) = (Zeroizing::new([0i16; 256]), Zeroizing::new([0i16; 256]));
+    for (i, (c0, c1)) in share_0.iter_mut().zip(share_1.iter_mut()).enumerate() {
+        let mut sum = [0i32; 2];
+        for j in 0..12 {
+            let index = 12 * i + j;
+            let (a0, a1) = b2a_bit(
+                get_bit(masked.as_slice(), index),
+                get_bit(mask.as_slice(), index),
+                P::Q,
+                rng,
+            )?;
+            sum[0] += (a0 as i32) << j;
+            sum[1] += (a1 as i32) << j;
+        }
+        *c0 = sum[0].rem_euclid(P::Q as i32) as i16;
+        *c1 = sum[1].rem_euclid(P::Q as i32) as i16;
+    }
+    Ok([PolynomialNTT::from(*share_0), PolynomialNTT::from(*share_1)])
+}
+
+/// Boolean shares of 32 bytes given in the clear
+fn split_bytes<R: RngCore>(bytes: &[u8], rng: &mut R) -> Result<[[u8; 32]; 2], Error> {
+    let mut mask = [0u8; 32];
+    fill_random(&mut mask, rng)?;
+    let masked = core::array::from_fn(|i| black_box(bytes[i] ^ mask[i]));
+    Ok([masked, mask])
+}
+
+/// Decompress_d(ByteDecode_d(bytes)), for a public polynomial
+fn decode_decompress<P: PolyParams>(bytes: &[u8], d: usize) -> Result<Polynomial<P>, Error> {
+    let mut coeffs = [0i16; 256];
+    byte_decode(bytes, d, P::Q, &mut coeffs)?;
+    Ok(Polynomial::from(coeffs.map(|y| decompress(y, d, P::Q))))
+}
+
+/// Masked SamplePolyCBD_eta, from the Boolean shares of PRF_eta
+fn sample_poly_cbd<P: PolyParams, R: RngCore>(
+    bytes: &[Vec<u8>; 2],
+    eta: usize,
+    rng: &mut R,
+) -> Result<[Polynomial<P>; 2], Error> {
+    let (mut share_0, mut share_1) = ([0i16; 256], [0i16; 256]);
+    for (i, (c0, c1)) in share_0.iter_mut().zip(share_1.iter_mut()).enumerate() {
+        let mut sum = [0i32; 2];
+        for j in 0..2 * eta {
+            let index = 2 * i * eta + j;
+            let (a0, a1) = b2a_bit(
+                get_bit(&bytes[0], index),
+                get_bit(&bytes[1], index),
+                P::Q,
+                rng,
+            )?;
+            let sign = if j < eta { 1 } else { -1 };
+            sum[0] += sign * a0 as i32;
+            sum[1] += sign * a1 as i32;
+        }
+        *c0 = sum[0].rem_euclid(P::Q as i32) as i16;
+        *c1 = sum[1].rem_euclid(P::Q as i32) as i16;
+    }
+    Ok([Polynomial::from(share_0), Polynomial::from(share_1)])
+}
+
+/// Masked Algorithm 15 (FIPS 203) : K-PKE.Decrypt(dk, c)
+///
+/// Input : decryption key dk in B^(384*k)
+/// Input : ciphertext c in B^(32 * (d_u*k + d_v))
+/// Output : Boolean shares of the message m in B^32
+pub fn decrypt<const K: usize, S: SecurityLevel, P: PolyParams, R: RngCore + CryptoRng>(
+    dk: &PkeDecryptKey<K>,
+    c: &[u8],
+    rng: &mut R,
+) -> Result<[[u8; 32]; 2], Error> {
+    if c.len() != 32 * (S::DU * K + S::DV) {
+        return Err(Error::InvalidCiphertextLength {
+            expected: 32 * (S::DU * K + S::DV),
+            got: c.len(),
+        });
+    }
+    let (c_1, c_2) = c.split_at(32 * S::DU * K);
+
+    let mut products = [
+        PolynomialNTT::<P>::from([0i16; 256]),
+        PolynomialNTT::<P>::from([0i16; 256]),
+    ];
+    for (i, chunk) in dk.0.iter().enumerate() {
+        let u_ntt =
+            decode_decompress::<P>(&c_1[32 * S::DU * i..32 * S::DU * (i + 1)], S::DU)?.to_ntt();
+
+        let s_shares = decode_shares::<P, R>(chunk, rng)?;
+
+        for (product, s_share) in products.iter_mut().zip(&s_shares) {
+            *product += &(s_share * &u_ntt);
+        }
+    }
+
+    let v = decode_decompress::<P>(c_2, S::DV)?;
+    let zero = Polynomial::<P>::from([0i16; 256]);
+    let w = [
+        &v - &Polynomial::from_ntt(&products[0]),
+        &zero - &Polynomial::from_ntt(&products[1]),
+    ];
+
+    let mut m = [[0u8; 32]; 2];
+    for j in 0..256 {
+        let bit = compress_eq(w[0].coeffs[j], w[1].coeffs[j], 1, 1, P::Q, rng)?;
+        m[0][j / 8] |= (bit.0 as u8) << (j % 8);
+        m[1][j / 8] |= (bit.1 as u8) << (j % 8);
+    }
+    Ok(m)
+}
+
+/// Masked K-PKE.Encrypt(ek, m, r), compared to the ciphertext c : a shared mask, all ones
+/// iff the re-encryption equals c.
+fn reencrypt_eq<const K: usize, S: SecurityLevel, P: PolyParams, R: RngCore>(
+    dk: &KemDecapsKey<K>,
+    m: &[[u8; 32]; 2],
+    r: &[[u8; 32]; 2],
+    c: &[u8],
+    rng: &mut R,
+) -> Result<Bool<u64>, Error> {
+    let (c_1, c_2) = c.split_at(32 * S::DU * K);
+    let rho = &dk.2[..32];
+
+    let mut n_var = 0u8;
+    let mut prf = |eta: usize, rng: &mut R| {
+        let bytes = sponge(SHAKE256, r, &[n_var], 64 * eta, rng)?;
+        n_var += 1;
+        sample_poly_cbd::<P, R>(&bytes, eta, rng)
+    };
+    let y_ntt = (0..K)
+        .map(|_| Ok(prf(S::ETA1, rng)?.map(|share| share.to_ntt())))
+        .collect::<Result<Vec<[PolynomialNTT<P>; 2]>, Error>>()?;
+    let e_1 = (0..K)
+        .map(|_| prf(S::ETA2, rng))
+        .collect::<Result<Vec<[Polynomial<P>; 2]>, Error>>()?;
+    let e_2 = prf(S::ETA2, rng)?;
+
+    let mut valid = Bool(1u16, 0);
+
+    for (i, e_1) in e_1.iter().enumerate() {
+        let mut products = [
+            PolynomialNTT::<P>::from([0i16; 256]),
+            PolynomialNTT::<P>::from([0i16; 256]),
+        ];
+        for (j, y_ntt) in y_ntt.iter().enumerate() {
+            let mut input = [0u8; 34];
+            input[0..32].copy_from_slice(rho);
+            input[32] = i as u8;
+            input[33] = j as u8;
+            let a_ntt = PolynomialNTT::<P>::sample_ntt(&input);
+            for (product, y_share) in products.iter_mut().zip(y_ntt) {
+                *product += &(&a_ntt * y_share);
+            }
+        }
+        let u = [
+            &Polynomial::from_ntt(&products[0]) + &e_1[0],
+            &Polynomial::from_ntt(&products[1]) + &e_1[1],
+        ];
+
+        let mut c_u = [0i16; 256];
+        byte_decode(
+            &c_1[32 * S::DU * i..32 * S::DU * (i + 1)],
+            S::DU,
+            P::Q,
+            &mut c_u,
+        )?;
+        for (j, &y) in c_u.iter().enumerate() {
+            let eq = compress_eq(u[0].coeffs[j], u[1].coeffs[j], y, S::DU, P::Q, rng)?;
+            valid = sec_and(valid, eq, rng)?;
+        }
+    }
+
+    let mut products = [
+        PolynomialNTT::<P>::from([0i16; 256]),
+        PolynomialNTT::<P>::from([0i16; 256]),
+    ];
+    for (chunk, y_ntt) in dk.1.iter().zip(&y_ntt) {
+        let mut t_ntt = [0i16; 256];
+        byte_decode(chunk, 12, P::Q, &mut t_ntt)?;
+        let t_ntt = PolynomialNTT::<P>::from(t_ntt);
+        for (product, y_share) in products.iter_mut().zip(y_ntt) {
+            *product += &(&t_ntt * y_share);
+        }
+    }
+
+    let one = decompress(1, 1, P::Q) as i32;
+    let (mut mu_0, mut mu_1) = ([0i16; 256], [0i16; 256]);
+    for (j, (c0, c1)) in mu_0.iter_mut().zip(mu_1.iter_mut()).enumerate() {
+        let (a0, a1) = b2a_bit(get_bit(&m[0], j), get_bit(&m[1], j), P::Q, rng)?;
+        *c0 = (a0 as i32 * one).rem_euclid(P::Q as i32) as i16;
+        *c1 = (a1 as i32 * one).rem_euclid(P::Q as i32) as i16;
+    }
+    let mu = [Polynomial::<P>::from(mu_0), Polynomial::<P>::from(mu_1)];
+
+    let v = [
+        &(&Polynomial::from_ntt(&products[0]) + &e_2[0]) + &mu[0],
+        &(&Polynomial::from_ntt(&products[1]) + &e_2[1]) + &mu[1],
+    ];
+
+    let mut c_v = [0i16; 256];
+    byte_decode(c_2, S::DV, P::Q, &mut c_v)?;
+    for (j, &y) in c_v.iter().enumerate() {
+        let eq = compress_eq(v[0].coeffs[j], v[1].coeffs[j], y, S::DV, P::Q, rng)?;
+        valid = sec_and(valid, eq, rng)?;
+    }
+
+    // the secure ANDs leave random bits above bit 0 in each share
+    Ok(Bool(
+        0u64.wrapping_sub((valid.0 & 1) as u64),
+        0u64.wrapping_sub((valid.1 & 1) as u64),
+    ))
+}
+
+/// Masked Algorithm 18 (FIPS 203) : ML-KEM.Decaps_internal(dk, c)
+///
+/// Input : decapsulation key dk in B^(768*k + 96)
+/// Input : ciphertext c in B^(32 * (d_u*k + d_v))
+/// Output : shared secret key K in B^32
+pub fn decaps<const K: usize, S: SecurityLevel, P: PolyParams>(
+    dk: &KemDecapsKey<K>,
+    c: &[u8],
+) -> Result<[u8; 32], Error> {
+    let mut rng = MaskSource::new();
+
+    let m_prime = Zeroizing::new(decrypt::<K, S, P, _>(&PkeDecryptKey(dk.0), c, &mut rng)?);
+
+    let g = Zeroizing::new(sponge(SHA3_512, &m_prime, &dk.2[32..64], 64, &mut rng)?);
+    let mut k_prime = Zeroizing::new([[0u8; 32]; 2]);
+    let mut r_prime = Zeroizing::new([[0u8; 32]; 2]);
+    for b in 0..2 {
+        k_prime[b].copy_from_slice(&g[b][..32]);
+        r_prime[b].copy_from_slice(&g[b][32..]);
+    }
+    drop(g);
+
+    let z = Zeroizing::new(split_bytes(&dk.2[64..96], &mut rng)?);
+    let k_bar = Zeroizing::new(sponge(SHAKE256, &z, c, 32, &mut rng)?);
+    drop(z);
+
+    let valid = reencrypt_eq::<K, S, P, _>(dk, &m_prime, &r_prime, c, &mut rng)?;
+    drop(m_prime);
+    drop(r_prime);
+
+    // K = K̄ ^ (valid & (K' ^ K̄))
+    let mut k = Zeroizing::new([0u8; 32]);
+    for (i, out) in k.chunks_exact_mut(8).enumerate() {
+        let lane = |bytes: &[u8]| u64::from_le_bytes(bytes[8 * i..8 * (i + 1)].try_into().unwrap());
+        let k_prime = Bool(lane(&k_prime[0]), lane(&k_prime[1]));
+        let k_bar = Bool(lane(&k_bar[0]), lane(&k_bar[1]));
+        let selected = k_bar.xor(sec_and(valid, k_prime.xor(k_bar), &mut rng)?);
+        out.copy_from_slice(&selected.unmask().to_le_bytes());
+    }
+    Ok(*k)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::KyberParams;
+    use crate::conversion::compress;
+    use crate::kem_scheme::MlKem;
+    use crate::params::{Kyber1024Params, Kyber512Params, Kyber768Params};
+    use crate::pke_scheme::KPke;
+    use crate::traits::{KemScheme, PkeScheme};
+    use rand::Rng;
+
+    const Q: i16 = KyberParams::Q;
+
+    fn in_preimage(x: i16, (lo, hi): (i16, i16)) -> bool {
+        if lo <= hi {
+            lo <= x && x <= hi
+        } else {
+            x >= lo || x <= hi
+        }
+    }
+
+    #[test]
+    fn compress_preimages() {
+        for d in [1, 4, 5, 10, 11] {
+            let mut size = 0;
+            for y in 0..1i16 << d {
+                let (lo, hi) = compress_preimage(y, d, Q);
+                size += (hi - lo).rem_euclid(Q) + 1;
+            }
+            assert_eq!(size, Q);
+            for x in 0..Q {
+                assert!(in_preimage(x, compress_preimage(compress(x, d, Q), d, Q)));
+            }
+        }
+    }
+
+    #[test]
+    fn gadgets() -> Result<(), Error> {
+        let mut rng = MaskSource::new();
+        for _ in 0..2000 {
+            let (a0, a1) = (rng.gen_range(0..Q), rng.gen_range(0..Q));
+            let x = (a0 + a1) % Q;
+            let s = sec_add(
+                Bool::mask(a0 as u16, &mut rng)?,
+                Bool::mask(a1 as u16, &mut rng)?,
+                &mut rng,
+            )?;
+            assert_eq!(s.unmask(), (a0 + a1) as u16);
+
+            for d in [1, 10, 11] {
+                let y = compress(x, d, Q);
+                assert_eq!(compress_eq(a0, a1, y, d, Q, &mut rng)?.unmask(), 1);
+                let other = (y + 1) % (1 << d);
+                assert_eq!(compress_eq(a0, a1, other, d, Q, &mut rng)?.unmask(), 0);
+            }
+
+            let (b0, b1) = (rng.gen_range(0..2), rng.gen_range(0..2));
+            let (c0, c1) = b2a_bit(b0, b1, Q, &mut rng)?;
+            assert_eq!((c0 + c1) % Q, b0 ^ b1);
+
+            let (x, y) = (rng.next_u64(), rng.next_u64());
+            let and = sec_and(Bool::mask(x, &mut rng)?, Bool::mask(y, &mut rng)?, &mut rng)?;
+            assert_eq!(and.unmask(), x & y);
+        }
+        Ok(())
+    }
+
+    fn matches_unmasked<const K: usize, S: SecurityLevel>() -> Result<(), Error> {
+        let kem = MlKem::<K, S, KyberParams>::new();
+        let pke = KPke::<K, S, KyberParams>::new();
+        for _ in 0..5 {
+            let (ek, dk) = kem.key_gen(&mut OsRng)?;
+            let (k, mut c) = kem.encaps(&ek, &mut OsRng)?;
+
+            let m = decrypt::<K, S, KyberParams, _>(&PkeDecryptKey(dk.0), &c, &mut OsRng)?;
+            let m: [u8; 32] = core::array::from_fn(|i| m[0][i] ^ m[1][i]);
+            assert_eq!(m, pke.decrypt(&PkeDecryptKey(dk.0), &c)?);
+
+            assert_eq!(decaps::<K, S, KyberParams>(&dk, &c)?, k.0);
+
+            let index = OsRng.gen_range(0..c.len());
+            c[index] ^= 1 << OsRng.gen_range(0..8);
+            assert_eq!(
+                decaps::<K, S, KyberParams>(&dk, &c)?,
+                kem.decaps_internal(&dk, &c)?.0
+            );
+        }
+        assert_eq!(
+            decaps::<K, S, KyberParams>(&kem.key_gen(&mut OsRng)?.1, &[0u8; 10]).err(),
+            Some(Error::InvalidCiphertextLength {
+                expected: 32 * (S::DU * K + S::DV),
+                got: 10
+            })
+        );
+
+        let (ek, dk) = kem.key_gen(&mut OsRng)?;
+        let (_, c) = kem.encaps(&ek, &mut OsRng)?;
+        assert_eq!(
+            decrypt::<K, S, KyberParams, _>(&PkeDecryptKey(dk.0), &c, &mut FailingRng).err(),
+            Some(Error::RngFailure)
+        );
+        Ok(())
+    }
+
+    /// Mask source that always fails
+    struct FailingRng;
+
+    impl RngCore for FailingRng {
+        fn next_u32(&mut self) -> u32 {
+            unreachable!()
+        }
+        fn next_u64(&mut self) -> u64 {
+            unreachable!()
+        }
+        fn fill_bytes(&mut self, _: &mut [u8]) {
+            unreachable!()
+        }
+        fn try_fill_bytes(&mut self, _: &mut [u8]) -> Result<(), rand::Error> {
+            Err(rand::Error::new("entropy source failed"))
+        }
+    }
+
+    impl CryptoRng for FailingRng {}
+
+    #[test]
+    fn basics() -> Result<(), Error> {
+        matches_unmasked::<2, Kyber512Params>()?;
+        matches_unmasked::<3, Kyber768Params>()?;
+        matches_unmasked::<4, Kyber1024Params>()
+    }
+}
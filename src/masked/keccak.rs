@@ -0,0 +1,200 @@
+//! Masked Keccak-f[1600] and sponge, for G, PRF and J on Boolean-shared inputs.
+
+use rand::RngCore;
+use zeroize::{Zeroize, Zeroizing};
+
+use super::{sec_and, Bool};
+use crate::errors::Error;
+
+/// SHA3-512 (G) : rate and domain separation suffix
+pub(super) const SHA3_512: (usize, u8) = (72, 0x06);
+/// SHAKE256 (PRF and J) : rate and domain separation suffix
+pub(super) const SHAKE256: (usize, u8) = (136, 0x1f);
+
+const ROUND_CONSTANTS: [u64; 24] = [
+    0x0000_0000_0000_0001,
+    0x0000_0000_0000_8082,
+    0x8000_0000_0000_808a,
+    0x8000_0000_8000_8000,
+    0x0000_0000_0000_808b,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8009,
+    0x0000_0000_0000_008a,
+    0x0000_0000_0000_0088,
+    0x0000_0000_8000_8009,
+    0x0000_0000_8000_000a,
+    0x0000_0000_8000_808b,
+    0x8000_0000_0000_008b,
+    0x8000_0000_0000_8089,
+    0x8000_0000_0000_8003,
+    0x8000_0000_0000_8002,
+    0x8000_0000_0000_0080,
+    0x0000_0000_0000_800a,
+    0x8000_0000_8000_000a,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8080,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8008,
+];
+
+/// ρ offsets of lane (x, y), at index x + 5y
+const ROTATIONS: [u32; 25] = [
+    0, 1, 62, 28, 27, 36, 44, 6, 55, 20, 3, 10, 43, 25, 39, 41, 45, 15, 21, 8, 18, 2, 61, 56, 14,
+];
+
+/// θ, ρ and π, which are linear : applied to each share on its own.
+fn theta_rho_pi(a: &mut [u64; 25]) {
+    let c: [u64; 5] = core::array::from_fn(|x| a[x] ^ a[x + 5] ^ a[x + 10] ^ a[x + 15] ^ a[x + 20]);
+    for x in 0..5 {
+        let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+        for y in 0..5 {
+            a[x + 5 * y] ^= d;
+        }
+    }
+
+    let mut b = [0u64; 25];
+    for x in 0..5 {
+        for y in 0..5 {
+            b[y + 5 * ((2 * x + 3 * y) % 5)] = a[x + 5 * y].rotate_left(ROTATIONS[x + 5 * y]);
+        }
+    }
+    *a = b;
+}
+
+/// χ : a[x] ^= !a[x + 1] & a[x + 2] on each row, with secure ANDs.
+fn chi<R: RngCore>(state: &mut [[u64; 25]; 2], rng: &mut R) -> Result<(), Error> {
+    for y in 0..5 {
+        let row: [Bool<u64>; 5] =
+            core::array::from_fn(|x| Bool(state[0][x + 5 * y], state[1][x + 5 * y]));
+        for x in 0..5 {
+            let t = sec_and(row[(x + 1) % 5].not(), row[(x + 2) % 5], rng)?;
+            state[0][x + 5 * y] = row[x].0 ^ t.0;
+            state[1][x + 5 * y] = row[x].1 ^ t.1;
+        }
+    }
+    Ok(())
+}
+
+/// Keccak-f[1600] on a Boolean-shared state
+fn permute<R: RngCore>(state: &mut [[u64; 25]; 2], rng: &mut R) -> Result<(), Error> {
+    for rc in ROUND_CONSTANTS {
+        theta_rho_pi(&mut state[0]);
+        theta_rho_pi(&mut state[1]);
+        chi(state, rng)?;
+        state[0][0] ^= rc;
+    }
+    Ok(())
+}
+
+/// Keccak sponge of `secret || public`, `secret` given by its Boolean shares : returns the
+/// Boolean shares of the first `out_len` output bytes.
+pub(super) fn sponge<R: RngCore>(
+    (rate, suffix): (usize, u8),
+    secret: &[[u8; 32]; 2],
+    public: &[u8],
+    out_len: usize,
+    rng: &mut R,
+) -> Result<[Vec<u8>; 2], Error> {
+    let len = 32 + public.len();
+    let padded_len = (len / rate + 1) * rate;
+
+    let mut messages = Zeroizing::new([vec![0u8; padded_len], vec![0u8; padded_len]]);
+    messages[0][..32].copy_from_slice(&secret[0]);
+    messages[1][..32].copy_from_slice(&secret[1]);
+    messages[0][32..len].copy_from_slice(public);
+    messages[0][len] ^= suffix;
+    messages[0][padded_len - 1] ^= 0x80;
+
+    let mut state = Zeroizing::new([[0u64; 25]; 2]);
+    for block in 0..padded_len / rate {
+        for (share, message) in state.iter_mut().zip(messages.iter()) {
+            let bytes = &message[block * rate..(block + 1) * rate];
+            for (lane, chunk) in share.iter_mut().zip(bytes.chunks_exact(8)) {
+                *lane ^= u64::from_le_bytes(chunk.try_into().unwrap());
+            }
+        }
+        permute(&mut state, rng)?;
+    }
+
+    let mut out = [Vec::with_capacity(out_len), Vec::with_capacity(out_len)];
+    loop {
+        for (bytes, share) in out.iter_mut().zip(state.iter()) {
+            for lane in &share[..rate / 8] {
+                bytes.extend_from_slice(&lane.to_le_bytes());
+            }
+        }
+        if out[0].len() >= out_len {
+            break;
+        }
+        if let Err(error) = permute(&mut state, rng) {
+            out.zeroize();
+            return Err(error);
+        }
+    }
+
+    out[0].truncate(out_len);
+    out[1].truncate(out_len);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::{prf, G, J};
+    use crate::masked::MaskSource;
+
+    fn shared(x: &[u8; 32], mask: u8) -> [[u8; 32]; 2] {
+        [x.map(|b| b ^ mask), [mask; 32]]
+    }
+
+    fn unmask(shares: &[Vec<u8>; 2]) -> Vec<u8> {
+        shares[0]
+            .iter()
+            .zip(&shares[1])
+            .map(|(a, b)| a ^ b)
+            .collect()
+    }
+
+    #[test]
+    fn basics() -> Result<(), Error> {
+        let mut rng = MaskSource::new();
+        let secret = [0x3cu8; 32];
+        let public: Vec<u8> = (0..=255).collect();
+
+        let (a, b) = G::evaluate(&[secret.as_slice(), &public[..32]].concat());
+        let g = sponge(
+            SHA3_512,
+            &shared(&secret, 0xa5),
+            &public[..32],
+            64,
+            &mut rng,
+        )?;
+        assert_eq!(unmask(&g), [a, b].concat());
+
+        let j = sponge(SHAKE256, &shared(&secret, 0x5a), &public, 32, &mut rng)?;
+        assert_eq!(
+            unmask(&j),
+            J::evaluate(&[secret.as_slice(), &public].concat())
+        );
+
+        for eta in [2, 3] {
+            let out = sponge(SHAKE256, &shared(&secret, 0x81), &[7], 64 * eta, &mut rng)?;
+            assert_eq!(unmask(&out), prf(eta, &secret, &[7]).unwrap());
+        }
+
+        // padding suffix and final bit in the same byte
+        let j = sponge(
+            SHAKE256,
+            &shared(&secret, 0x11),
+            &public[..103],
+            32,
+            &mut rng,
+        )?;
+        assert_eq!(
+            unmask(&j),
+            J::evaluate(&[secret.as_slice(), &public[..103]].concat())
+        );
+        Ok(())
+    }
+}
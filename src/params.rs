@@ -28,3 +28,48 @@ impl SecurityLevel for Kyber1024Params {
     const DU: usize = 11;
     const DV: usize = 5;
 }
+
+/// The three parameter sets approved by FIPS 203 (Table 2).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParameterSet {
+    MlKem512,
+    MlKem768,
+    MlKem1024,
+}
+
+impl ParameterSet {
+    /// Returns the approved parameter set matching `K` and `S`, if any.
+    pub fn from_params<const K: usize, S: SecurityLevel>() -> Option<Self> {
+        let set = match K {
+            2 => ParameterSet::MlKem512,
+            3 => ParameterSet::MlKem768,
+            4 => ParameterSet::MlKem1024,
+            _ => return None,
+        };
+        let (eta1, eta2, du, dv) = match set {
+            ParameterSet::MlKem512 => (3, 2, 10, 4),
+            ParameterSet::MlKem768 => (2, 2, 10, 4),
+            ParameterSet::MlKem1024 => (2, 2, 11, 5),
+        };
+
+        (S::ETA1 == eta1 && S::ETA2 == eta2 && S::DU == du && S::DV == dv).then_some(set)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ParameterSet::MlKem512 => "ML-KEM-512",
+            ParameterSet::MlKem768 => "ML-KEM-768",
+            ParameterSet::MlKem1024 => "ML-KEM-1024",
+        }
+    }
+
+    /// Returns the parameter set of module rank `k`, if any.
+    pub(crate) fn from_k(k: usize) -> Option<Self> {
+        match k {
+            2 => Some(ParameterSet::MlKem512),
+            3 => Some(ParameterSet::MlKem768),
+            4 => Some(ParameterSet::MlKem1024),
+            _ => None,
+        }
+    }
+}
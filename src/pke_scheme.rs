@@ -1,4 +1,7 @@
 use core::array;
+use core::fmt;
+
+use subtle::{Choice, ConstantTimeEq};
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
 use crate::constants::PolyParams;
@@ -27,10 +30,74 @@ impl<const K: usize, S: SecurityLevel, P: PolyParams> Default for KPke<K, S, P>
     }
 }
 
+/// K-PKE decryption key dk = ByteEncode_12(ŝ)
+///
+/// `Debug` is redacted, `==` is constant time, and copies are made with [`Self::clone_secret`].
 #[derive(Zeroize, ZeroizeOnDrop)]
-pub struct PkeDecryptKey<const K: usize>(pub [[u8; 384]; K]);
+pub struct PkeDecryptKey<const K: usize>(pub(crate) [[u8; 384]; K]);
+
+impl<const K: usize> PkeDecryptKey<K> {
+    /// ByteEncode_12(ŝ)
+    pub fn s_hat(&self) -> &[[u8; 384]; K] {
+        &self.0
+    }
+
+    /// Copy of the key, wiped on drop like the original.
+    pub fn clone_secret(&self) -> Self {
+        PkeDecryptKey(self.0)
+    }
+}
+
+impl<const K: usize> ConstantTimeEq for PkeDecryptKey<K> {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.0.as_flattened().ct_eq(other.0.as_flattened())
+    }
+}
 
-pub struct PkeEncryptKey<const K: usize>(pub [[u8; 384]; K], pub [u8; 32]);
+impl<const K: usize> PartialEq for PkeDecryptKey<K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl<const K: usize> Eq for PkeDecryptKey<K> {}
+
+impl<const K: usize> fmt::Debug for PkeDecryptKey<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PkeDecryptKey(<redacted>)")
+    }
+}
+
+/// K-PKE encryption key ek = ByteEncode_12(t̂) || ρ
+///
+/// `Debug` shows the first 8 bytes of H(ek).
+#[derive(Clone, PartialEq, Eq)]
+pub struct PkeEncryptKey<const K: usize>(pub(crate) [[u8; 384]; K], pub(crate) [u8; 32]);
+
+impl<const K: usize> PkeEncryptKey<K> {
+    /// ByteEncode_12(t̂)
+    pub fn t_hat(&self) -> &[[u8; 384]; K] {
+        &self.0
+    }
+
+    /// Seed ρ of the matrix Â
+    pub fn rho(&self) -> &[u8; 32] {
+        &self.1
+    }
+}
+
+impl<const K: usize> fmt::Debug for PkeEncryptKey<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut hasher = crate::hash::H::new();
+        for chunk in &self.0 {
+            hasher.absorb(chunk);
+        }
+        hasher.absorb(&self.1);
+        f.debug_struct("PkeEncryptKey")
+            .field("fingerprint", &hex::encode(&hasher.squeeze()[..8]))
+            .finish()
+    }
+}
 
 impl<const K: usize, S: SecurityLevel, P: PolyParams> PkeScheme for KPke<K, S, P> {
     type DecryptKey = PkeDecryptKey<K>;
@@ -42,11 +109,15 @@ impl<const K: usize, S: SecurityLevel, P: PolyParams> PkeScheme for KPke<K, S, P
     /// Output : (ek, dk) pair of encryption-decryption keys
     /// with : ek in B^(384*k + 32), and dk in B^(384*k)
     fn key_gen(&self, d: &[u8; 32]) -> Result<(Self::EncryptKey, Self::DecryptKey), Error> {
+        #[cfg(feature = "fips")]
+        crate::service_indicator::not_approved();
+
         let mut d_ext = [0u8; 33];
         d_ext[0..32].copy_from_slice(d);
         d_ext[32] = K as u8;
 
         let (rho, gamma) = G::evaluate(&d_ext);
+        declassify!(&rho);
 
         let mut n_var = 0u8;
 
@@ -117,6 +188,9 @@ impl<const K: usize, S: SecurityLevel, P: PolyParams> PkeScheme for KPke<K, S, P
     /// Input : randomness r in B^32
     /// Output : ciphertext c in B^(32 * (d_u * k + d_v))
     fn encrypt(&self, ek: &Self::EncryptKey, m: &[u8; 32], r: &[u8; 32]) -> Result<Vec<u8>, Error> {
+        #[cfg(feature = "fips")]
+        crate::service_indicator::not_approved();
+
         let mut n_var = 0usize;
         let mut t_ntt = Vec::with_capacity(K * K);
         for i in 0..K {
@@ -226,6 +300,16 @@ impl<const K: usize, S: SecurityLevel, P: PolyParams> PkeScheme for KPke<K, S, P
     /// Input : ciphertext c in B^(32 * (d_u*k + d_v))
     /// Output : message m in B^32
     fn decrypt(&self, dk: &Self::DecryptKey, c: &[u8]) -> Result<[u8; 32], Error> {
+        #[cfg(feature = "fips")]
+        crate::service_indicator::not_approved();
+
+        if c.len() != 32 * (S::DU * K + S::DV) {
+            return Err(Error::InvalidCiphertextLength {
+                expected: 32 * (S::DU * K + S::DV),
+                got: c.len(),
+            });
+        }
+
         let c_1 = &c[0..32 * S::DU * K];
         let c_2 = &c[32 * S::DU * K..];
 
@@ -290,6 +374,82 @@ impl<const K: usize, S: SecurityLevel, P: PolyParams> PkeScheme for KPke<K, S, P
     }
 }
 
+/// Kani proofs : K-PKE.Decrypt cannot panic on a ciphertext of the right length, whatever the
+/// key and ciphertext bytes, for each of ML-KEM-512, ML-KEM-768 and ML-KEM-1024.
+///
+/// Unrolled over symbolic bytes, `ByteDecode` and the NTTs are out of reach, so they are replaced
+/// by stubs returning any output the proofs of `conversion` and `polynomial` allow: coefficients
+/// below m from `byte_decode`, reduced coefficients from the transforms, whose butterflies keep
+/// reduced inputs reduced. Each stub asserts the precondition of the proof it stands for.
+#[cfg(kani)]
+mod verification {
+    use super::*;
+    use crate::constants::KyberParams;
+    use crate::params::{Kyber1024Params, Kyber512Params, Kyber768Params};
+
+    fn reduced_coeffs<P: PolyParams>() -> [i16; 256] {
+        array::from_fn(|_| kani::any_where(|x: &i16| (0..P::Q).contains(x)))
+    }
+
+    fn is_reduced<P: PolyParams>(coeffs: &[i16; 256]) -> bool {
+        coeffs.iter().all(|c| (0..P::Q).contains(c))
+    }
+
+    fn byte_decode_stub(bytes: &[u8], d: usize, q: i16, out: &mut [i16]) -> Result<(), Error> {
+        assert!((1..=12).contains(&d) && out.len() == bytes.len() * 8 / d);
+        let m = if d == 12 { q as i32 } else { 1 << d };
+        for f in out.iter_mut() {
+            *f = kani::any_where(|f: &i16| 0 <= *f && (*f as i32) < m);
+        }
+        Ok(())
+    }
+
+    fn to_ntt_stub<P: PolyParams>(f: &Polynomial<P>) -> PolynomialNTT<P> {
+        assert!(is_reduced::<P>(&f.coeffs));
+        PolynomialNTT::from(reduced_coeffs::<P>())
+    }
+
+    fn from_ntt_stub<P: PolyParams>(f: &PolynomialNTT<P>) -> Polynomial<P> {
+        assert!(is_reduced::<P>(&f.coeffs));
+        Polynomial::from(reduced_coeffs::<P>())
+    }
+
+    /// Decrypts any ciphertext of the right length (`C` bytes) with any key.
+    fn decrypt_is_ok<const K: usize, S: SecurityLevel, const C: usize>() {
+        let pke = KPke::<K, S, KyberParams>::new();
+        let dk = PkeDecryptKey::<K>(kani::any());
+        let c: [u8; C] = kani::any();
+        assert!(pke.decrypt(&dk, &c).is_ok());
+    }
+
+    #[kani::proof]
+    #[kani::unwind(257)]
+    #[kani::stub(byte_decode, byte_decode_stub)]
+    #[kani::stub(Polynomial::to_ntt, to_ntt_stub)]
+    #[kani::stub(Polynomial::from_ntt, from_ntt_stub)]
+    fn decrypt_never_panics_512() {
+        decrypt_is_ok::<2, Kyber512Params, 768>();
+    }
+
+    #[kani::proof]
+    #[kani::unwind(257)]
+    #[kani::stub(byte_decode, byte_decode_stub)]
+    #[kani::stub(Polynomial::to_ntt, to_ntt_stub)]
+    #[kani::stub(Polynomial::from_ntt, from_ntt_stub)]
+    fn decrypt_never_panics_768() {
+        decrypt_is_ok::<3, Kyber768Params, 1088>();
+    }
+
+    #[kani::proof]
+    #[kani::unwind(257)]
+    #[kani::stub(byte_decode, byte_decode_stub)]
+    #[kani::stub(Polynomial::to_ntt, to_ntt_stub)]
+    #[kani::stub(Polynomial::from_ntt, from_ntt_stub)]
+    fn decrypt_never_panics_1024() {
+        decrypt_is_ok::<4, Kyber1024Params, 1568>();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -55,6 +55,9 @@ impl<P: PolyParams> Polynomial<P> {
         if (eta != 2) && (eta != 3) {
             return Err(Error::InvalidEta);
         }
+        if b.len() != 64 * eta {
+            return Err(Error::InvalidInputLength);
+        }
 
         let mut coeffs = [0i16; 256];
         for (i, coeff) in coeffs.iter_mut().enumerate() {
@@ -87,9 +90,8 @@ impl<P: PolyParams> Polynomial<P> {
                 let zeta = zetas[i];
                 i += 1;
                 for j in start..(start + len) {
-                    let t = (zeta as i32 * coeffs[j + len] as i32).rem_euclid(P::Q as i32) as i16;
-                    coeffs[j + len] = (coeffs[j] - t).rem_euclid(P::Q);
-                    coeffs[j] = (coeffs[j] + t).rem_euclid(P::Q);
+                    (coeffs[j], coeffs[j + len]) =
+                        ntt_butterfly::<P>(zeta, coeffs[j], coeffs[j + len]);
                 }
             }
             len /= 2;
@@ -116,10 +118,8 @@ impl<P: PolyParams> Polynomial<P> {
                 let zeta = zetas[i];
                 i -= 1;
                 for j in start..(start + len) {
-                    let t = coeffs[j];
-                    coeffs[j] = (t + coeffs[j + len]).rem_euclid(P::Q);
-                    coeffs[j + len] =
-                        (zeta as i32 * (coeffs[j + len] - t) as i32).rem_euclid(P::Q as i32) as i16;
+                    (coeffs[j], coeffs[j + len]) =
+                        inv_ntt_butterfly::<P>(zeta, coeffs[j], coeffs[j + len]);
                 }
             }
             len *= 2;
@@ -205,6 +205,20 @@ impl<P: PolyParams> Index<usize> for Polynomial<P> {
     }
 }
 
+/// Butterfly of Algorithm 9 : (a + zeta * b, a - zeta * b) mod Q
+fn ntt_butterfly<P: PolyParams>(zeta: i16, a: i16, b: i16) -> (i16, i16) {
+    let t = (zeta as i32 * b as i32).rem_euclid(P::Q as i32) as i16;
+    ((a + t).rem_euclid(P::Q), (a - t).rem_euclid(P::Q))
+}
+
+/// Butterfly of Algorithm 10 : (a + b, zeta * (b - a)) mod Q
+fn inv_ntt_butterfly<P: PolyParams>(zeta: i16, a: i16, b: i16) -> (i16, i16) {
+    (
+        (a + b).rem_euclid(P::Q),
+        (zeta as i32 * (b - a) as i32).rem_euclid(P::Q as i32) as i16,
+    )
+}
+
 impl<P: PolyParams> IndexMut<usize> for Polynomial<P> {
     fn index_mut(&mut self, index: usize) -> &mut Self::Output {
         &mut self.coeffs[index]
@@ -291,18 +305,14 @@ impl<P: PolyParams> Mul for &PolynomialNTT<P> {
     fn mul(self, rhs: Self) -> Self::Output {
         let mut new_coeffs = [0i16; 256];
 
-        let zetas = P::zetas();
         for i in 0..128 {
-            let gamma = ((zetas[i] as i32 * zetas[i] as i32).rem_euclid(P::Q as i32)
-                * P::ZETA as i32)
-                .rem_euclid(P::Q as i32) as i16;
-            new_coeffs[2 * i] = (self[2 * i] as i32 * rhs[2 * i] as i32
-                + (self[2 * i + 1] as i32 * rhs[2 * i + 1] as i32).rem_euclid(P::Q as i32)
-                    * gamma as i32)
-                .rem_euclid(P::Q as i32) as i16;
-            new_coeffs[2 * i + 1] = (self[2 * i] as i32 * rhs[2 * i + 1] as i32
-                + self[2 * i + 1] as i32 * rhs[2 * i] as i32)
-                .rem_euclid(P::Q as i32) as i16;
+            (new_coeffs[2 * i], new_coeffs[2 * i + 1]) = base_case_multiply::<P>(
+                self[2 * i],
+                self[2 * i + 1],
+                rhs[2 * i],
+                rhs[2 * i + 1],
+                base_case_gamma::<P>(i),
+            );
         }
         PolynomialNTT::<P> {
             coeffs: new_coeffs,
@@ -311,6 +321,25 @@ impl<P: PolyParams> Mul for &PolynomialNTT<P> {
     }
 }
 
+/// gamma = zeta^(2 BitRev_7(i) + 1) mod Q, the modulus X^2 - gamma of the i-th base case
+fn base_case_gamma<P: PolyParams>(i: usize) -> i16 {
+    let zeta = P::zetas()[i] as i32;
+    ((zeta * zeta).rem_euclid(P::Q as i32) * P::ZETA as i32).rem_euclid(P::Q as i32) as i16
+}
+
+/// Algorithm 12 (FIPS 203) : BaseCaseMultiply(a0, a1, b0, b1, gamma)
+/// Computes the product of two degree-one polynomials with respect to a quadratic modulus
+///
+/// Input : a0, a1, b0, b1 in Z_Q, gamma in Z_Q
+/// Output : c0, c1 in Z_Q
+fn base_case_multiply<P: PolyParams>(a0: i16, a1: i16, b0: i16, b1: i16, gamma: i16) -> (i16, i16) {
+    let q = P::Q as i32;
+    let c0 = (a0 as i32 * b0 as i32 + (a1 as i32 * b1 as i32).rem_euclid(q) * gamma as i32)
+        .rem_euclid(q) as i16;
+    let c1 = (a0 as i32 * b1 as i32 + a1 as i32 * b0 as i32).rem_euclid(q) as i16;
+    (c0, c1)
+}
+
 impl<P: PolyParams> Index<usize> for PolynomialNTT<P> {
     type Output = i16;
     fn index(&self, index: usize) -> &Self::Output {
@@ -324,6 +353,64 @@ impl<P: PolyParams> IndexMut<usize> for PolynomialNTT<P> {
     }
 }
 
+/// Kani proofs : no overflow or out-of-range coefficient in the NTT and the NTT-domain product.
+/// Every coefficient of `to_ntt`, `from_ntt` and `Mul` goes through these helpers, whose loop
+/// indices do not depend on the data, so reduced inputs stay reduced through whole transforms.
+#[cfg(kani)]
+mod verification {
+    use super::*;
+    use crate::constants::KyberParams;
+
+    const Q: i16 = KyberParams::Q;
+
+    fn reduced() -> i16 {
+        kani::any_where(|x: &i16| (0..Q).contains(x))
+    }
+
+    fn zeta() -> i16 {
+        let i: usize = kani::any_where(|i: &usize| *i < 128);
+        KyberParams::zetas()[i]
+    }
+
+    #[kani::proof]
+    fn ntt_butterfly_stays_reduced() {
+        let (a, b) = ntt_butterfly::<KyberParams>(zeta(), reduced(), reduced());
+        assert!((0..Q).contains(&a) && (0..Q).contains(&b));
+    }
+
+    #[kani::proof]
+    fn inv_ntt_butterfly_stays_reduced() {
+        let (a, b) = inv_ntt_butterfly::<KyberParams>(zeta(), reduced(), reduced());
+        assert!((0..Q).contains(&a) && (0..Q).contains(&b));
+    }
+
+    #[kani::proof]
+    fn base_case_multiply_stays_reduced() {
+        let i: usize = kani::any_where(|i: &usize| *i < 128);
+        let gamma = base_case_gamma::<KyberParams>(i);
+        assert!((0..Q).contains(&gamma));
+        let (c0, c1) =
+            base_case_multiply::<KyberParams>(reduced(), reduced(), reduced(), reduced(), gamma);
+        assert!((0..Q).contains(&c0) && (0..Q).contains(&c1));
+    }
+
+    #[kani::proof]
+    #[kani::unwind(257)]
+    fn sample_poly_cbd_2_is_in_bounds() {
+        let b: [u8; 128] = kani::any();
+        let f = Polynomial::<KyberParams>::sample_poly_cbd(&b, 2).unwrap();
+        assert!(f.coeffs.iter().all(|c| (0..Q).contains(c)));
+    }
+
+    #[kani::proof]
+    #[kani::unwind(257)]
+    fn sample_poly_cbd_3_is_in_bounds() {
+        let b: [u8; 192] = kani::any();
+        let f = Polynomial::<KyberParams>::sample_poly_cbd(&b, 3).unwrap();
+        assert!(f.coeffs.iter().all(|c| (0..Q).contains(c)));
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -346,6 +433,11 @@ mod tests {
             KyberPoly::from_ntt(&(&p1.to_ntt() * &p2.to_ntt())).coeffs,
             (&p1 * &p2).coeffs
         );
+
+        assert_eq!(
+            KyberPoly::sample_poly_cbd(&[0u8; 64], 3).err(),
+            Some(Error::InvalidInputLength)
+        );
         Ok(())
     }
 }
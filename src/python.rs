@@ -0,0 +1,318 @@
+//! # Python bindings (feature `python`)
+//!
+//! Exposes `MlKem512`, `MlKem768` and `MlKem1024` classes to Python through PyO3, taking and
+//! returning `bytes`. Build the extension module with `maturin` (see `pyproject.toml`).
+//!
+//! Every [`Error`] is raised as a subclass of `kyber_nz.KyberError` (itself a `ValueError`).
+
+use pyo3::create_exception;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use rand::rngs::OsRng;
+use zeroize::Zeroizing;
+
+use crate::constants::KyberParams;
+use crate::errors::Error;
+use crate::kem_scheme::{KemDecapsKey, KemEncapsKey, MlKem};
+use crate::params::{Kyber1024Params, Kyber512Params, Kyber768Params, SecurityLevel};
+use crate::traits::KemScheme;
+
+create_exception!(
+    kyber_nz,
+    KyberError,
+    PyValueError,
+    "Base class of kyber-nz errors."
+);
+create_exception!(
+    kyber_nz,
+    InvalidInputLength,
+    KyberError,
+    "Input length is invalid."
+);
+create_exception!(kyber_nz, InvalidEta, KyberError, "Invalid value for Eta.");
+create_exception!(
+    kyber_nz,
+    InvalidBitWidth,
+    KyberError,
+    "Invalid value for d."
+);
+create_exception!(
+    kyber_nz,
+    InvalidEnvelope,
+    KyberError,
+    "Envelope is invalid."
+);
+create_exception!(
+    kyber_nz,
+    DecryptionFailed,
+    KyberError,
+    "Envelope authentication failed."
+);
+create_exception!(
+    kyber_nz,
+    ModulusCheckFailed,
+    KyberError,
+    "Encapsulation key failed the modulus check."
+);
+create_exception!(
+    kyber_nz,
+    HashCheckFailed,
+    KyberError,
+    "Decapsulation key failed the hash check."
+);
+
+create_exception!(
+    kyber_nz,
+    SelfTestFailed,
+    KyberError,
+    "Self-tests failed, the module refuses service."
+);
+create_exception!(
+    kyber_nz,
+    PairwiseConsistencyFailed,
+    KyberError,
+    "Key pair failed the pairwise consistency test."
+);
+create_exception!(
+    kyber_nz,
+    NotApproved,
+    KyberError,
+    "Service is not approved in FIPS mode."
+);
+create_exception!(
+    kyber_nz,
+    FaultDetected,
+    KyberError,
+    "A fault was detected, the output was wiped."
+);
+create_exception!(
+    kyber_nz,
+    MemoryLockFailed,
+    KyberError,
+    "Secret memory could not be locked."
+);
+create_exception!(
+    kyber_nz,
+    InvalidCiphertextLength,
+    InvalidInputLength,
+    "Ciphertext length is invalid."
+);
+create_exception!(
+    kyber_nz,
+    InvalidKeyLength,
+    InvalidInputLength,
+    "Key length is invalid."
+);
+create_exception!(
+    kyber_nz,
+    ParameterSetMismatch,
+    InvalidInputLength,
+    "Key or envelope of another parameter set."
+);
+create_exception!(
+    kyber_nz,
+    RngFailure,
+    KyberError,
+    "The random number generator failed."
+);
+create_exception!(
+    kyber_nz,
+    UnsupportedParameters,
+    KyberError,
+    "Parameters are not supported."
+);
+create_exception!(
+    kyber_nz,
+    EntropyHealthFailed,
+    KyberError,
+    "Entropy source failed its health tests, the module refuses service."
+);
+
+impl From<Error> for PyErr {
+    fn from(error: Error) -> PyErr {
+        let message = error.to_string();
+        match error {
+            Error::InvalidInputLength => InvalidInputLength::new_err(message),
+            Error::InvalidEta => InvalidEta::new_err(message),
+            Error::InvalidBitWidth => InvalidBitWidth::new_err(message),
+            Error::InvalidEnvelope => InvalidEnvelope::new_err(message),
+            Error::DecryptionFailed => DecryptionFailed::new_err(message),
+            Error::ModulusCheckFailed => ModulusCheckFailed::new_err(message),
+            Error::HashCheckFailed => HashCheckFailed::new_err(message),
+            Error::SelfTestFailed => SelfTestFailed::new_err(message),
+            Error::PairwiseConsistencyFailed => PairwiseConsistencyFailed::new_err(message),
+            Error::NotApproved => NotApproved::new_err(message),
+            Error::FaultDetected => FaultDetected::new_err(message),
+            Error::MemoryLockFailed => MemoryLockFailed::new_err(message),
+            Error::InvalidCiphertextLength { .. } => InvalidCiphertextLength::new_err(message),
+            Error::InvalidKeyLength { .. } => InvalidKeyLength::new_err(message),
+            Error::ParameterSetMismatch { .. } => ParameterSetMismatch::new_err(message),
+            Error::RngFailure => RngFailure::new_err(message),
+            Error::UnsupportedParameters => UnsupportedParameters::new_err(message),
+            Error::EntropyHealthFailed => EntropyHealthFailed::new_err(message),
+        }
+    }
+}
+
+fn seed(bytes: &[u8]) -> Result<&[u8; 32], Error> {
+    bytes.try_into().map_err(|_| Error::InvalidInputLength)
+}
+
+type KeyPair = (Vec<u8>, Zeroizing<Vec<u8>>);
+
+fn keygen_derand<const K: usize, S: SecurityLevel>(d: &[u8], z: &[u8]) -> Result<KeyPair, Error> {
+    let kem = MlKem::<K, S, KyberParams>::new();
+    let mut entropy = Zeroizing::new([0u8; 64]);
+    entropy[..32].copy_from_slice(seed(d)?);
+    entropy[32..].copy_from_slice(seed(z)?);
+    let (ek, dk) = kem.key_gen_from_entropy(&entropy)?;
+    Ok((ek.to_bytes(), dk.to_bytes()))
+}
+
+fn keygen<const K: usize, S: SecurityLevel>() -> Result<KeyPair, Error> {
+    let kem = MlKem::<K, S, KyberParams>::new();
+    let (ek, dk) = kem.key_gen(&mut OsRng)?;
+    Ok((ek.to_bytes(), dk.to_bytes()))
+}
+
+fn encaps<const K: usize, S: SecurityLevel>(
+    ek: &[u8],
+    m: Option<&[u8]>,
+) -> Result<([u8; 32], Vec<u8>), Error> {
+    let kem = MlKem::<K, S, KyberParams>::new();
+    let ek = KemEncapsKey::<K>::from_bytes(ek)?;
+    let (shared_secret, ct) = match m {
+        Some(m) => kem.encaps_from_entropy(&ek, seed(m)?)?,
+        None => kem.encaps(&ek, &mut OsRng)?,
+    };
+    Ok((shared_secret.0, ct))
+}
+
+fn decaps<const K: usize, S: SecurityLevel>(dk: &[u8], ct: &[u8]) -> Result<[u8; 32], Error> {
+    let kem = MlKem::<K, S, KyberParams>::new();
+    let dk = KemDecapsKey::<K>::from_bytes(dk)?;
+    Ok(kem.decaps(&dk, ct)?.0)
+}
+
+/// Defines the Python class `$name` for the parameter set (`$k`, `$params`).
+macro_rules! ml_kem_class {
+    ($name:ident, $k:literal, $params:ty) => {
+        #[pyclass(module = "kyber_nz", frozen)]
+        pub struct $name;
+
+        #[pymethods]
+        impl $name {
+            #[classattr]
+            const ENCAPS_KEY_SIZE: usize = 384 * $k + 32;
+            #[classattr]
+            const DECAPS_KEY_SIZE: usize = 768 * $k + 96;
+            #[classattr]
+            const CIPHERTEXT_SIZE: usize = 32 * (<$params>::DU * $k + <$params>::DV);
+            #[classattr]
+            const SHARED_SECRET_SIZE: usize = 32;
+
+            #[new]
+            fn new() -> Self {
+                $name
+            }
+
+            /// Generates a key pair, returning `(ek, dk)`.
+            fn keygen<'py>(
+                &self,
+                py: Python<'py>,
+            ) -> PyResult<(Bound<'py, PyBytes>, Bound<'py, PyBytes>)> {
+                let (ek, dk) = keygen::<$k, $params>()?;
+                Ok((PyBytes::new(py, &ek), PyBytes::new(py, &dk)))
+            }
+
+            /// Generates the key pair `(ek, dk)` derived from the 32-byte seeds `d` and `z`.
+            fn keygen_derand<'py>(
+                &self,
+                py: Python<'py>,
+                d: &[u8],
+                z: &[u8],
+            ) -> PyResult<(Bound<'py, PyBytes>, Bound<'py, PyBytes>)> {
+                let (ek, dk) = keygen_derand::<$k, $params>(d, z)?;
+                Ok((PyBytes::new(py, &ek), PyBytes::new(py, &dk)))
+            }
+
+            /// Encapsulates to `ek`, returning `(shared_secret, ciphertext)`.
+            fn encaps<'py>(
+                &self,
+                py: Python<'py>,
+                ek: &[u8],
+            ) -> PyResult<(Bound<'py, PyBytes>, Bound<'py, PyBytes>)> {
+                let (shared_secret, ct) = encaps::<$k, $params>(ek, None)?;
+                Ok((PyBytes::new(py, &shared_secret), PyBytes::new(py, &ct)))
+            }
+
+            /// Encapsulates to `ek` with the 32-byte seed `m`, returning
+            /// `(shared_secret, ciphertext)`.
+            fn encaps_derand<'py>(
+                &self,
+                py: Python<'py>,
+                ek: &[u8],
+                m: &[u8],
+            ) -> PyResult<(Bound<'py, PyBytes>, Bound<'py, PyBytes>)> {
+                let (shared_secret, ct) = encaps::<$k, $params>(ek, Some(m))?;
+                Ok((PyBytes::new(py, &shared_secret), PyBytes::new(py, &ct)))
+            }
+
+            /// Decapsulates `ct` with `dk`, returning the shared secret.
+            fn decaps<'py>(
+                &self,
+                py: Python<'py>,
+                dk: &[u8],
+                ct: &[u8],
+            ) -> PyResult<Bound<'py, PyBytes>> {
+                let shared_secret = decaps::<$k, $params>(dk, ct)?;
+                Ok(PyBytes::new(py, &shared_secret))
+            }
+        }
+    };
+}
+
+ml_kem_class!(MlKem512, 2, Kyber512Params);
+ml_kem_class!(MlKem768, 3, Kyber768Params);
+ml_kem_class!(MlKem1024, 4, Kyber1024Params);
+
+#[pymodule]
+fn kyber_nz(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = m.py();
+    m.add_class::<MlKem512>()?;
+    m.add_class::<MlKem768>()?;
+    m.add_class::<MlKem1024>()?;
+    m.add("KyberError", py.get_type::<KyberError>())?;
+    m.add("InvalidInputLength", py.get_type::<InvalidInputLength>())?;
+    m.add("InvalidEta", py.get_type::<InvalidEta>())?;
+    m.add("InvalidBitWidth", py.get_type::<InvalidBitWidth>())?;
+    m.add("SelfTestFailed", py.get_type::<SelfTestFailed>())?;
+    m.add("InvalidEnvelope", py.get_type::<InvalidEnvelope>())?;
+    m.add("DecryptionFailed", py.get_type::<DecryptionFailed>())?;
+    m.add("ModulusCheckFailed", py.get_type::<ModulusCheckFailed>())?;
+    m.add("HashCheckFailed", py.get_type::<HashCheckFailed>())?;
+    m.add(
+        "PairwiseConsistencyFailed",
+        py.get_type::<PairwiseConsistencyFailed>(),
+    )?;
+    m.add("NotApproved", py.get_type::<NotApproved>())?;
+    m.add("FaultDetected", py.get_type::<FaultDetected>())?;
+    m.add("MemoryLockFailed", py.get_type::<MemoryLockFailed>())?;
+    m.add(
+        "InvalidCiphertextLength",
+        py.get_type::<InvalidCiphertextLength>(),
+    )?;
+    m.add("InvalidKeyLength", py.get_type::<InvalidKeyLength>())?;
+    m.add(
+        "ParameterSetMismatch",
+        py.get_type::<ParameterSetMismatch>(),
+    )?;
+    m.add("RngFailure", py.get_type::<RngFailure>())?;
+    m.add(
+        "UnsupportedParameters",
+        py.get_type::<UnsupportedParameters>(),
+    )?;
+    m.add("EntropyHealthFailed", py.get_type::<EntropyHealthFailed>())?;
+    Ok(())
+}
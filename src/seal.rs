@@ -0,0 +1,609 @@
+//! # KEM-DEM public-key encryption (seal / open)
+//!
+//! Encrypts messages of arbitrary length to an ML-KEM encapsulation key:
+//! `ML-KEM.Encaps` produces a shared secret, an AEAD key is derived from it with `J` (SHAKE256),
+//! and the message is encrypted with AES-256-GCM or ChaCha20-Poly1305.
+//!
+//! ## Envelope format (version 1)
+//!
+//! ```text
+//! version (1) || parameter set (1) || aead (1) || reserved (1) || chunk size (4, big endian)
+//!   || ML-KEM ciphertext || chunk_0 || ... || chunk_n
+//! ```
+//!
+//! The message is split into chunks of `chunk size` bytes (at most [`MAX_CHUNK_SIZE`]), each
+//! encrypted separately following the STREAM construction: the nonce of chunk `i` is
+//! `0^7 || i (4, big endian) || last`, where `last` is 1 for the final chunk only. Every chunk but
+//! the final one holds exactly `chunk size` bytes of plaintext, so truncating, reordering or
+//! extending the envelope is detected. The header and the caller's associated data are
+//! authenticated with every chunk.
+//!
+//! With the streaming APIs ([`Sealer`], [`Opener`], [`MlKem::seal_io`], [`MlKem::open_io`]) the
+//! plaintext of a chunk is released as soon as that chunk is authenticated: if opening later fails,
+//! the output produced so far must be discarded.
+
+use std::io::{self, Read, Write};
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::Aes256Gcm;
+use chacha20poly1305::ChaCha20Poly1305;
+use rand::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+use crate::constants::PolyParams;
+use crate::errors::Error;
+use crate::hash::J;
+use crate::kem_scheme::{KemDecapsKey, KemEncapsKey, MlKem};
+use crate::params::{ParameterSet, SecurityLevel};
+use crate::traits::KemScheme;
+
+/// Current envelope format version.
+pub const VERSION: u8 = 1;
+
+/// Chunk size used by [`MlKem::seal`] and [`MlKem::seal_io`].
+pub const DEFAULT_CHUNK_SIZE: u32 = 64 * 1024;
+
+/// Largest chunk size accepted when sealing or opening. The chunk size of an envelope is read
+/// before anything is authenticated, so it bounds the buffers of [`MlKem::open_io`].
+pub const MAX_CHUNK_SIZE: u32 = 16 * 1024 * 1024;
+
+const HEADER_LEN: usize = 8;
+const TAG_LEN: usize = 16;
+const KDF_LABEL: &[u8] = b"kyber-nz seal v1";
+
+/// AEAD algorithm protecting the message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AeadAlgorithm {
+    Aes256Gcm,
+    ChaCha20Poly1305,
+}
+
+impl AeadAlgorithm {
+    fn id(self) -> u8 {
+        match self {
+            AeadAlgorithm::Aes256Gcm => 1,
+            AeadAlgorithm::ChaCha20Poly1305 => 2,
+        }
+    }
+
+    fn from_id(id: u8) -> Option<Self> {
+        match id {
+            1 => Some(AeadAlgorithm::Aes256Gcm),
+            2 => Some(AeadAlgorithm::ChaCha20Poly1305),
+            _ => None,
+        }
+    }
+}
+
+fn parameter_set_id(set: ParameterSet) -> u8 {
+    match set {
+        ParameterSet::MlKem512 => 1,
+        ParameterSet::MlKem768 => 2,
+        ParameterSet::MlKem1024 => 3,
+    }
+}
+
+fn parameter_set_from_id(id: u8) -> Option<ParameterSet> {
+    match id {
+        1 => Some(ParameterSet::MlKem512),
+        2 => Some(ParameterSet::MlKem768),
+        3 => Some(ParameterSet::MlKem1024),
+        _ => None,
+    }
+}
+
+struct Header {
+    parameter_set: ParameterSet,
+    aead: AeadAlgorithm,
+    chunk_size: u32,
+}
+
+impl Header {
+    fn to_bytes(&self) -> [u8; HEADER_LEN] {
+        let mut out = [0u8; HEADER_LEN];
+        out[0] = VERSION;
+        out[1] = parameter_set_id(self.parameter_set);
+        out[2] = self.aead.id();
+        out[4..].copy_from_slice(&self.chunk_size.to_be_bytes());
+        out
+    }
+
+    fn parse(bytes: &[u8], expected: ParameterSet) -> Result<Self, Error> {
+        if bytes.len() < HEADER_LEN {
+            return Err(Error::InvalidInputLength);
+        }
+        if bytes[0] != VERSION || bytes[3] != 0 {
+            return Err(Error::InvalidEnvelope);
+        }
+        if bytes[1] != parameter_set_id(expected) {
+            return Err(match parameter_set_from_id(bytes[1]) {
+                Some(got) => Error::ParameterSetMismatch { expected, got },
+                None => Error::InvalidEnvelope,
+            });
+        }
+        let aead = AeadAlgorithm::from_id(bytes[2]).ok_or(Error::InvalidEnvelope)?;
+        let chunk_size = u32::from_be_bytes(bytes[4..HEADER_LEN].try_into().unwrap());
+        if chunk_size == 0 || chunk_size > MAX_CHUNK_SIZE {
+            return Err(Error::InvalidEnvelope);
+        }
+
+        Ok(Header {
+            parameter_set: expected,
+            aead,
+            chunk_size,
+        })
+    }
+}
+
+enum Cipher {
+    Aes256Gcm(Box<Aes256Gcm>),
+    ChaCha20Poly1305(Box<ChaCha20Poly1305>),
+}
+
+/// Chunk encryption state shared by [`Sealer`] and [`Opener`].
+struct Stream {
+    cipher: Cipher,
+    aad: Vec<u8>,
+    chunk_size: usize,
+    counter: u32,
+}
+
+impl Stream {
+    /// Derives the AEAD key J(label || header || K || c) and binds `aad` to every chunk.
+    fn new(header: &Header, shared_secret: &[u8; 32], ciphertext: &[u8], aad: &[u8]) -> Self {
+        let header_bytes = header.to_bytes();
+        let mut key = {
+            let mut j_hasher = J::default();
+            j_hasher.absorb(KDF_LABEL);
+            j_hasher.absorb(&header_bytes);
+            j_hasher.absorb(shared_secret);
+            j_hasher.absorb(ciphertext);
+            j_hasher.squeeze()
+        };
+
+        let cipher = match header.aead {
+            AeadAlgorithm::Aes256Gcm => Cipher::Aes256Gcm(Box::new(Aes256Gcm::new(&key.into()))),
+            AeadAlgorithm::ChaCha20Poly1305 => {
+                Cipher::ChaCha20Poly1305(Box::new(ChaCha20Poly1305::new(&key.into())))
+            }
+        };
+        key.zeroize();
+
+        let mut chunk_aad = header_bytes.to_vec();
+        chunk_aad.extend_from_slice(aad);
+
+        Stream {
+            cipher,
+            aad: chunk_aad,
+            chunk_size: header.chunk_size as usize,
+            counter: 0,
+        }
+    }
+
+    fn next_nonce(&mut self, last: bool) -> Result<[u8; 12], Error> {
+        let mut nonce = [0u8; 12];
+        nonce[7..11].copy_from_slice(&self.counter.to_be_bytes());
+        nonce[11] = last as u8;
+        self.counter = self.counter.checked_add(1).ok_or(Error::InvalidEnvelope)?;
+        Ok(nonce)
+    }
+
+    fn encrypt(&mut self, chunk: &[u8], last: bool) -> Result<Vec<u8>, Error> {
+        let nonce = self.next_nonce(last)?;
+        let payload = Payload {
+            msg: chunk,
+            aad: &self.aad,
+        };
+        let result = match &self.cipher {
+            Cipher::Aes256Gcm(c) => c.encrypt(&nonce.into(), payload),
+            Cipher::ChaCha20Poly1305(c) => c.encrypt(&nonce.into(), payload),
+        };
+        result.map_err(|_| Error::InvalidEnvelope)
+    }
+
+    fn decrypt(&mut self, chunk: &[u8], last: bool) -> Result<Vec<u8>, Error> {
+        let nonce = self.next_nonce(last)?;
+        let payload = Payload {
+            msg: chunk,
+            aad: &self.aad,
+        };
+        let result = match &self.cipher {
+            Cipher::Aes256Gcm(c) => c.decrypt(&nonce.into(), payload),
+            Cipher::ChaCha20Poly1305(c) => c.decrypt(&nonce.into(), payload),
+        };
+        result.map_err(|_| Error::DecryptionFailed)
+    }
+}
+
+/// Incremental encryption of an envelope, one chunk at a time.
+pub struct Sealer(Stream);
+
+impl Sealer {
+    /// Number of plaintext bytes expected by [`Sealer::seal_chunk`].
+    pub fn chunk_size(&self) -> usize {
+        self.0.chunk_size
+    }
+
+    /// Encrypts a non-final chunk, which must hold exactly [`Sealer::chunk_size`] bytes.
+    pub fn seal_chunk(&mut self, chunk: &[u8]) -> Result<Vec<u8>, Error> {
+        if chunk.len() != self.0.chunk_size {
+            return Err(Error::InvalidInputLength);
+        }
+        self.0.encrypt(chunk, false)
+    }
+
+    /// Encrypts the final chunk, of at most [`Sealer::chunk_size`] bytes (possibly empty).
+    pub fn finish(mut self, chunk: &[u8]) -> Result<Vec<u8>, Error> {
+        if chunk.len() > self.0.chunk_size {
+            return Err(Error::InvalidInputLength);
+        }
+        self.0.encrypt(chunk, true)
+    }
+}
+
+/// Incremental decryption of an envelope, one chunk at a time.
+pub struct Opener {
+    stream: Stream,
+    sealed_chunk_size: usize,
+}
+
+impl Opener {
+    /// Number of envelope bytes expected by [`Opener::open_chunk`].
+    pub fn sealed_chunk_size(&self) -> usize {
+        self.sealed_chunk_size
+    }
+
+    /// Decrypts a non-final chunk, which must hold exactly [`Opener::sealed_chunk_size`] bytes.
+    pub fn open_chunk(&mut self, chunk: &[u8]) -> Result<Vec<u8>, Error> {
+        if chunk.len() != self.sealed_chunk_size() {
+            return Err(Error::InvalidInputLength);
+        }
+        self.stream.decrypt(chunk, false)
+    }
+
+    /// Decrypts the final chunk, of at most [`Opener::sealed_chunk_size`] bytes.
+    pub fn finish(mut self, chunk: &[u8]) -> Result<Vec<u8>, Error> {
+        if chunk.len() < TAG_LEN || chunk.len() > self.sealed_chunk_size() {
+            return Err(Error::InvalidInputLength);
+        }
+        self.stream.decrypt(chunk, true)
+    }
+}
+
+/// Reads until `buf` is full or the end of the input is reached, returning the bytes read.
+fn read_full<Rd: Read>(reader: &mut Rd, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+fn io_error(e: Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+impl<const K: usize, S: SecurityLevel, P: PolyParams> MlKem<K, S, P> {
+    /// Length of the envelope prefix (header and ML-KEM ciphertext) preceding the chunks.
+    pub fn envelope_prefix_len() -> usize {
+        HEADER_LEN + 32 * (S::DU * K + S::DV)
+    }
+
+    fn parameter_set() -> Result<ParameterSet, Error> {
+        ParameterSet::from_params::<K, S>().ok_or(Error::UnsupportedParameters)
+    }
+
+    /// Starts an envelope: returns its prefix (header and ML-KEM ciphertext), to be followed by
+    /// the chunks produced by the returned [`Sealer`].
+    pub fn seal_stream<R: RngCore + CryptoRng>(
+        &self,
+        ek: &KemEncapsKey<K>,
+        aad: &[u8],
+        aead: AeadAlgorithm,
+        chunk_size: u32,
+        rng: &mut R,
+    ) -> Result<(Vec<u8>, Sealer), Error> {
+        if chunk_size == 0 || chunk_size > MAX_CHUNK_SIZE {
+            return Err(Error::InvalidInputLength);
+        }
+        let header = Header {
+            parameter_set: Self::parameter_set()?,
+            aead,
+            chunk_size,
+        };
+
+        let (shared_secret, ciphertext) = self.encaps(ek, rng)?;
+        let stream = Stream::new(&header, &shared_secret.0, &ciphertext, aad);
+
+        let mut prefix = header.to_bytes().to_vec();
+        prefix.extend_from_slice(&ciphertext);
+        Ok((prefix, Sealer(stream)))
+    }
+
+    /// Parses an envelope prefix of [`MlKem::envelope_prefix_len`] bytes and returns the
+    /// [`Opener`] for the chunks that follow it.
+    pub fn open_stream(
+        &self,
+        dk: &KemDecapsKey<K>,
+        prefix: &[u8],
+        aad: &[u8],
+    ) -> Result<Opener, Error> {
+        if prefix.len() != Self::envelope_prefix_len() {
+            return Err(Error::InvalidInputLength);
+        }
+        let header = Header::parse(prefix, Self::parameter_set()?)?;
+        let ciphertext = &prefix[HEADER_LEN..];
+        let sealed_chunk_size = (header.chunk_size as usize)
+            .checked_add(TAG_LEN)
+            .ok_or(Error::InvalidEnvelope)?;
+
+        let shared_secret = self.decaps(dk, ciphertext)?;
+        Ok(Opener {
+            stream: Stream::new(&header, &shared_secret.0, ciphertext, aad),
+            sealed_chunk_size,
+        })
+    }
+
+    /// Encrypts `plaintext` to `ek`, authenticating `aad`.
+    pub fn seal<R: RngCore + CryptoRng>(
+        &self,
+        ek: &KemEncapsKey<K>,
+        plaintext: &[u8],
+        aad: &[u8],
+        aead: AeadAlgorithm,
+        rng: &mut R,
+    ) -> Result<Vec<u8>, Error> {
+        let (mut envelope, mut sealer) =
+            self.seal_stream(ek, aad, aead, DEFAULT_CHUNK_SIZE, rng)?;
+
+        let mut chunks = plaintext.chunks(sealer.chunk_size()).peekable();
+        while let Some(chunk) = chunks.next() {
+            if chunks.peek().is_none() {
+                envelope.extend(sealer.finish(chunk)?);
+                return Ok(envelope);
+            }
+            envelope.extend(sealer.seal_chunk(chunk)?);
+        }
+
+        envelope.extend(sealer.finish(&[])?);
+        Ok(envelope)
+    }
+
+    /// Decrypts an envelope produced by [`MlKem::seal`] or [`MlKem::seal_io`] with `dk`,
+    /// checking `aad`.
+    pub fn open(
+        &self,
+        dk: &KemDecapsKey<K>,
+        envelope: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        // an envelope of another parameter set is reported as such, whatever its length
+        Header::parse(envelope, Self::parameter_set()?)?;
+        let prefix_len = Self::envelope_prefix_len();
+        if envelope.len() < prefix_len + TAG_LEN {
+            return Err(Error::InvalidInputLength);
+        }
+
+        let mut opener = self.open_stream(dk, &envelope[..prefix_len], aad)?;
+        let mut rest = &envelope[prefix_len..];
+        let mut plaintext = Vec::with_capacity(rest.len());
+
+        while rest.len() > opener.sealed_chunk_size() {
+            let (chunk, tail) = rest.split_at(opener.sealed_chunk_size());
+            plaintext.extend(opener.open_chunk(chunk)?);
+            rest = tail;
+        }
+        plaintext.extend(opener.finish(rest)?);
+        Ok(plaintext)
+    }
+
+    /// Encrypts everything read from `reader` to `ek` and writes the envelope to `writer`,
+    /// holding at most two chunks in memory. Returns the number of plaintext bytes sealed.
+    pub fn seal_io<Rd: Read, W: Write, R: RngCore + CryptoRng>(
+        &self,
+        ek: &KemEncapsKey<K>,
+        reader: &mut Rd,
+        writer: &mut W,
+        aad: &[u8],
+        aead: AeadAlgorithm,
+        rng: &mut R,
+    ) -> io::Result<u64> {
+        let (prefix, mut sealer) = self
+            .seal_stream(ek, aad, aead, DEFAULT_CHUNK_SIZE, rng)
+            .map_err(io_error)?;
+        writer.write_all(&prefix)?;
+
+        let mut current = vec![0u8; sealer.chunk_size()];
+        let mut next = vec![0u8; sealer.chunk_size()];
+        let mut current_len = read_full(reader, &mut current)?;
+        let mut total = 0u64;
+
+        loop {
+            total += current_len as u64;
+            let next_len = if current_len == current.len() {
+                read_full(reader, &mut next)?
+            } else {
+                0
+            };
+
+            if next_len == 0 {
+                let sealed = sealer.finish(&current[..current_len]).map_err(io_error)?;
+                writer.write_all(&sealed)?;
+                current.zeroize();
+                next.zeroize();
+                return Ok(total);
+            }
+
+            writer.write_all(&sealer.seal_chunk(&current).map_err(io_error)?)?;
+            std::mem::swap(&mut current, &mut next);
+            current_len = next_len;
+        }
+    }
+
+    /// Decrypts an envelope read from `reader` with `dk` and writes the plaintext to `writer`,
+    /// holding at most two chunks in memory. Returns the number of plaintext bytes written.
+    ///
+    /// On error, the plaintext already written must be discarded.
+    pub fn open_io<Rd: Read, W: Write>(
+        &self,
+        dk: &KemDecapsKey<K>,
+        reader: &mut Rd,
+        writer: &mut W,
+        aad: &[u8],
+    ) -> io::Result<u64> {
+        let mut prefix = vec![0u8; Self::envelope_prefix_len()];
+        if read_full(reader, &mut prefix)? != prefix.len() {
+            return Err(io_error(Error::InvalidInputLength));
+        }
+        let mut opener = self.open_stream(dk, &prefix, aad).map_err(io_error)?;
+
+        let mut current = vec![0u8; opener.sealed_chunk_size()];
+        let mut next = vec![0u8; opener.sealed_chunk_size()];
+        let mut current_len = read_full(reader, &mut current)?;
+        let mut total = 0u64;
+
+        loop {
+            let next_len = if current_len == current.len() {
+                read_full(reader, &mut next)?
+            } else {
+                0
+            };
+
+            if next_len == 0 {
+                let mut plaintext = opener.finish(&current[..current_len]).map_err(io_error)?;
+                writer.write_all(&plaintext)?;
+                total += plaintext.len() as u64;
+                plaintext.zeroize();
+                return Ok(total);
+            }
+
+            let mut plaintext = opener.open_chunk(&current).map_err(io_error)?;
+            writer.write_all(&plaintext)?;
+            total += plaintext.len() as u64;
+            plaintext.zeroize();
+
+            std::mem::swap(&mut current, &mut next);
+            current_len = next_len;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Kyber1024, Kyber512, Kyber768};
+    use rand::rngs::OsRng;
+
+    #[test]
+    fn basics() -> Result<(), Error> {
+        let kem = Kyber768::new();
+        let (ek, dk) = kem.key_gen(&mut OsRng)?;
+        let aad = b"kyber-nz envelope";
+
+        for aead in [AeadAlgorithm::Aes256Gcm, AeadAlgorithm::ChaCha20Poly1305] {
+            for len in [
+                0,
+                1,
+                DEFAULT_CHUNK_SIZE as usize,
+                2 * DEFAULT_CHUNK_SIZE as usize + 7,
+            ] {
+                let plaintext: Vec<u8> = (0..len).map(|i| i as u8).collect();
+                let envelope = kem.seal(&ek, &plaintext, aad, aead, &mut OsRng)?;
+                assert_eq!(kem.open(&dk, &envelope, aad)?, plaintext);
+
+                let mut streamed = Vec::new();
+                kem.seal_io(
+                    &ek,
+                    &mut plaintext.as_slice(),
+                    &mut streamed,
+                    aad,
+                    aead,
+                    &mut OsRng,
+                )
+                .unwrap();
+                assert_eq!(kem.open(&dk, &streamed, aad)?, plaintext);
+
+                let mut opened = Vec::new();
+                kem.open_io(&dk, &mut envelope.as_slice(), &mut opened, aad)
+                    .unwrap();
+                assert_eq!(opened, plaintext);
+            }
+        }
+
+        let plaintext = vec![0x42u8; DEFAULT_CHUNK_SIZE as usize + 10];
+        let envelope = kem.seal(&ek, &plaintext, aad, AeadAlgorithm::Aes256Gcm, &mut OsRng)?;
+
+        assert_eq!(
+            kem.open(&dk, &envelope, b"other aad"),
+            Err(Error::DecryptionFailed)
+        );
+
+        let mut tampered = envelope.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert_eq!(kem.open(&dk, &tampered, aad), Err(Error::DecryptionFailed));
+
+        let mut downgraded = envelope.clone();
+        downgraded[2] = AeadAlgorithm::ChaCha20Poly1305.id();
+        assert_eq!(
+            kem.open(&dk, &downgraded, aad),
+            Err(Error::DecryptionFailed)
+        );
+
+        let truncated =
+            &envelope[..Kyber768::envelope_prefix_len() + DEFAULT_CHUNK_SIZE as usize + 16];
+        assert_eq!(kem.open(&dk, truncated, aad), Err(Error::DecryptionFailed));
+
+        let (ek_512, _) = Kyber512::new().key_gen(&mut OsRng)?;
+        let envelope_512 =
+            Kyber512::new().seal(&ek_512, b"m", aad, AeadAlgorithm::Aes256Gcm, &mut OsRng)?;
+        let (_, dk_1024) = Kyber1024::new().key_gen(&mut OsRng)?;
+        assert_eq!(
+            Kyber1024::new().open(&dk_1024, &envelope_512, aad),
+            Err(Error::ParameterSetMismatch {
+                expected: ParameterSet::MlKem1024,
+                got: ParameterSet::MlKem512
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn chunk_size_limit() -> Result<(), Error> {
+        let kem = Kyber768::new();
+        let (ek, dk) = kem.key_gen(&mut OsRng)?;
+        let aead = AeadAlgorithm::ChaCha20Poly1305;
+        let envelope = kem.seal(&ek, b"m", b"", aead, &mut OsRng)?;
+
+        for chunk_size in [MAX_CHUNK_SIZE + 1, u32::MAX] {
+            assert_eq!(
+                kem.seal_stream(&ek, b"", aead, chunk_size, &mut OsRng)
+                    .err(),
+                Some(Error::InvalidInputLength)
+            );
+
+            // A forged header must be rejected before any chunk buffer is allocated.
+            let mut forged = envelope.clone();
+            forged[4..HEADER_LEN].copy_from_slice(&chunk_size.to_be_bytes());
+            assert_eq!(kem.open(&dk, &forged, b""), Err(Error::InvalidEnvelope));
+            let err = kem
+                .open_io(&dk, &mut forged.as_slice(), &mut Vec::new(), b"")
+                .unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+
+        let mut forged = envelope.clone();
+        forged[4..HEADER_LEN].copy_from_slice(&MAX_CHUNK_SIZE.to_be_bytes());
+        assert_eq!(kem.open(&dk, &forged, b""), Err(Error::DecryptionFailed));
+
+        // The chunk counter of the nonce cannot wrap around.
+        let (_, mut sealer) = kem.seal_stream(&ek, b"", aead, 1, &mut OsRng)?;
+        sealer.0.counter = u32::MAX;
+        assert_eq!(sealer.seal_chunk(b"m"), Err(Error::InvalidEnvelope));
+        Ok(())
+    }
+}
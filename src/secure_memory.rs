@@ -0,0 +1,309 @@
+//! # Locked, guarded memory for secrets (feature `secure-memory`)
+//!
+//! [`Locked`] keeps a decapsulation key or a shared secret in its own anonymous mapping:
+//!
+//! ```text
+//! | guard page | canary ... value | guard page |
+//! ```
+//!
+//! - the pages holding the value are locked with `mlock`, so they are never written to swap,
+//!   and excluded from core dumps with `MADV_DONTDUMP` (Linux);
+//! - the guard pages around them are `PROT_NONE`, and the value ends against the upper one, so
+//!   a linear overflow or underflow from a neighbouring buffer faults instead of reading it;
+//! - the value pages are themselves `PROT_NONE` outside of [`Locked::with`], which maps them
+//!   read-only for the duration of the closure;
+//! - a random canary sits right before the value, and is checked on every access and on drop;
+//! - on drop, the value pages are wiped, unlocked and unmapped.
+//!
+//! The value is only reachable through [`Locked::with`]: there is no `Deref`, `Clone` or
+//! accessor returning a reference that outlives the borrow.
+//!
+//! ## Policy on failure
+//!
+//! [`Locked::new`] fails closed: if the mapping cannot be created, protected, locked or
+//! excluded from dumps, the value is wiped and [`Error::MemoryLockFailed`] is returned; if the
+//! canary cannot be drawn from `OsRng`, [`Error::RngFailure`] is. There is no fallback to
+//! unlocked memory. Each `Locked` locks one page or more, counted against
+//! `RLIMIT_MEMLOCK` (`ulimit -l`); raise it, or grant `CAP_IPC_LOCK`, for many live secrets.
+//!
+//! A corrupted canary, or a failure to change the protection of a mapping that is already
+//! locked, means the memory of the process can no longer be trusted: the process aborts.
+//!
+//! The value still passes through the stack when it is computed, before [`Locked::new`] moves
+//! it in and wipes the source. `Locked` is `Send` but not `Sync`: share it between threads
+//! behind a `Mutex`.
+
+use core::cell::Cell;
+use core::fmt;
+use core::marker::PhantomData;
+use core::ptr::{self, NonNull};
+
+use rand::rngs::OsRng;
+use rand::RngCore;
+use subtle::ConstantTimeEq;
+use zeroize::Zeroize;
+
+use crate::entropy_health::rng_error;
+use crate::errors::Error;
+use crate::kem_scheme::{KemDecapsKey, KemSharedSecret};
+
+const CANARY_LEN: usize = 16;
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Secrets that can be kept in [`Locked`] memory. They only hold byte arrays, so moving them
+/// bit for bit and wiping the source is sound.
+pub trait Secret: Zeroize + sealed::Sealed {}
+
+impl<const K: usize> sealed::Sealed for KemDecapsKey<K> {}
+impl<const K: usize> Secret for KemDecapsKey<K> {}
+impl sealed::Sealed for KemSharedSecret {}
+impl Secret for KemSharedSecret {}
+
+/// A secret in locked, guarded pages (see the [module documentation](self)).
+pub struct Locked<T: Secret> {
+    /// Start of the mapping (lower guard page)
+    base: NonNull<u8>,
+    page: usize,
+    /// Length of the value pages, between the two guard pages
+    data_len: usize,
+    value: NonNull<T>,
+    canary: [u8; CANARY_LEN],
+    /// Number of live borrows, the value pages are readable while it is not 0
+    readers: Cell<usize>,
+    _marker: PhantomData<T>,
+}
+
+// The mapping is owned by the `Locked`, and only accessed through it.
+unsafe impl<T: Secret + Send> Send for Locked<T> {}
+
+impl<T: Secret> Locked<T> {
+    /// Moves `value` into locked, guarded memory, and wipes the source.
+    pub fn new(mut value: T) -> Result<Self, Error> {
+        match Self::allocate() {
+            Ok(locked) => {
+                unsafe {
+                    locked.protect(libc::PROT_READ | libc::PROT_WRITE);
+                    ptr::copy_nonoverlapping(&value, locked.value.as_ptr(), 1);
+                    locked.protect(libc::PROT_NONE);
+                }
+                value.zeroize();
+                Ok(locked)
+            }
+            Err(e) => {
+                value.zeroize();
+                Err(e)
+            }
+        }
+    }
+
+    /// Calls `f` with a borrow of the secret, after checking the canary.
+    pub fn with<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        struct Borrow<'a, T: Secret>(&'a Locked<T>);
+
+        impl<T: Secret> Drop for Borrow<'_, T> {
+            fn drop(&mut self) {
+                let readers = self.0.readers.get() - 1;
+                self.0.readers.set(readers);
+                if readers == 0 {
+                    unsafe { self.0.protect(libc::PROT_NONE) };
+                }
+            }
+        }
+
+        if self.readers.get() == 0 {
+            unsafe { self.protect(libc::PROT_READ) };
+        }
+        self.readers.set(self.readers.get() + 1);
+        let borrow = Borrow(self);
+        self.check_canary();
+
+        let result = f(unsafe { borrow.0.value.as_ref() });
+        drop(borrow);
+        result
+    }
+
+    /// Maps the guard and value pages, and writes the canary. The value pages are left
+    /// `PROT_NONE`.
+    fn allocate() -> Result<Self, Error> {
+        let page = match unsafe { libc::sysconf(libc::_SC_PAGESIZE) } {
+            n if n > 0 => n as usize,
+            _ => return Err(Error::MemoryLockFailed),
+        };
+        let size = core::mem::size_of::<T>();
+        let data_len = (CANARY_LEN + size).div_ceil(page) * page;
+        let total = data_len + 2 * page;
+
+        // Drawn before the mapping exists, so that a failing RNG has nothing to release.
+        let mut canary = [0u8; CANARY_LEN];
+        OsRng.try_fill_bytes(&mut canary).map_err(rng_error)?;
+
+        let base = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                total,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        if base == libc::MAP_FAILED {
+            return Err(Error::MemoryLockFailed);
+        }
+        let Some(base) = NonNull::new(base.cast::<u8>()) else {
+            unsafe { libc::munmap(base, total) };
+            return Err(Error::MemoryLockFailed);
+        };
+        let data = unsafe { base.add(page) };
+
+        // The value ends against the upper guard page, its alignment permitting.
+        let offset = (data_len - size) / core::mem::align_of::<T>() * core::mem::align_of::<T>();
+
+        let locked = Locked {
+            base,
+            page,
+            data_len,
+            value: unsafe { data.add(offset) }.cast::<T>(),
+            canary,
+            readers: Cell::new(0),
+            _marker: PhantomData,
+        };
+
+        // From here on, dropping `locked` releases the mapping.
+        let (base, data) = (base.as_ptr(), data.as_ptr());
+        let ok = unsafe {
+            libc::mlock(data.cast(), data_len) == 0
+                && dont_dump(data, data_len)
+                && libc::mprotect(base.cast(), page, libc::PROT_NONE) == 0
+                && libc::mprotect(data.add(data_len).cast(), page, libc::PROT_NONE) == 0
+        };
+        if !ok {
+            locked.release();
+            core::mem::forget(locked);
+            return Err(Error::MemoryLockFailed);
+        }
+
+        unsafe {
+            ptr::copy_nonoverlapping(canary.as_ptr(), locked.canary_ptr(), CANARY_LEN);
+            locked.protect(libc::PROT_NONE);
+        }
+        Ok(locked)
+    }
+
+    fn data(&self) -> *mut u8 {
+        unsafe { self.base.as_ptr().add(self.page) }
+    }
+
+    fn canary_ptr(&self) -> *mut u8 {
+        unsafe { self.value.as_ptr().cast::<u8>().sub(CANARY_LEN) }
+    }
+
+    /// Whether the canary in front of the value is intact. The value pages must be readable.
+    fn canary_intact(&self) -> bool {
+        let stored = unsafe { core::slice::from_raw_parts(self.canary_ptr(), CANARY_LEN) };
+        stored.ct_eq(&self.canary).into()
+    }
+
+    fn check_canary(&self) {
+        if !self.canary_intact() {
+            std::process::abort();
+        }
+    }
+
+    /// Sets the protection of the value pages.
+    unsafe fn protect(&self, prot: libc::c_int) {
+        if libc::mprotect(self.data().cast(), self.data_len, prot) != 0 {
+            std::process::abort();
+        }
+    }
+
+    /// Wipes, unlocks and unmaps the value pages and their guards.
+    fn release(&self) {
+        unsafe {
+            self.protect(libc::PROT_READ | libc::PROT_WRITE);
+            core::slice::from_raw_parts_mut(self.data(), self.data_len).zeroize();
+            libc::munlock(self.data().cast(), self.data_len);
+            libc::munmap(self.base.as_ptr().cast(), self.data_len + 2 * self.page);
+        }
+    }
+}
+
+impl<T: Secret> Drop for Locked<T> {
+    fn drop(&mut self) {
+        unsafe { self.protect(libc::PROT_READ) };
+        self.check_canary();
+        self.release();
+    }
+}
+
+impl<T: Secret> fmt::Debug for Locked<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Locked(..)")
+    }
+}
+
+#[cfg(target_os = "linux")]
+unsafe fn dont_dump(data: *mut u8, len: usize) -> bool {
+    libc::madvise(data.cast(), len, libc::MADV_DONTDUMP) == 0
+}
+
+/// Other Unix systems have no portable way of excluding pages from core dumps.
+#[cfg(not(target_os = "linux"))]
+unsafe fn dont_dump(_data: *mut u8, _len: usize) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::KemScheme;
+    use crate::Kyber768;
+
+    #[test]
+    fn basics() -> Result<(), Error> {
+        let secret = Locked::new(KemSharedSecret([7u8; 32]))?;
+        assert_eq!(secret.with(|k| k.0), [7u8; 32]);
+        // nested borrows keep the pages readable
+        secret.with(|a| secret.with(|b| assert_eq!(a.0, b.0)));
+        assert_eq!(secret.readers.get(), 0);
+        assert_eq!(format!("{secret:?}"), "Locked(..)");
+
+        // the value ends against the upper guard page
+        let end = secret.value.as_ptr() as usize + core::mem::size_of::<KemSharedSecret>();
+        assert_eq!(end, secret.data() as usize + secret.data_len);
+        Ok(())
+    }
+
+    #[test]
+    fn canary_is_checked() -> Result<(), Error> {
+        let secret = Locked::new(KemSharedSecret([1u8; 32]))?;
+        secret.with(|_| unsafe {
+            secret.protect(libc::PROT_READ | libc::PROT_WRITE);
+            *secret.canary_ptr() ^= 1;
+            assert!(!secret.canary_intact());
+            *secret.canary_ptr() ^= 1;
+            assert!(secret.canary_intact());
+            secret.protect(libc::PROT_READ);
+        });
+        Ok(())
+    }
+
+    #[test]
+    fn kem() -> Result<(), Error> {
+        let kem = Kyber768::new();
+        let (ek, dk) = kem.key_gen_locked(&mut OsRng)?;
+        let (k, c) = kem.encaps(&ek, &mut OsRng)?;
+        let k_locked = kem.decaps_locked(&dk, &c)?;
+        k_locked.with(|k_prime| assert_eq!(k_prime.0, k.0));
+
+        // keys larger than a page
+        let kem = crate::Kyber1024::new();
+        let (ek, dk) = kem.key_gen_locked(&mut OsRng)?;
+        let (k, c) = kem.encaps(&ek, &mut OsRng)?;
+        assert_eq!(kem.decaps_locked(&dk, &c)?.with(|k| k.0), k.0);
+        Ok(())
+    }
+}
@@ -0,0 +1,252 @@
+//! # Self-tests (FIPS 140-3)
+//!
+//! Cryptographic algorithm self-tests (CASTs) of the ML-KEM module: known-answer tests of
+//! SHA3-256 (H), SHA3-512 (G), SHAKE128 (XOF) and SHAKE256 (J and PRF), of K-PKE, and of
+//! ML-KEM.KeyGen, Encaps and Decaps on ML-KEM-768, Decaps covering both a valid and an
+//! implicitly rejected ciphertext. Long outputs are compared through their SHA3-256 digest.
+//!
+//! [`run_self_tests`] runs them on demand. With the `fips` feature, `key_gen`, `encaps` and
+//! `decaps` run them before their first use; the outcome is tracked by [`SelfTestState`], and
+//! once a self-test has failed every service is refused with [`Error::SelfTestFailed`].
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use sha3::digest::{ExtendableOutput, Update, XofReader};
+use sha3::Shake128;
+
+use crate::constants::KyberParams;
+use crate::errors::Error;
+use crate::hash::{prf, G, H, J};
+use crate::kem_scheme::MlKem;
+use crate::params::Kyber768Params;
+use crate::pke_scheme::KPke;
+use crate::traits::{KemScheme, PkeScheme};
+
+/// State of the self-tests of the module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelfTestState {
+    /// Not run yet.
+    Untested,
+    /// Running.
+    Running,
+    /// All self-tests passed : the services are available.
+    Passed,
+    /// A self-test failed : the services are refused.
+    Failed,
+}
+
+static STATE: AtomicU8 = AtomicU8::new(SelfTestState::Untested as u8);
+
+/// Current state of the self-tests.
+pub fn state() -> SelfTestState {
+    match STATE.load(Ordering::Acquire) {
+        0 => SelfTestState::Untested,
+        1 => SelfTestState::Running,
+        2 => SelfTestState::Passed,
+        _ => SelfTestState::Failed,
+    }
+}
+
+/// Runs every self-test, and moves to [`SelfTestState::Passed`] or [`SelfTestState::Failed`].
+/// The failed state is permanent.
+///
+/// Only the first run goes through [`SelfTestState::Running`]: a re-run once they passed keeps
+/// [`SelfTestState::Passed`], so the services stay available to other threads, unless it fails.
+pub fn run_self_tests() -> Result<(), Error> {
+    if state() == SelfTestState::Failed {
+        return Err(Error::SelfTestFailed);
+    }
+    let _ = STATE.compare_exchange(
+        SelfTestState::Untested as u8,
+        SelfTestState::Running as u8,
+        Ordering::AcqRel,
+        Ordering::Acquire,
+    );
+
+    let result = hash_casts()
+        .and_then(|()| k_pke_cast())
+        .and_then(|()| ml_kem_cast())
+        .map_err(|_| Error::SelfTestFailed);
+
+    let state = match result {
+        Ok(()) => SelfTestState::Passed,
+        Err(_) => SelfTestState::Failed,
+    };
+    if STATE
+        .fetch_update(Ordering::AcqRel, Ordering::Acquire, |s| {
+            (s != SelfTestState::Failed as u8).then_some(state as u8)
+        })
+        .is_err()
+    {
+        return Err(Error::SelfTestFailed);
+    }
+    result
+}
+
+/// Runs the self-tests on first use, and refuses service unless they passed.
+#[cfg(feature = "fips")]
+pub(crate) fn ensure_self_tests() -> Result<(), Error> {
+    static POWER_ON: std::sync::Once = std::sync::Once::new();
+    POWER_ON.call_once(|| {
+        let _ = run_self_tests();
+    });
+    match state() {
+        SelfTestState::Passed => Ok(()),
+        _ => Err(Error::SelfTestFailed),
+    }
+}
+
+fn expect(actual: &[u8], expected: &str) -> Result<(), Error> {
+    if hex::encode(actual) == expected {
+        Ok(())
+    } else {
+        Err(Error::SelfTestFailed)
+    }
+}
+
+/// SHA3-256 digest of the concatenation of `parts`.
+fn digest(parts: &[&[u8]]) -> [u8; 32] {
+    let mut hasher = H::new();
+    for part in parts {
+        hasher.absorb(part);
+    }
+    hasher.squeeze()
+}
+
+/// Fixed inputs : `start`, `start + 1`, ..., `start + 31`.
+fn pattern(start: u8) -> [u8; 32] {
+    core::array::from_fn(|i| start + i as u8)
+}
+
+fn hash_casts() -> Result<(), Error> {
+    expect(
+        &H::evaluate(b"abc"),
+        "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532",
+    )?;
+
+    let (g_a, g_b) = G::evaluate(b"abc");
+    expect(
+        &[g_a, g_b].concat(),
+        "b751850b1a57168a5693cd924b6b096e08f621827444f70d884f5d0240d2712e\
+         10e116e9192af3c91a7ec57647e3934057340b4cf408d5a56592f8274eec53f0",
+    )?;
+
+    let mut xof = Shake128::default();
+    xof.update(b"abc");
+    let mut shake128 = [0u8; 32];
+    xof.finalize_xof().read(&mut shake128);
+    expect(
+        &shake128,
+        "5881092dd818bf5cf8a3ddb793fbcba74097d5c526a6d35f97b83351940f2cc8",
+    )?;
+
+    expect(
+        &J::evaluate(b"abc"),
+        "483366601360a8771c6863080cc4114d8db44530f8f1e1ee4f94ea37e78b5739",
+    )?;
+
+    expect(
+        &prf(2, &pattern(0), &[0])?,
+        "a421bc5578446c875756f5e7abee96cf101bab0cda143fa0e85ac03860483c3c\
+         5bc156b20a72d3c0babbe0f8a1fac7502099fc512c6a955065589fc1bd348072\
+         facec615b6c7a517d517a4731b29445e9355f73fdf7d9df50bba3dd1910b9ead\
+         985a7a7eb5699b2cc8f938ecd31f05871902e6b9895324efca3f62dda9e66320",
+    )
+}
+
+/// K-PKE.KeyGen, Encrypt and Decrypt (ML-KEM-768)
+fn k_pke_cast() -> Result<(), Error> {
+    let pke = KPke::<3, Kyber768Params, KyberParams>::new();
+    let (ek, dk) = pke.key_gen(&pattern(0))?;
+    expect(
+        &digest(&[ek.0.as_flattened(), &ek.1]),
+        "a24e16d8f8f9383a95b77050f4d9fd2f5733eec1d63ef3c23ebf9918173669a7",
+    )?;
+    expect(
+        &digest(&[dk.0.as_flattened()]),
+        "4c6954b8b0e3268ef19ae0eb25efdacdd504d6fa2afa6bb6f49f4a951716c96a",
+    )?;
+
+    let m = pattern(64);
+    let c = pke.encrypt(&ek, &m, &pattern(96))?;
+    expect(
+        &digest(&[&c]),
+        "aac7934f956d82466ff9ddce612236ba59802d7dc61cb16cd5c08a3a24b48ffe",
+    )?;
+    expect(&pke.decrypt(&dk, &c)?, &hex::encode(m))
+}
+
+/// ML-KEM.KeyGen_internal, Encaps_internal and Decaps (ML-KEM-768), with a valid and an
+/// implicitly rejected ciphertext. Decapsulation runs on the path of `decaps`, masked with the
+/// `masked` feature.
+fn ml_kem_cast() -> Result<(), Error> {
+    let kem = MlKem::<3, Kyber768Params, KyberParams>::new();
+    let (ek, dk) = kem.key_gen_internal(&pattern(0), &pattern(32))?;
+    expect(
+        &digest(&[&ek.to_bytes()]),
+        "a24e16d8f8f9383a95b77050f4d9fd2f5733eec1d63ef3c23ebf9918173669a7",
+    )?;
+    expect(
+        &digest(&[&dk.to_bytes()]),
+        "1149f17c3c4ac6ab1e3e2d9d8bd0171355ac0fa31bb8855c48ceade874c0864b",
+    )?;
+
+    let (k, c) = kem.encaps_internal(&ek, &pattern(64))?;
+    expect(
+        &digest(&[&c]),
+        "b4cfbd24cef67afd3764276c6980e0f88f8e9ca57f59b7f12fe1a9c1e72f4710",
+    )?;
+    let k_expected = "9cddd089ffe70e3996e76f7c8d06746df34d07e8657bc0fcf2bb0e1c3084aea1";
+    expect(&k.0, k_expected)?;
+    expect(&kem.decaps_checked(&dk, &c)?.0, k_expected)?;
+
+    let mut rejected = c;
+    rejected[0] ^= 1;
+    expect(
+        &kem.decaps_checked(&dk, &rejected)?.0,
+        "dcfc80c6db46ff7028e3a4398651c063ae7a42c107a6dc8cb07141861698ab92",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn basics() -> Result<(), Error> {
+        run_self_tests()?;
+        assert_eq!(state(), SelfTestState::Passed);
+        assert_eq!(expect(&[0xab], "ac"), Err(Error::SelfTestFailed));
+        Ok(())
+    }
+
+    #[cfg(feature = "fips")]
+    #[test]
+    fn services_are_gated() -> Result<(), Error> {
+        let kem = MlKem::<3, Kyber768Params, KyberParams>::new();
+        let (ek, dk) = kem.key_gen(&mut rand::rngs::OsRng)?;
+        assert_eq!(state(), SelfTestState::Passed);
+        let (k, c) = kem.encaps(&ek, &mut rand::rngs::OsRng)?;
+        assert_eq!(kem.decaps(&dk, &c)?.0, k.0);
+        Ok(())
+    }
+
+    #[cfg(feature = "fips")]
+    #[test]
+    fn rerun_keeps_services_available() -> Result<(), Error> {
+        let kem = MlKem::<3, Kyber768Params, KyberParams>::new();
+        run_self_tests()?;
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                for _ in 0..20 {
+                    run_self_tests().unwrap();
+                }
+            });
+            for _ in 0..200 {
+                assert_eq!(state(), SelfTestState::Passed);
+                kem.key_gen(&mut rand::rngs::OsRng).unwrap();
+            }
+        });
+        Ok(())
+    }
+}
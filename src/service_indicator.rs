@@ -0,0 +1,144 @@
+//! # Approved-service indicator (FIPS 140-3)
+//!
+//! With the `fips` feature, every service of the module records on the calling thread whether
+//! it ran as an approved service, and [`last`] reads it back after the call:
+//!
+//! - `key_gen`, `encaps` and `decaps` of [`MlKem`](crate::kem_scheme::MlKem) with one of the
+//!   three parameter sets of FIPS 203 are approved;
+//! - `key_gen_internal`, `encaps_internal` and `decaps_internal`, which take their randomness
+//!   from the caller and are meant for testing only (FIPS 203, 3.3), are not approved, nor are
+//!   the K-PKE functions of [`pke_scheme`](crate::pke_scheme);
+//! - a call that fails is not approved.
+//!
+//! In that mode, `key_gen`, `encaps` and `decaps` also refuse, with [`Error::NotApproved`], a
+//! `SecurityLevel` or `PolyParams` other than those of ML-KEM-512, ML-KEM-768 and ML-KEM-1024.
+//! Inputs of the wrong length are refused by `from_bytes` and `decaps` in every mode.
+//!
+//! Without the `fips` feature nothing is recorded, and [`last`] returns `None`.
+
+use core::cell::Cell;
+
+#[cfg(feature = "fips")]
+use crate::constants::PolyParams;
+#[cfg(feature = "fips")]
+use crate::errors::Error;
+#[cfg(feature = "fips")]
+use crate::params::{ParameterSet, SecurityLevel};
+
+/// Whether a service ran in approved mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceIndicator {
+    Approved,
+    NotApproved,
+}
+
+std::thread_local! {
+    static LAST: Cell<Option<ServiceIndicator>> = const { Cell::new(None) };
+}
+
+/// Indicator of the last service called on this thread, `None` if there was none since the
+/// last [`reset`].
+pub fn last() -> Option<ServiceIndicator> {
+    LAST.with(Cell::get)
+}
+
+/// Forgets the indicator of this thread.
+pub fn reset() {
+    LAST.with(|last| last.set(None));
+}
+
+/// Records a non-approved service, or the start of an approved one.
+#[cfg(feature = "fips")]
+pub(crate) fn not_approved() {
+    LAST.with(|last| last.set(Some(ServiceIndicator::NotApproved)));
+}
+
+/// Records the successful end of an approved service.
+#[cfg(feature = "fips")]
+pub(crate) fn approved() {
+    LAST.with(|last| last.set(Some(ServiceIndicator::Approved)));
+}
+
+/// Entry of an approved service : runs the self-tests on first use and refuses the parameter
+/// sets that FIPS 203 does not approve.
+#[cfg(feature = "fips")]
+pub(crate) fn enter<const K: usize, S: SecurityLevel, P: PolyParams>() -> Result<(), Error> {
+    not_approved();
+    crate::self_test::ensure_self_tests()?;
+
+    if P::N != 256 || P::Q != 3329 || ParameterSet::from_params::<K, S>().is_none() {
+        return Err(Error::NotApproved);
+    }
+    Ok(())
+}
+
+#[cfg(all(test, feature = "fips"))]
+mod tests {
+    use super::*;
+    use crate::constants::KyberParams;
+    use crate::kem_scheme::MlKem;
+    use crate::params::Kyber768Params;
+    use crate::pke_scheme::KPke;
+    use crate::traits::{KemScheme, PkeScheme};
+    use rand::rngs::OsRng;
+
+    struct Custom;
+    impl SecurityLevel for Custom {
+        const ETA1: usize = 3;
+        const ETA2: usize = 3;
+        const DU: usize = 10;
+        const DV: usize = 4;
+    }
+
+    #[test]
+    fn basics() -> Result<(), Error> {
+        let kem = MlKem::<3, Kyber768Params, KyberParams>::new();
+        reset();
+        assert_eq!(last(), None);
+
+        let (ek, dk) = kem.key_gen(&mut OsRng)?;
+        assert_eq!(last(), Some(ServiceIndicator::Approved));
+        let (k, c) = kem.encaps(&ek, &mut OsRng)?;
+        assert_eq!(last(), Some(ServiceIndicator::Approved));
+        assert_eq!(kem.decaps(&dk, &c)?.0, k.0);
+        assert_eq!(last(), Some(ServiceIndicator::Approved));
+
+        assert_eq!(
+            kem.decaps(&dk, &c[1..]).err(),
+            Some(Error::InvalidCiphertextLength {
+                expected: 1088,
+                got: 1087
+            })
+        );
+        assert_eq!(last(), Some(ServiceIndicator::NotApproved));
+
+        let (k, c) = kem.encaps_internal(&ek, &[7u8; 32])?;
+        assert_eq!(last(), Some(ServiceIndicator::NotApproved));
+        kem.decaps(&dk, &c)?;
+        assert_eq!(last(), Some(ServiceIndicator::Approved));
+        assert_eq!(kem.decaps_internal(&dk, &c)?.0, k.0);
+        assert_eq!(last(), Some(ServiceIndicator::NotApproved));
+
+        kem.key_gen_internal(&[1u8; 32], &[2u8; 32])?;
+        assert_eq!(last(), Some(ServiceIndicator::NotApproved));
+
+        kem.key_gen(&mut OsRng)?;
+        KPke::<3, Kyber768Params, KyberParams>::new().key_gen(&[3u8; 32])?;
+        assert_eq!(last(), Some(ServiceIndicator::NotApproved));
+        Ok(())
+    }
+
+    #[test]
+    fn custom_parameters_are_refused() -> Result<(), Error> {
+        let kem = MlKem::<3, Custom, KyberParams>::new();
+        assert_eq!(kem.key_gen(&mut OsRng).err(), Some(Error::NotApproved));
+        assert_eq!(last(), Some(ServiceIndicator::NotApproved));
+
+        let (ek, dk) = kem.key_gen_internal(&[1u8; 32], &[2u8; 32])?;
+        assert_eq!(kem.encaps(&ek, &mut OsRng).err(), Some(Error::NotApproved));
+        let (_, c) = kem.encaps_internal(&ek, &[3u8; 32])?;
+        assert_eq!(kem.decaps(&dk, &c).err(), Some(Error::NotApproved));
+        assert_eq!(last(), Some(ServiceIndicator::NotApproved));
+        Ok(())
+    }
+}
@@ -0,0 +1,181 @@
+//! # WebAssembly bindings (feature `wasm`)
+//!
+//! `wasm-bindgen` exports for `wasm32-unknown-unknown`, taking and returning `Uint8Array`s.
+//! Randomness comes from `crypto.getRandomValues` through the JS backend of `getrandom`.
+//!
+//! ```text
+//! wasm-pack build --target web -- --features wasm
+//! ```
+//!
+//! The generated TypeScript typings declare, for each parameter set (`mlKem512`, `mlKem768`,
+//! `mlKem1024`), the functions `<set>Keygen`, `<set>KeygenDerand`, `<set>Encaps`,
+//! `<set>EncapsDerand` and `<set>Decaps`. Errors are thrown as JavaScript `Error`s.
+
+use rand::rngs::OsRng;
+use wasm_bindgen::prelude::*;
+use zeroize::Zeroizing;
+
+use crate::constants::KyberParams;
+use crate::errors::Error;
+use crate::kem_scheme::{KemDecapsKey, KemEncapsKey, MlKem};
+use crate::params::{Kyber1024Params, Kyber512Params, Kyber768Params, SecurityLevel};
+use crate::traits::KemScheme;
+
+/// Encapsulation and decapsulation keys returned by the `*Keygen` functions.
+#[wasm_bindgen]
+pub struct KeyPair {
+    encapsulation_key: Vec<u8>,
+    decapsulation_key: Zeroizing<Vec<u8>>,
+}
+
+#[wasm_bindgen]
+impl KeyPair {
+    #[wasm_bindgen(getter, js_name = encapsulationKey)]
+    pub fn encapsulation_key(&self) -> Vec<u8> {
+        self.encapsulation_key.clone()
+    }
+
+    #[wasm_bindgen(getter, js_name = decapsulationKey)]
+    pub fn decapsulation_key(&self) -> Vec<u8> {
+        self.decapsulation_key.to_vec()
+    }
+}
+
+/// Shared secret and ciphertext returned by the `*Encaps` functions.
+#[wasm_bindgen]
+pub struct Encapsulation {
+    shared_secret: Vec<u8>,
+    ciphertext: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl Encapsulation {
+    #[wasm_bindgen(getter, js_name = sharedSecret)]
+    pub fn shared_secret(&self) -> Vec<u8> {
+        self.shared_secret.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn ciphertext(&self) -> Vec<u8> {
+        self.ciphertext.clone()
+    }
+}
+
+impl Drop for Encapsulation {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(&mut self.shared_secret);
+    }
+}
+
+fn seed(bytes: &[u8]) -> Result<&[u8; 32], Error> {
+    bytes.try_into().map_err(|_| Error::InvalidInputLength)
+}
+
+fn keygen<const K: usize, S: SecurityLevel>(
+    seeds: Option<(&[u8], &[u8])>,
+) -> Result<KeyPair, Error> {
+    let kem = MlKem::<K, S, KyberParams>::new();
+    let (ek, dk) = match seeds {
+        Some((d, z)) => {
+            let mut entropy = Zeroizing::new([0u8; 64]);
+            entropy[..32].copy_from_slice(seed(d)?);
+            entropy[32..].copy_from_slice(seed(z)?);
+            kem.key_gen_from_entropy(&entropy)?
+        }
+        None => kem.key_gen(&mut OsRng)?,
+    };
+    Ok(KeyPair {
+        encapsulation_key: ek.to_bytes(),
+        decapsulation_key: dk.to_bytes(),
+    })
+}
+
+fn encaps<const K: usize, S: SecurityLevel>(
+    ek: &[u8],
+    m: Option<&[u8]>,
+) -> Result<Encapsulation, Error> {
+    let kem = MlKem::<K, S, KyberParams>::new();
+    let ek = KemEncapsKey::<K>::from_bytes(ek)?;
+    let (shared_secret, ciphertext) = match m {
+        Some(m) => kem.encaps_from_entropy(&ek, seed(m)?)?,
+        None => kem.encaps(&ek, &mut OsRng)?,
+    };
+    Ok(Encapsulation {
+        shared_secret: shared_secret.0.to_vec(),
+        ciphertext,
+    })
+}
+
+fn decaps<const K: usize, S: SecurityLevel>(dk: &[u8], ct: &[u8]) -> Result<Vec<u8>, Error> {
+    let kem = MlKem::<K, S, KyberParams>::new();
+    let dk = KemDecapsKey::<K>::from_bytes(dk)?;
+    Ok(kem.decaps(&dk, ct)?.0.to_vec())
+}
+
+/// Defines the exported functions of one parameter set, with their JavaScript names.
+macro_rules! ml_kem_functions {
+    ($k:literal, $params:ty,
+     $keygen:ident = $keygen_js:literal,
+     $keygen_derand:ident = $keygen_derand_js:literal,
+     $encaps:ident = $encaps_js:literal,
+     $encaps_derand:ident = $encaps_derand_js:literal,
+     $decaps:ident = $decaps_js:literal) => {
+        /// Generates a key pair.
+        #[wasm_bindgen(js_name = $keygen_js)]
+        pub fn $keygen() -> Result<KeyPair, JsError> {
+            Ok(keygen::<$k, $params>(None)?)
+        }
+
+        /// Generates the key pair derived from the 32-byte seeds `d` and `z`.
+        #[wasm_bindgen(js_name = $keygen_derand_js)]
+        pub fn $keygen_derand(d: &[u8], z: &[u8]) -> Result<KeyPair, JsError> {
+            Ok(keygen::<$k, $params>(Some((d, z)))?)
+        }
+
+        /// Encapsulates a fresh shared secret to `ek`.
+        #[wasm_bindgen(js_name = $encaps_js)]
+        pub fn $encaps(ek: &[u8]) -> Result<Encapsulation, JsError> {
+            Ok(encaps::<$k, $params>(ek, None)?)
+        }
+
+        /// Encapsulates to `ek` with the 32-byte seed `m`.
+        #[wasm_bindgen(js_name = $encaps_derand_js)]
+        pub fn $encaps_derand(ek: &[u8], m: &[u8]) -> Result<Encapsulation, JsError> {
+            Ok(encaps::<$k, $params>(ek, Some(m))?)
+        }
+
+        /// Decapsulates `ct` with `dk`, returning the shared secret.
+        #[wasm_bindgen(js_name = $decaps_js)]
+        pub fn $decaps(dk: &[u8], ct: &[u8]) -> Result<Vec<u8>, JsError> {
+            Ok(decaps::<$k, $params>(dk, ct)?)
+        }
+    };
+}
+
+ml_kem_functions!(
+    2,
+    Kyber512Params,
+    ml_kem_512_keygen = "mlKem512Keygen",
+    ml_kem_512_keygen_derand = "mlKem512KeygenDerand",
+    ml_kem_512_encaps = "mlKem512Encaps",
+    ml_kem_512_encaps_derand = "mlKem512EncapsDerand",
+    ml_kem_512_decaps = "mlKem512Decaps"
+);
+ml_kem_functions!(
+    3,
+    Kyber768Params,
+    ml_kem_768_keygen = "mlKem768Keygen",
+    ml_kem_768_keygen_derand = "mlKem768KeygenDerand",
+    ml_kem_768_encaps = "mlKem768Encaps",
+    ml_kem_768_encaps_derand = "mlKem768EncapsDerand",
+    ml_kem_768_decaps = "mlKem768Decaps"
+);
+ml_kem_functions!(
+    4,
+    Kyber1024Params,
+    ml_kem_1024_keygen = "mlKem1024Keygen",
+    ml_kem_1024_keygen_derand = "mlKem1024KeygenDerand",
+    ml_kem_1024_encaps = "mlKem1024Encaps",
+    ml_kem_1024_encaps_derand = "mlKem1024EncapsDerand",
+    ml_kem_1024_decaps = "mlKem1024Decaps"
+);
@@ -0,0 +1,261 @@
+//! Runner for NIST ACVP ML-KEM vector sets (FIPS 203).
+//!
+//! Consumes the `internalProjection.json` files (inputs and expected results together) of the
+//! `keyGen` and `encapDecap` modes: keyGen AFT, encapsulation AFT, decapsulation VAL and
+//! the encapsulation / decapsulation key checks of FIPS 203 §7, and reports each test case by
+//! `tcId`.
+//!
+//! By default, the official vector sets are run from `tests/acvp/`, where
+//! `scripts/fetch-test-vectors.sh` downloads them. A missing one fails the test, unless
+//! `KYBER_NZ_SKIP_MISSING_VECTORS` is set to skip it with a warning. `ACVP_VECTORS_DIR`
+//! overrides them with every `*.json` file of another directory.
+
+use kyber_nz::constants::KyberParams;
+use kyber_nz::kem_scheme::{KemDecapsKey, KemEncapsKey, MlKem};
+use kyber_nz::params::{Kyber1024Params, Kyber512Params, Kyber768Params, SecurityLevel};
+use kyber_nz::traits::KemScheme;
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Default)]
+struct Report {
+    passed: usize,
+    failures: Vec<String>,
+}
+
+impl Report {
+    fn record(&mut self, label: &str, tc_id: u64, result: Result<(), String>) {
+        match result {
+            Ok(()) => {
+                println!("{} tcId {}: PASS", label, tc_id);
+                self.passed += 1;
+            }
+            Err(reason) => {
+                println!("{} tcId {}: FAIL ({})", label, tc_id, reason);
+                self.failures
+                    .push(format!("{} tcId {}: {}", label, tc_id, reason));
+            }
+        }
+    }
+}
+
+/// Copies of `gen-val/json-files/<name>/internalProjection.json` of usnistgov/ACVP-Server
+const OFFICIAL_VECTOR_SETS: [&str; 2] = [
+    "ML-KEM-keyGen-FIPS203.json",
+    "ML-KEM-encapDecap-FIPS203.json",
+];
+
+fn vector_sets() -> Vec<PathBuf> {
+    if let Some(dir) = std::env::var_os("ACVP_VECTORS_DIR") {
+        let dir = PathBuf::from(dir);
+        let mut paths: Vec<PathBuf> = fs::read_dir(&dir)
+            .unwrap_or_else(|e| panic!("cannot read {}: {}", dir.display(), e))
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+        assert!(
+            !paths.is_empty(),
+            "no ACVP vector sets in {}",
+            dir.display()
+        );
+        return paths;
+    }
+
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/acvp");
+    let required = std::env::var_os("KYBER_NZ_SKIP_MISSING_VECTORS").is_none();
+    OFFICIAL_VECTOR_SETS
+        .iter()
+        .map(|name| dir.join(name))
+        .filter(|path| {
+            let found = path.exists();
+            assert!(
+                found || !required,
+                "{} not found, run scripts/fetch-test-vectors.sh \
+                 (or set KYBER_NZ_SKIP_MISSING_VECTORS to skip it)",
+                path.display()
+            );
+            if !found {
+                eprintln!(
+                    "⚠️  WARNING: File '{}' not found. Test ignored.",
+                    path.display()
+                );
+            }
+            found
+        })
+        .collect()
+}
+
+/// ACVP files are either the vector set itself or `[{"acvVersion": ..}, {vector set}]`.
+fn vector_set(document: &Value) -> Option<&Value> {
+    match document {
+        Value::Array(items) => items.iter().find(|item| item.get("testGroups").is_some()),
+        Value::Object(_) => Some(document),
+        _ => None,
+    }
+}
+
+/// Hex field `name` of the test case, falling back to the test group.
+fn bytes(test: &Value, group: &Value, name: &str) -> Result<Vec<u8>, String> {
+    let field = test
+        .get(name)
+        .or_else(|| group.get(name))
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("missing field `{}`", name))?;
+    hex::decode(field).map_err(|e| format!("field `{}`: {}", name, e))
+}
+
+fn seed(bytes: &[u8]) -> Result<&[u8; 32], String> {
+    bytes
+        .try_into()
+        .map_err(|_| format!("seed of {} bytes", bytes.len()))
+}
+
+fn expect(name: &str, got: &[u8], expected: &[u8]) -> Result<(), String> {
+    if got == expected {
+        Ok(())
+    } else {
+        Err(format!("{} mismatch", name))
+    }
+}
+
+fn key_gen<const K: usize, S: SecurityLevel>(test: &Value, group: &Value) -> Result<(), String> {
+    let kem = MlKem::<K, S, KyberParams>::new();
+    let d = bytes(test, group, "d")?;
+    let z = bytes(test, group, "z")?;
+    let (ek, dk) = kem
+        .key_gen_internal(seed(&d)?, seed(&z)?)
+        .map_err(|e| e.to_string())?;
+    expect("ek", &ek.to_bytes(), &bytes(test, group, "ek")?)?;
+    expect("dk", &dk.to_bytes(), &bytes(test, group, "dk")?)
+}
+
+fn encapsulation<const K: usize, S: SecurityLevel>(
+    test: &Value,
+    group: &Value,
+) -> Result<(), String> {
+    let kem = MlKem::<K, S, KyberParams>::new();
+    let ek =
+        KemEncapsKey::<K>::from_bytes(&bytes(test, group, "ek")?).map_err(|e| e.to_string())?;
+    let m = bytes(test, group, "m")?;
+    let (k, c) = kem
+        .encaps_internal(&ek, seed(&m)?)
+        .map_err(|e| e.to_string())?;
+    expect("c", &c, &bytes(test, group, "c")?)?;
+    expect("k", k.as_bytes(), &bytes(test, group, "k")?)
+}
+
+fn decapsulation<const K: usize, S: SecurityLevel>(
+    test: &Value,
+    group: &Value,
+) -> Result<(), String> {
+    let kem = MlKem::<K, S, KyberParams>::new();
+    let dk =
+        KemDecapsKey::<K>::from_bytes(&bytes(test, group, "dk")?).map_err(|e| e.to_string())?;
+    let k = kem
+        .decaps_internal(&dk, &bytes(test, group, "c")?)
+        .map_err(|e| e.to_string())?;
+    expect("k", k.as_bytes(), &bytes(test, group, "k")?)
+}
+
+fn key_check(test: &Value, passed: bool) -> Result<(), String> {
+    let expected = test
+        .get("testPassed")
+        .and_then(Value::as_bool)
+        .ok_or("missing field `testPassed`")?;
+    if passed == expected {
+        Ok(())
+    } else {
+        Err(format!("check returned {}, expected {}", passed, expected))
+    }
+}
+
+fn encapsulation_key_check<const K: usize, S: SecurityLevel>(
+    test: &Value,
+    group: &Value,
+) -> Result<(), String> {
+    let passed = KemEncapsKey::<K>::from_bytes(&bytes(test, group, "ek")?)
+        .and_then(|ek| MlKem::<K, S, KyberParams>::check_encaps_key(&ek))
+        .is_ok();
+    key_check(test, passed)
+}
+
+fn decapsulation_key_check<const K: usize, S: SecurityLevel>(
+    test: &Value,
+    group: &Value,
+) -> Result<(), String> {
+    let passed = KemDecapsKey::<K>::from_bytes(&bytes(test, group, "dk")?)
+        .and_then(|dk| MlKem::<K, S, KyberParams>::check_decaps_key(&dk))
+        .is_ok();
+    key_check(test, passed)
+}
+
+fn run_group<const K: usize, S: SecurityLevel>(
+    label: &str,
+    mode: &str,
+    group: &Value,
+    report: &mut Report,
+) {
+    let function = group.get("function").and_then(Value::as_str).unwrap_or("");
+    let tests = group.get("tests").and_then(Value::as_array);
+    for test in tests.into_iter().flatten() {
+        let tc_id = test.get("tcId").and_then(Value::as_u64).unwrap_or(0);
+        let result = match (mode, function) {
+            ("keyGen", _) => key_gen::<K, S>(test, group),
+            ("encapDecap", "encapsulation") => encapsulation::<K, S>(test, group),
+            ("encapDecap", "decapsulation") => decapsulation::<K, S>(test, group),
+            ("encapDecap", "encapsulationKeyCheck") => encapsulation_key_check::<K, S>(test, group),
+            ("encapDecap", "decapsulationKeyCheck") => decapsulation_key_check::<K, S>(test, group),
+            _ => Err(format!(
+                "unsupported mode `{}` / function `{}`",
+                mode, function
+            )),
+        };
+        report.record(label, tc_id, result);
+    }
+}
+
+fn run_vector_set(name: &str, set: &Value, report: &mut Report) {
+    let mode = set.get("mode").and_then(Value::as_str).unwrap_or("");
+    let groups = set.get("testGroups").and_then(Value::as_array);
+    for group in groups.into_iter().flatten() {
+        let tg_id = group.get("tgId").and_then(Value::as_u64).unwrap_or(0);
+        let parameter_set = group
+            .get("parameterSet")
+            .and_then(Value::as_str)
+            .unwrap_or("");
+        let label = format!("{} tgId {} {}", name, tg_id, parameter_set);
+        match parameter_set {
+            "ML-KEM-512" => run_group::<2, Kyber512Params>(&label, mode, group, report),
+            "ML-KEM-768" => run_group::<3, Kyber768Params>(&label, mode, group, report),
+            "ML-KEM-1024" => run_group::<4, Kyber1024Params>(&label, mode, group, report),
+            _ => report.record(&label, 0, Err("unknown parameter set".to_string())),
+        }
+    }
+}
+
+#[test]
+fn acvp_vector_sets() {
+    let paths = vector_sets();
+    if paths.is_empty() {
+        return;
+    }
+
+    let mut report = Report::default();
+    for path in &paths {
+        let name = path.file_name().unwrap().to_string_lossy();
+        let document: Value = serde_json::from_str(&fs::read_to_string(path).unwrap())
+            .unwrap_or_else(|e| panic!("{}: {}", name, e));
+        let set = vector_set(&document).unwrap_or_else(|| panic!("{}: no testGroups", name));
+        run_vector_set(&name, set, &mut report);
+    }
+
+    println!("{} passed, {} failed", report.passed, report.failures.len());
+    assert!(report.passed > 0, "no ACVP test cases found");
+    assert!(
+        report.failures.is_empty(),
+        "ACVP failures:\n{}",
+        report.failures.join("\n")
+    );
+}
@@ -0,0 +1,59 @@
+#![cfg(all(feature = "capi", unix))]
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Builds `libkyber_nz.a` and its header with `cargo rustc --crate-type staticlib`, returning
+/// the directory holding them (`<target>/debug`, header in `include/`).
+///
+/// The library is not a crate type of every build, and the `cargo test` running this holds the
+/// lock of its own target directory, so it is built under `CARGO_TARGET_TMPDIR`.
+fn build_static_lib() -> PathBuf {
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("capi");
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let status = Command::new(cargo)
+        .args(["rustc", "--lib", "--features", "capi"])
+        .args(["--crate-type", "staticlib"])
+        .arg("--manifest-path")
+        .arg(Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml"))
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .status()
+        .expect("Unable to run cargo");
+    assert!(status.success(), "Build of the staticlib failed");
+    target_dir.join("debug")
+}
+
+#[test]
+fn test_capi_c_harness() {
+    let lib_dir = build_static_lib();
+    let static_lib = lib_dir.join("libkyber_nz.a");
+    assert!(static_lib.exists(), "{} not found", static_lib.display());
+
+    let harness = lib_dir.join(format!("capi_harness_{}", std::process::id()));
+    let compiler = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let status = Command::new(&compiler)
+        .arg("-std=c99")
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg("-I")
+        .arg(lib_dir.join("include"))
+        .arg("tests/capi/harness.c")
+        .arg(&static_lib)
+        .args(["-lpthread", "-ldl", "-lm"])
+        .arg("-o")
+        .arg(&harness)
+        .status()
+        .expect("Unable to run the C compiler");
+    assert!(status.success(), "Compilation of the C harness failed");
+
+    let output = Command::new(&harness)
+        .output()
+        .expect("Unable to run the C harness");
+    std::fs::remove_file(&harness).unwrap();
+    assert!(
+        output.status.success(),
+        "C harness failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -0,0 +1,111 @@
+/* C harness for the kyber-nz C API, built and run by tests/capi.rs. */
+
+#include <stdio.h>
+#include <string.h>
+
+#include "kyber_nz.h"
+
+#define CHECK(cond)                                                   \
+    do {                                                              \
+        if (!(cond)) {                                                \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__,   \
+                    __LINE__, #cond);                                 \
+            return 1;                                                 \
+        }                                                             \
+    } while (0)
+
+#define TEST_PQCLEAN(LEVEL)                                                         \
+    static int test_pqclean_##LEVEL(void) {                                         \
+        uint8_t pk[PQCLEAN_MLKEM##LEVEL##_CRYPTO_PUBLICKEYBYTES];                   \
+        uint8_t sk[PQCLEAN_MLKEM##LEVEL##_CRYPTO_SECRETKEYBYTES];                   \
+        uint8_t ct[PQCLEAN_MLKEM##LEVEL##_CRYPTO_CIPHERTEXTBYTES];                  \
+        uint8_t ss_enc[PQCLEAN_MLKEM##LEVEL##_CRYPTO_BYTES];                        \
+        uint8_t ss_dec[PQCLEAN_MLKEM##LEVEL##_CRYPTO_BYTES];                        \
+                                                                                    \
+        CHECK(PQCLEAN_MLKEM##LEVEL##_crypto_kem_keypair(pk, sk) == KYBER_NZ_OK);    \
+        CHECK(PQCLEAN_MLKEM##LEVEL##_crypto_kem_enc(ct, ss_enc, pk) == KYBER_NZ_OK); \
+        CHECK(PQCLEAN_MLKEM##LEVEL##_crypto_kem_dec(ss_dec, ct, sk) == KYBER_NZ_OK); \
+        CHECK(memcmp(ss_enc, ss_dec, sizeof ss_enc) == 0);                          \
+                                                                                    \
+        ct[0] ^= 1;                                                                 \
+        CHECK(PQCLEAN_MLKEM##LEVEL##_crypto_kem_dec(ss_dec, ct, sk) == KYBER_NZ_OK); \
+        CHECK(memcmp(ss_enc, ss_dec, sizeof ss_enc) != 0);                          \
+                                                                                    \
+        CHECK(PQCLEAN_MLKEM##LEVEL##_crypto_kem_keypair(NULL, sk) ==                \
+              KYBER_NZ_ERROR_NULL_POINTER);                                         \
+        return 0;                                                                   \
+    }
+
+#define TEST_OQS(LEVEL)                                                                   \
+    static int test_oqs_##LEVEL(void) {                                                   \
+        uint8_t pk[OQS_KEM_ml_kem_##LEVEL##_length_public_key];                           \
+        uint8_t pk_again[OQS_KEM_ml_kem_##LEVEL##_length_public_key];                     \
+        uint8_t sk[OQS_KEM_ml_kem_##LEVEL##_length_secret_key];                           \
+        uint8_t sk_again[OQS_KEM_ml_kem_##LEVEL##_length_secret_key];                     \
+        uint8_t ct[OQS_KEM_ml_kem_##LEVEL##_length_ciphertext];                           \
+        uint8_t ct_again[OQS_KEM_ml_kem_##LEVEL##_length_ciphertext];                     \
+        uint8_t ss_enc[OQS_KEM_ml_kem_##LEVEL##_length_shared_secret];                    \
+        uint8_t ss_again[OQS_KEM_ml_kem_##LEVEL##_length_shared_secret];                  \
+        uint8_t ss_dec[OQS_KEM_ml_kem_##LEVEL##_length_shared_secret];                    \
+        uint8_t keypair_seed[OQS_KEM_ml_kem_##LEVEL##_length_keypair_seed];               \
+        uint8_t encaps_seed[OQS_KEM_ml_kem_##LEVEL##_length_encaps_seed];                 \
+                                                                                          \
+        memset(keypair_seed, 0x5a, sizeof keypair_seed);                                  \
+        memset(encaps_seed, 0xa5, sizeof encaps_seed);                                    \
+                                                                                          \
+        CHECK(OQS_KEM_ml_kem_##LEVEL##_keypair_derand(pk, sk, keypair_seed) == 0);        \
+        CHECK(OQS_KEM_ml_kem_##LEVEL##_keypair_derand(pk_again, sk_again, keypair_seed)   \
+              == 0);                                                                      \
+        CHECK(memcmp(pk, pk_again, sizeof pk) == 0);                                      \
+        CHECK(memcmp(sk, sk_again, sizeof sk) == 0);                                      \
+                                                                                          \
+        CHECK(OQS_KEM_ml_kem_##LEVEL##_encaps_derand(ct, ss_enc, pk, encaps_seed) == 0);  \
+        CHECK(OQS_KEM_ml_kem_##LEVEL##_encaps_derand(ct_again, ss_again, pk,              \
+                                                     encaps_seed) == 0);                  \
+        CHECK(memcmp(ct, ct_again, sizeof ct) == 0);                                      \
+        CHECK(memcmp(ss_enc, ss_again, sizeof ss_enc) == 0);                              \
+        CHECK(OQS_KEM_ml_kem_##LEVEL##_decaps(ss_dec, ct, sk) == 0);                      \
+        CHECK(memcmp(ss_enc, ss_dec, sizeof ss_enc) == 0);                                \
+                                                                                          \
+        CHECK(OQS_KEM_ml_kem_##LEVEL##_keypair(pk, sk) == 0);                             \
+        CHECK(OQS_KEM_ml_kem_##LEVEL##_encaps(ct, ss_enc, pk) == 0);                      \
+        CHECK(OQS_KEM_ml_kem_##LEVEL##_decaps(ss_dec, ct, sk) == 0);                      \
+        CHECK(memcmp(ss_enc, ss_dec, sizeof ss_enc) == 0);                                \
+                                                                                          \
+        CHECK(OQS_KEM_ml_kem_##LEVEL##_encaps_derand(ct, ss_enc, pk, NULL) ==             \
+              KYBER_NZ_ERROR_NULL_POINTER);                                               \
+        return 0;                                                                         \
+    }
+
+TEST_PQCLEAN(512)
+TEST_PQCLEAN(768)
+TEST_PQCLEAN(1024)
+TEST_OQS(512)
+TEST_OQS(768)
+TEST_OQS(1024)
+
+int main(void) {
+    CHECK(test_pqclean_512() == 0);
+    CHECK(test_pqclean_768() == 0);
+    CHECK(test_pqclean_1024() == 0);
+    CHECK(test_oqs_512() == 0);
+    CHECK(test_oqs_768() == 0);
+    CHECK(test_oqs_1024() == 0);
+    CHECK(strcmp(kyber_nz_error_string(KYBER_NZ_ERROR_NULL_POINTER), "Null pointer argument") == 0);
+
+    /* the derandomized entry points check pk like the randomized ones */
+    static uint8_t pk[OQS_KEM_ml_kem_768_length_public_key];
+    static uint8_t sk[OQS_KEM_ml_kem_768_length_secret_key];
+    static uint8_t ct[OQS_KEM_ml_kem_768_length_ciphertext];
+    static uint8_t ss[OQS_KEM_ml_kem_768_length_shared_secret];
+    static uint8_t seed[OQS_KEM_ml_kem_768_length_keypair_seed];
+    CHECK(OQS_KEM_ml_kem_768_keypair_derand(pk, sk, seed) == 0);
+    pk[0] = 0xff; /* first coefficient 0xfff >= q */
+    pk[1] |= 0x0f;
+    CHECK(OQS_KEM_ml_kem_768_encaps(ct, ss, pk) == KYBER_NZ_ERROR_MODULUS_CHECK_FAILED);
+    CHECK(OQS_KEM_ml_kem_768_encaps_derand(ct, ss, pk, seed) ==
+          KYBER_NZ_ERROR_MODULUS_CHECK_FAILED);
+
+    printf("C API harness: all checks passed\n");
+    return 0;
+}
@@ -0,0 +1,111 @@
+#![cfg(feature = "cli")]
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn kyber_nz(dir: &PathBuf, args: &[&str]) -> Output {
+    let output = Command::new(env!("CARGO_BIN_EXE_kyber-nz"))
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .expect("Unable to run kyber-nz");
+    assert!(
+        output.status.success(),
+        "kyber-nz {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    output
+}
+
+fn field(output: &Output, name: &str) -> String {
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.strip_prefix(&format!("{}: ", name)))
+        .unwrap_or_else(|| panic!("Missing '{}' in output", name))
+        .to_string()
+}
+
+fn run_cli_test(level: &str, format: &str) {
+    let dir = std::env::temp_dir().join(format!(
+        "kyber-nz-cli-{}-{}-{}",
+        level,
+        format,
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let keygen = kyber_nz(&dir, &["keygen", "--level", level, "--format", format]);
+    let dk_file = if format == "seed" {
+        "mlkem.seed"
+    } else {
+        "mlkem.dk"
+    };
+
+    let encaps = kyber_nz(&dir, &["encaps", "mlkem.ek"]);
+    std::fs::write(dir.join("ct.hex"), field(&encaps, "ct")).unwrap();
+
+    let decaps = kyber_nz(&dir, &["decaps", dk_file, "ct.hex"]);
+    assert_eq!(field(&encaps, "ss"), field(&decaps, "ss"));
+
+    let inspect = kyber_nz(&dir, &["inspect", dk_file]);
+    assert_eq!(field(&keygen, "H(ek)"), field(&inspect, "H(ek)"));
+    assert_eq!(
+        field(&inspect, "parameter set"),
+        format!("ML-KEM-{}", level)
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_cli_pem() {
+    run_cli_test("768", "pem");
+}
+
+#[test]
+fn test_cli_raw() {
+    run_cli_test("512", "raw");
+}
+
+#[test]
+fn test_cli_seed() {
+    run_cli_test("1024", "seed");
+}
+
+/// Count 0 of the published ML-KEM-768 KAT, from its d, z and msg seeds.
+#[test]
+fn test_cli_kat() {
+    let dir = std::env::temp_dir().join(format!("kyber-nz-cli-kat-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("seeds.rsp"),
+        "count = 0\n\
+         d = 7C9935A0B07694AA0C6D10E4DB6B1ADD2FD81A25CCB148032DCD739936737F2D\n\
+         z = B505D7CFAD1B497499323C8686325E4792F267AAFA3F87CA60D01CB54F29202A\n\
+         msg = EB4A7C66EF4EBA2DDB38C88D8BC706B1D639002198172A7B1942ECA8F6C001BA\n",
+    )
+    .unwrap();
+
+    let kat = kyber_nz(&dir, &["kat", "--level", "768", "seeds.rsp"]);
+    let stdout = String::from_utf8_lossy(&kat.stdout);
+    let record = |name: &str| {
+        stdout
+            .lines()
+            .find_map(|line| line.strip_prefix(&format!("{} = ", name)))
+            .unwrap_or_else(|| panic!("Missing '{}' in output", name))
+            .to_uppercase()
+    };
+
+    assert_eq!(record("count"), "0");
+    assert!(record("pk")
+        .starts_with("A8E651A1E685F22478A8954F007BC7711B930772C78F092E82878E3E937F3679"));
+    assert!(record("ct")
+        .starts_with("3B835A5FA145387A0819C4DAA1E65FBE2BA5400AFCD640BBDDBBE3585F24BEDD"));
+    assert_eq!(
+        record("ss"),
+        "AC865F839FEF1BF3D528DD7504BED2F64B5502B0FA81D1C32763658E4AAC5037"
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,31 @@
+//! Helpers shared by the integration tests.
+
+use rand::{CryptoRng, RngCore};
+use rand_core::impls;
+
+/// Test RNG handing out the bytes written by a closure. `next_u32` and `next_u64` are read
+/// from the same bytes, so every output of the RNG goes through the closure.
+pub struct FillRng<F>(pub F);
+
+impl<F: FnMut(&mut [u8]) -> Result<(), rand::Error>> RngCore for FillRng<F> {
+    fn next_u32(&mut self) -> u32 {
+        impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_fill(self)
+    }
+
+    /// Panics if the closure fails, as `OsRng` does.
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        if let Err(e) = self.try_fill_bytes(dest) {
+            panic!("{e}");
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        (self.0)(dest)
+    }
+}
+
+impl<F> CryptoRng for FillRng<F> {}
@@ -8,22 +8,24 @@ use std::path::Path;
 
 fn flatten_encaps_key<const K: usize>(key: &KemEncapsKey<K>) -> Vec<u8> {
     let mut out = Vec::new();
-    for chunk in &key.0 {
+    for chunk in key.t_hat() {
         out.extend_from_slice(chunk);
     }
-    out.extend_from_slice(&key.1);
+    out.extend_from_slice(key.rho());
     out
 }
 
 fn flatten_decaps_key<const K: usize>(key: &KemDecapsKey<K>) -> Vec<u8> {
     let mut out = Vec::new();
-    for chunk in &key.0 {
+    for chunk in key.dk_pke() {
         out.extend_from_slice(chunk);
     }
-    for chunk in &key.1 {
+    for chunk in key.t_hat() {
         out.extend_from_slice(chunk);
     }
-    out.extend_from_slice(&key.2);
+    out.extend_from_slice(key.rho());
+    out.extend_from_slice(key.h());
+    out.extend_from_slice(key.z());
     out
 }
 
@@ -60,6 +62,12 @@ where
     let path = Path::new(&filepath);
 
     if !path.exists() {
+        assert!(
+            std::env::var_os("KYBER_NZ_SKIP_MISSING_VECTORS").is_some(),
+            "{} not found, run scripts/fetch-test-vectors.sh \
+             (or set KYBER_NZ_SKIP_MISSING_VECTORS to skip it)",
+            filepath
+        );
         eprintln!("⚠️  WARNING: File '{}' not found. Test ignored.", filename);
         return;
     }
@@ -153,7 +161,7 @@ where
         count
     );
     assert_eq!(
-        &ss_bob.0.to_vec(),
+        &ss_bob.as_bytes().to_vec(),
         case.ss.as_ref().unwrap(),
         "❌ [Count {}] SS (Bob) mismatch",
         count
@@ -162,7 +170,7 @@ where
     // 3. Decaps
     let ss_alice = kem.decaps_internal(&dk, &ct).unwrap();
     assert_eq!(
-        &ss_alice.0.to_vec(),
+        &ss_alice.as_bytes().to_vec(),
         case.ss.as_ref().unwrap(),
         "❌ [Count {}] SS (Alice) mismatch",
         count
@@ -0,0 +1,102 @@
+//! Secret-dependence check under valgrind's memcheck (feature `ct-verify`).
+//!
+//! The test re-runs its own binary under memcheck, where key generation, encapsulation and
+//! decapsulation run with d, z, m and the decapsulation key marked as undefined: any branch or
+//! memory index depending on them is reported, and fails the test. Release builds only, since
+//! overflow checks branch on the values they check:
+//!
+//! ```text
+//! cargo test --release --features ct-verify --test ct_verify
+//! ```
+//!
+//! `VALGRIND` overrides the valgrind executable.
+
+#![cfg(feature = "ct-verify")]
+
+use std::process::Command;
+
+use kyber_nz::constants::KyberParams;
+use kyber_nz::ct_verify::{declassify, poison, running_on_valgrind};
+use kyber_nz::kem_scheme::MlKem;
+use kyber_nz::params::{Kyber1024Params, Kyber512Params, Kyber768Params, SecurityLevel};
+use kyber_nz::traits::KemScheme;
+use rand::rngs::OsRng;
+use rand::RngCore;
+
+mod common;
+
+use common::FillRng;
+
+/// Set in the environment of the run under memcheck.
+const UNDER_MEMCHECK: &str = "KYBER_NZ_CT_VERIFY_CHILD";
+
+/// Marks every byte it hands out as secret, including those of `next_u32` and `next_u64`.
+fn poison_rng() -> FillRng<impl FnMut(&mut [u8]) -> Result<(), rand::Error>> {
+    FillRng(|dest: &mut [u8]| {
+        OsRng.try_fill_bytes(dest)?;
+        poison(dest);
+        Ok(())
+    })
+}
+
+fn run_kem<const K: usize, S: SecurityLevel>() {
+    let kem = MlKem::<K, S, KyberParams>::new();
+
+    // d and z, then m, come from the poisoning RNG
+    let (ek, dk) = kem.key_gen(&mut poison_rng()).unwrap();
+    let (ss, ct) = kem.encaps(&ek, &mut poison_rng()).unwrap();
+
+    poison(dk.dk_pke().as_flattened());
+    poison(dk.t_hat().as_flattened());
+    poison(dk.rho());
+    poison(dk.h());
+    poison(dk.z());
+    let ss_valid = kem.decaps(&dk, &ct).unwrap();
+    let mut modified = ct.clone();
+    modified[0] ^= 1;
+    let ss_rejected = kem.decaps(&dk, &modified).unwrap();
+
+    declassify(ss.as_bytes());
+    declassify(ss_valid.as_bytes());
+    declassify(ss_rejected.as_bytes());
+    assert_eq!(ss, ss_valid);
+    assert_ne!(ss, ss_rejected);
+}
+
+#[test]
+#[cfg_attr(
+    debug_assertions,
+    ignore = "needs --release, see the module documentation"
+)]
+fn ct_verify_under_memcheck() {
+    if std::env::var_os(UNDER_MEMCHECK).is_some() {
+        run_kem::<2, Kyber512Params>();
+        run_kem::<3, Kyber768Params>();
+        run_kem::<4, Kyber1024Params>();
+        assert!(running_on_valgrind(), "not running under valgrind");
+        return;
+    }
+
+    let valgrind = std::env::var("VALGRIND").unwrap_or_else(|_| "valgrind".to_string());
+    let status = Command::new(&valgrind)
+        .args([
+            "--tool=memcheck",
+            "--error-exitcode=1",
+            "--track-origins=yes",
+            "-q",
+        ])
+        .arg(std::env::current_exe().unwrap())
+        .args([
+            "--exact",
+            "ct_verify_under_memcheck",
+            "--test-threads=1",
+            "--nocapture",
+        ])
+        .env(UNDER_MEMCHECK, "1")
+        .status()
+        .unwrap_or_else(|e| panic!("cannot run {} (is valgrind installed?): {}", valgrind, e));
+    assert!(
+        status.success(),
+        "memcheck reported a secret-dependent branch or memory access (see above)"
+    );
+}
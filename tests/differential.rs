@@ -0,0 +1,514 @@
+//! Differential tests against independent ML-KEM implementations: the RustCrypto `ml-kem`
+//! crate, and OpenSSL (libcrypto >= 3.5) loaded at run time.
+//!
+//! Randomized (d, z, m) seeds, valid and malformed ciphertexts, and malformed keys go through
+//! both implementations, on all three parameter sets; ek, dk, ciphertexts, shared secrets and
+//! accept/reject decisions must match byte for byte. The RustCrypto comparison runs with
+//! `cargo test`; the OpenSSL one is ignored by default since it needs the library :
+//!
+//! ```text
+//! KYBER_NZ_LIBCRYPTO=/path/to/libcrypto.so.3 cargo test --release --test differential -- --ignored --nocapture
+//! ```
+//!
+//! `DIFFERENTIAL_SEED` replays a run (its seed is printed, and repeated in any divergence report)
+//! and `DIFFERENTIAL_ITERATIONS` sets its length.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::ffi::{c_char, c_int, c_uint, c_void, CStr};
+use std::ptr;
+
+use kyber_nz::constants::KyberParams;
+use kyber_nz::kem_scheme::{KemDecapsKey, KemEncapsKey, MlKem};
+use kyber_nz::params::{Kyber1024Params, Kyber512Params, Kyber768Params, SecurityLevel};
+use kyber_nz::traits::KemScheme;
+use libloading::Library;
+use rand::rngs::{OsRng, StdRng};
+use rand::{Rng, RngCore, SeedableRng};
+
+const DEFAULT_ITERATIONS: usize = 200;
+
+/// An independent ML-KEM implementation, on the parameter set of rank `k`.
+trait Reference {
+    fn name(&self) -> &'static str;
+
+    /// ML-KEM.KeyGen_internal(d, z), returning (ek, dk).
+    fn key_gen(&self, k: usize, d: &[u8; 32], z: &[u8; 32]) -> (Vec<u8>, Vec<u8>);
+
+    /// ML-KEM.Encaps_internal(ek, m), returning (ct, ss), or `None` if ek is rejected.
+    fn encaps(&self, k: usize, ek: &[u8], m: &[u8; 32]) -> Option<(Vec<u8>, Vec<u8>)>;
+
+    /// ML-KEM.Decaps(dk, c), or `None` if dk or c is rejected.
+    fn decaps(&self, k: usize, dk: &[u8], ct: &[u8]) -> Option<Vec<u8>>;
+}
+
+/// The RustCrypto `ml-kem` crate.
+struct RustCrypto;
+
+/// Runs `$body` with `$params` bound to the `ml-kem` parameter set of rank `$k`.
+macro_rules! with_params {
+    ($k:expr, $params:ident => $body:expr) => {
+        match $k {
+            2 => {
+                type $params = ml_kem::MlKem512;
+                $body
+            }
+            3 => {
+                type $params = ml_kem::MlKem768;
+                $body
+            }
+            _ => {
+                type $params = ml_kem::MlKem1024;
+                $body
+            }
+        }
+    };
+}
+
+#[allow(deprecated)]
+impl Reference for RustCrypto {
+    fn name(&self) -> &'static str {
+        "RustCrypto ml-kem"
+    }
+
+    fn key_gen(&self, k: usize, d: &[u8; 32], z: &[u8; 32]) -> (Vec<u8>, Vec<u8>) {
+        use ml_kem::{DecapsulationKey, ExpandedKeyEncoding, KeyExport};
+
+        let seed = ml_kem::Seed::try_from([&d[..], &z[..]].concat().as_slice()).unwrap();
+        with_params!(k, P => {
+            let dk = DecapsulationKey::<P>::from_seed(seed);
+            (
+                dk.encapsulation_key().to_bytes().to_vec(),
+                dk.to_expanded_bytes().to_vec(),
+            )
+        })
+    }
+
+    fn encaps(&self, k: usize, ek: &[u8], m: &[u8; 32]) -> Option<(Vec<u8>, Vec<u8>)> {
+        use ml_kem::EncapsulationKey;
+
+        with_params!(k, P => {
+            let ek = EncapsulationKey::<P>::new(ek.try_into().ok()?).ok()?;
+            let (ct, ss) = ek.encapsulate_deterministic(&(*m).into());
+            Some((ct.to_vec(), ss.to_vec()))
+        })
+    }
+
+    fn decaps(&self, k: usize, dk: &[u8], ct: &[u8]) -> Option<Vec<u8>> {
+        use ml_kem::{Decapsulate, DecapsulationKey, ExpandedKeyEncoding};
+
+        with_params!(k, P => {
+            let dk = DecapsulationKey::<P>::from_expanded_bytes(dk.try_into().ok()?).ok()?;
+            Some(dk.decapsulate(ct.try_into().ok()?).to_vec())
+        })
+    }
+}
+
+/// `OSSL_PARAM` (openssl/core.h)
+#[repr(C)]
+struct OsslParam {
+    key: *const c_char,
+    data_type: c_uint,
+    data: *mut c_void,
+    data_size: usize,
+    return_size: usize,
+}
+
+const OSSL_PARAM_OCTET_STRING: c_uint = 5;
+const OSSL_PARAM_UNMODIFIED: usize = usize::MAX;
+/// `EVP_PKEY_PUBLIC_KEY` / `EVP_PKEY_KEYPAIR` (openssl/evp.h)
+const EVP_PKEY_PUBLIC_KEY: c_int = 0x86;
+const EVP_PKEY_KEYPAIR: c_int = 0x87;
+
+/// Single octet string parameter, followed by the end marker.
+fn octet_param(key: &'static CStr, data: &[u8]) -> [OsslParam; 2] {
+    [
+        OsslParam {
+            key: key.as_ptr(),
+            data_type: OSSL_PARAM_OCTET_STRING,
+            data: data.as_ptr() as *mut c_void,
+            data_size: data.len(),
+            return_size: OSSL_PARAM_UNMODIFIED,
+        },
+        OsslParam {
+            key: ptr::null(),
+            data_type: 0,
+            data: ptr::null_mut(),
+            data_size: 0,
+            return_size: 0,
+        },
+    ]
+}
+
+type Ctx = *mut c_void;
+type PKey = *mut c_void;
+
+/// The libcrypto functions used by the harness.
+struct OpenSsl {
+    ctx_new_from_name: unsafe extern "C" fn(*mut c_void, *const c_char, *const c_char) -> Ctx,
+    ctx_new_from_pkey: unsafe extern "C" fn(*mut c_void, PKey, *const c_char) -> Ctx,
+    ctx_free: unsafe extern "C" fn(Ctx),
+    ctx_set_params: unsafe extern "C" fn(Ctx, *const OsslParam) -> c_int,
+    keygen_init: unsafe extern "C" fn(Ctx) -> c_int,
+    generate: unsafe extern "C" fn(Ctx, *mut PKey) -> c_int,
+    fromdata_init: unsafe extern "C" fn(Ctx) -> c_int,
+    fromdata: unsafe extern "C" fn(Ctx, *mut PKey, c_int, *const OsslParam) -> c_int,
+    get_octet_string_param:
+        unsafe extern "C" fn(PKey, *const c_char, *mut u8, usize, *mut usize) -> c_int,
+    encapsulate_init: unsafe extern "C" fn(Ctx, *const OsslParam) -> c_int,
+    encapsulate: unsafe extern "C" fn(Ctx, *mut u8, *mut usize, *mut u8, *mut usize) -> c_int,
+    decapsulate_init: unsafe extern "C" fn(Ctx, *const OsslParam) -> c_int,
+    decapsulate: unsafe extern "C" fn(Ctx, *mut u8, *mut usize, *const u8, usize) -> c_int,
+    pkey_free: unsafe extern "C" fn(PKey),
+    _library: Library,
+}
+
+macro_rules! symbol {
+    ($library:expr, $name:literal) => {
+        *$library
+            .get($name)
+            .unwrap_or_else(|e| panic!("{}: {}", String::from_utf8_lossy($name), e))
+    };
+}
+
+impl OpenSsl {
+    fn load() -> Self {
+        let path =
+            std::env::var("KYBER_NZ_LIBCRYPTO").unwrap_or_else(|_| "libcrypto.so.3".to_string());
+        // SAFETY: loading libcrypto runs no initialization code with preconditions.
+        let library = unsafe { Library::new(&path) }
+            .unwrap_or_else(|e| panic!("cannot load {} (set KYBER_NZ_LIBCRYPTO): {}", path, e));
+
+        // SAFETY: the signatures match the OpenSSL 3 declarations.
+        unsafe {
+            Self {
+                ctx_new_from_name: symbol!(library, b"EVP_PKEY_CTX_new_from_name"),
+                ctx_new_from_pkey: symbol!(library, b"EVP_PKEY_CTX_new_from_pkey"),
+                ctx_free: symbol!(library, b"EVP_PKEY_CTX_free"),
+                ctx_set_params: symbol!(library, b"EVP_PKEY_CTX_set_params"),
+                keygen_init: symbol!(library, b"EVP_PKEY_keygen_init"),
+                generate: symbol!(library, b"EVP_PKEY_generate"),
+                fromdata_init: symbol!(library, b"EVP_PKEY_fromdata_init"),
+                fromdata: symbol!(library, b"EVP_PKEY_fromdata"),
+                get_octet_string_param: symbol!(library, b"EVP_PKEY_get_octet_string_param"),
+                encapsulate_init: symbol!(library, b"EVP_PKEY_encapsulate_init"),
+                encapsulate: symbol!(library, b"EVP_PKEY_encapsulate"),
+                decapsulate_init: symbol!(library, b"EVP_PKEY_decapsulate_init"),
+                decapsulate: symbol!(library, b"EVP_PKEY_decapsulate"),
+                pkey_free: symbol!(library, b"EVP_PKEY_free"),
+                _library: library,
+            }
+        }
+    }
+
+    /// Runs `f` with a context for `algorithm`, or for `pkey` when given.
+    fn with_ctx<T>(
+        &self,
+        algorithm: &CStr,
+        pkey: PKey,
+        f: impl FnOnce(Ctx) -> Option<T>,
+    ) -> Option<T> {
+        // SAFETY: the context is checked for NULL and freed once.
+        unsafe {
+            let ctx = if pkey.is_null() {
+                (self.ctx_new_from_name)(ptr::null_mut(), algorithm.as_ptr(), ptr::null())
+            } else {
+                (self.ctx_new_from_pkey)(ptr::null_mut(), pkey, ptr::null())
+            };
+            assert!(
+                !ctx.is_null(),
+                "{:?} is not supported by this libcrypto",
+                algorithm
+            );
+            let result = f(ctx);
+            (self.ctx_free)(ctx);
+            result
+        }
+    }
+
+    fn octet_string(&self, pkey: PKey, name: &CStr) -> Vec<u8> {
+        let mut buffer = vec![0u8; 4096];
+        let mut len = 0;
+        // SAFETY: `buffer` has the advertised capacity.
+        let ok = unsafe {
+            (self.get_octet_string_param)(
+                pkey,
+                name.as_ptr(),
+                buffer.as_mut_ptr(),
+                buffer.len(),
+                &mut len,
+            )
+        };
+        assert_eq!(ok, 1, "cannot export {:?}", name);
+        buffer.truncate(len);
+        buffer
+    }
+
+    fn free(&self, pkey: PKey) {
+        // SAFETY: `pkey` comes from EVP_PKEY_generate / EVP_PKEY_fromdata.
+        unsafe { (self.pkey_free)(pkey) }
+    }
+
+    /// Imports a key, `None` when OpenSSL rejects it.
+    fn import(&self, algorithm: &CStr, name: &'static CStr, key: &[u8]) -> Option<PKey> {
+        let params = octet_param(name, key);
+        let selection = if name == c"pub" {
+            EVP_PKEY_PUBLIC_KEY
+        } else {
+            EVP_PKEY_KEYPAIR
+        };
+        self.with_ctx(algorithm, ptr::null_mut(), |ctx| {
+            let mut pkey = ptr::null_mut();
+            // SAFETY: `params` outlives the calls.
+            let ok = unsafe {
+                (self.fromdata_init)(ctx) == 1
+                    && (self.fromdata)(ctx, &mut pkey, selection, params.as_ptr()) == 1
+            };
+            ok.then_some(pkey)
+        })
+    }
+}
+
+/// Algorithm name of the parameter set of rank `k`.
+fn algorithm(k: usize) -> &'static CStr {
+    match k {
+        2 => c"ML-KEM-512",
+        3 => c"ML-KEM-768",
+        _ => c"ML-KEM-1024",
+    }
+}
+
+impl Reference for OpenSsl {
+    fn name(&self) -> &'static str {
+        "OpenSSL"
+    }
+
+    fn key_gen(&self, k: usize, d: &[u8; 32], z: &[u8; 32]) -> (Vec<u8>, Vec<u8>) {
+        let algorithm = algorithm(k);
+        let seed = [&d[..], &z[..]].concat();
+        let params = octet_param(c"seed", &seed);
+        let pkey = self
+            .with_ctx(algorithm, ptr::null_mut(), |ctx| {
+                let mut pkey = ptr::null_mut();
+                // SAFETY: `params` outlives the calls.
+                let ok = unsafe {
+                    (self.keygen_init)(ctx) == 1
+                        && (self.ctx_set_params)(ctx, params.as_ptr()) == 1
+                        && (self.generate)(ctx, &mut pkey) == 1
+                };
+                ok.then_some(pkey)
+            })
+            .expect("OpenSSL key generation failed");
+        let keys = (
+            self.octet_string(pkey, c"pub"),
+            self.octet_string(pkey, c"priv"),
+        );
+        self.free(pkey);
+        keys
+    }
+
+    fn encaps(&self, k: usize, ek: &[u8], m: &[u8; 32]) -> Option<(Vec<u8>, Vec<u8>)> {
+        let algorithm = algorithm(k);
+        let pkey = self.import(algorithm, c"pub", ek)?;
+        let params = octet_param(c"ikme", m);
+        let result = self.with_ctx(algorithm, pkey, |ctx| {
+            let (mut ct, mut ss) = (vec![0u8; 1568], vec![0u8; 32]);
+            let (mut ct_len, mut ss_len) = (ct.len(), ss.len());
+            // SAFETY: the buffers have the advertised capacities.
+            let ok = unsafe {
+                (self.encapsulate_init)(ctx, params.as_ptr()) == 1
+                    && (self.encapsulate)(
+                        ctx,
+                        ct.as_mut_ptr(),
+                        &mut ct_len,
+                        ss.as_mut_ptr(),
+                        &mut ss_len,
+                    ) == 1
+            };
+            ct.truncate(ct_len);
+            ss.truncate(ss_len);
+            ok.then_some((ct, ss))
+        });
+        self.free(pkey);
+        result
+    }
+
+    fn decaps(&self, k: usize, dk: &[u8], ct: &[u8]) -> Option<Vec<u8>> {
+        let algorithm = algorithm(k);
+        let pkey = self.import(algorithm, c"priv", dk)?;
+        let result = self.with_ctx(algorithm, pkey, |ctx| {
+            let mut ss = vec![0u8; 32];
+            let mut ss_len = ss.len();
+            // SAFETY: `ss` has the advertised capacity.
+            let ok = unsafe {
+                (self.decapsulate_init)(ctx, ptr::null()) == 1
+                    && (self.decapsulate)(ctx, ss.as_mut_ptr(), &mut ss_len, ct.as_ptr(), ct.len())
+                        == 1
+            };
+            ok.then_some(ss)
+        });
+        self.free(pkey);
+        result
+    }
+}
+
+/// Context of a test case, printed with any divergence.
+struct Case<'a> {
+    reference: &'a str,
+    seed: u64,
+    iteration: usize,
+    k: usize,
+    d: [u8; 32],
+    z: [u8; 32],
+    m: [u8; 32],
+}
+
+impl Case<'_> {
+    fn check<T: PartialEq + std::fmt::Debug>(&self, what: &str, ours: T, theirs: T) {
+        assert!(
+            ours == theirs,
+            "divergence from {} on {} (DIFFERENTIAL_SEED={}, iteration {}, {:?})\n\
+             d = {}\nz = {}\nm = {}\nkyber-nz: {:02x?}\n{}: {:02x?}",
+            self.reference,
+            what,
+            self.seed,
+            self.iteration,
+            algorithm(self.k),
+            hex::encode(self.d),
+            hex::encode(self.z),
+            hex::encode(self.m),
+            ours,
+            self.reference,
+            theirs
+        );
+    }
+}
+
+fn run_case<const K: usize, S: SecurityLevel>(
+    reference: &impl Reference,
+    case: &Case,
+    rng: &mut StdRng,
+) {
+    let kem = MlKem::<K, S, KyberParams>::new();
+
+    // KeyGen
+    let (ek, dk) = kem.key_gen_internal(&case.d, &case.z).unwrap();
+    let (ek, dk) = (ek.to_bytes(), dk.to_bytes());
+    let (their_ek, their_dk) = reference.key_gen(K, &case.d, &case.z);
+    case.check("ek", &ek, &their_ek);
+    case.check("dk", dk.as_slice(), &their_dk);
+
+    // Encaps
+    let ek_key = KemEncapsKey::<K>::from_bytes(&ek).unwrap();
+    let (ss, ct) = kem.encaps_internal(&ek_key, &case.m).unwrap();
+    let (their_ct, their_ss) = reference.encaps(K, &ek, &case.m).unwrap();
+    case.check("ct", &ct, &their_ct);
+    case.check("ss", &ss.as_bytes()[..], &their_ss[..]);
+
+    // Decaps of the valid, a modified and a random ciphertext (implicit rejection)
+    let dk_key = KemDecapsKey::<K>::from_bytes(&dk).unwrap();
+    let mut modified = ct.clone();
+    let position = rng.gen_range(0..modified.len());
+    modified[position] ^= 1 << rng.gen_range(0..8);
+    let mut random = vec![0u8; ct.len()];
+    rng.fill_bytes(&mut random);
+    for (what, c) in [
+        ("decaps", &ct),
+        ("decaps of a modified ct", &modified),
+        ("decaps of a random ct", &random),
+    ] {
+        let ours = kem.decaps(&dk_key, c).unwrap();
+        case.check(
+            what,
+            Some(ours.as_bytes().to_vec()),
+            reference.decaps(K, &dk, c),
+        );
+    }
+    let truncated = &ct[..rng.gen_range(0..ct.len())];
+    case.check(
+        "acceptance of a truncated ct",
+        kem.decaps(&dk_key, truncated).is_ok(),
+        reference.decaps(K, &dk, truncated).is_some(),
+    );
+
+    // Malformed encapsulation key : a coefficient >= q
+    let mut bad_ek = ek.clone();
+    let chunk = 384 * rng.gen_range(0..K) + 3 * rng.gen_range(0..128);
+    let coefficient: u16 = rng.gen_range(3329..4096);
+    bad_ek[chunk] = coefficient as u8;
+    bad_ek[chunk + 1] = (bad_ek[chunk + 1] & 0xf0) | (coefficient >> 8) as u8;
+    let ours = MlKem::<K, S, KyberParams>::check_encaps_key(
+        &KemEncapsKey::<K>::from_bytes(&bad_ek).unwrap(),
+    );
+    case.check(
+        "acceptance of an ek with a coefficient >= q",
+        ours.is_ok(),
+        reference.encaps(K, &bad_ek, &case.m).is_some(),
+    );
+
+    // Malformed decapsulation key : H(ek) mismatch
+    let mut bad_dk = dk.clone();
+    bad_dk[768 * K + 32 + rng.gen_range(0..32)] ^= 1;
+    let bad_dk_key = KemDecapsKey::<K>::from_bytes(&bad_dk).unwrap();
+    case.check(
+        "acceptance of a dk with an H(ek) mismatch",
+        kem.decaps(&bad_dk_key, &ct).is_ok(),
+        reference.decaps(K, &bad_dk, &ct).is_some(),
+    );
+}
+
+/// Runs `DIFFERENTIAL_ITERATIONS` (default `default_iterations`) random cases on the three
+/// parameter sets.
+fn differential(reference: &impl Reference, default_iterations: usize) {
+    let seed = std::env::var("DIFFERENTIAL_SEED")
+        .map(|s| s.parse().expect("DIFFERENTIAL_SEED must be a u64"))
+        .unwrap_or_else(|_| OsRng.next_u64());
+    let iterations = std::env::var("DIFFERENTIAL_ITERATIONS")
+        .map(|s| s.parse().expect("DIFFERENTIAL_ITERATIONS must be a number"))
+        .unwrap_or(default_iterations);
+    println!(
+        "{}: DIFFERENTIAL_SEED={} DIFFERENTIAL_ITERATIONS={}",
+        reference.name(),
+        seed,
+        iterations
+    );
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    for iteration in 0..iterations {
+        for k in [2, 3, 4] {
+            let mut case = Case {
+                reference: reference.name(),
+                seed,
+                iteration,
+                k,
+                d: [0u8; 32],
+                z: [0u8; 32],
+                m: [0u8; 32],
+            };
+            rng.fill_bytes(&mut case.d);
+            rng.fill_bytes(&mut case.z);
+            rng.fill_bytes(&mut case.m);
+            match k {
+                2 => run_case::<2, Kyber512Params>(reference, &case, &mut rng),
+                3 => run_case::<3, Kyber768Params>(reference, &case, &mut rng),
+                _ => run_case::<4, Kyber1024Params>(reference, &case, &mut rng),
+            }
+        }
+    }
+    println!(
+        "{}: {} iterations on 3 parameter sets, no divergence",
+        reference.name(),
+        iterations
+    );
+}
+
+#[test]
+fn differential_rustcrypto() {
+    differential(&RustCrypto, DEFAULT_ITERATIONS / 4);
+}
+
+#[test]
+#[ignore = "needs OpenSSL >= 3.5, see the module documentation"]
+fn differential_openssl() {
+    differential(&OpenSsl::load(), DEFAULT_ITERATIONS);
+}
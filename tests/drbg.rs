@@ -0,0 +1,288 @@
+//! CTR_DRBG (feature `drbg`): CAVP vectors, prediction resistance and reseeding, and use as the
+//! randomness source of `key_gen` and `encaps`.
+//!
+//! The AES-256 sections of the CAVP `CTR_DRBG.rsp` files are run from `tests/drbg/`, where
+//! `scripts/fetch-test-vectors.sh` downloads them. A missing file fails the test, unless
+//! `KYBER_NZ_SKIP_MISSING_VECTORS` is set to skip it with a warning. The extra cases committed in
+//! `tests/drbg/extra/` always run.
+
+#![cfg(feature = "drbg")]
+
+use kyber_nz::drbg::CtrDrbg;
+use kyber_nz::errors::Error;
+use kyber_nz::traits::KemScheme;
+use kyber_nz::Kyber768;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use std::collections::VecDeque;
+use std::fs;
+use std::path::PathBuf;
+
+mod common;
+
+use common::FillRng;
+
+/// CTR_DRBG.rsp of the CAVP DRBG test vectors, fetched into `tests/drbg/` by
+/// `scripts/fetch-test-vectors.sh`. Their AES-256 sections are run, along with the extra cases
+/// of `tests/drbg/extra/`.
+const CAVP_FILES: [&str; 3] = [
+    "CTR_DRBG_pr_true.rsp",
+    "CTR_DRBG_pr_false.rsp",
+    "CTR_DRBG_no_reseed.rsp",
+];
+
+/// Entropy source replaying the inputs of a test vector, one per request.
+fn replay(inputs: &[&str]) -> FillRng<impl FnMut(&mut [u8]) -> Result<(), rand::Error>> {
+    let mut inputs: VecDeque<Vec<u8>> = inputs.iter().map(|s| hex::decode(s).unwrap()).collect();
+    FillRng(move |dest: &mut [u8]| {
+        let input = inputs
+            .pop_front()
+            .ok_or_else(|| rand::Error::new("entropy source exhausted"))?;
+        dest.copy_from_slice(&input);
+        Ok(())
+    })
+}
+
+fn cavp_files() -> Vec<PathBuf> {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/drbg");
+    let required = std::env::var_os("KYBER_NZ_SKIP_MISSING_VECTORS").is_none();
+    let mut paths: Vec<PathBuf> = CAVP_FILES
+        .iter()
+        .map(|name| dir.join(name))
+        .filter(|path| {
+            let found = path.exists();
+            assert!(
+                found || !required,
+                "{} not found, run scripts/fetch-test-vectors.sh \
+                 (or set KYBER_NZ_SKIP_MISSING_VECTORS to skip it)",
+                path.display()
+            );
+            if !found {
+                eprintln!(
+                    "⚠️  WARNING: File '{}' not found. Test ignored.",
+                    path.display()
+                );
+            }
+            found
+        })
+        .collect();
+    paths.extend(CAVP_FILES.iter().map(|name| dir.join("extra").join(name)));
+    paths.retain(|path| path.exists());
+    paths
+}
+
+/// Runs one COUNT of an AES-256 section : instantiate, reseed if the case has
+/// EntropyInputReseed, then one generate per AdditionalInput, returning the last output.
+fn run_cavp_case(
+    derivation_function: bool,
+    prediction_resistance: bool,
+    fields: &[(String, String)],
+) -> Result<String, Error> {
+    let values = |name: &'static str| {
+        fields
+            .iter()
+            .filter(move |(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    };
+
+    // instantiation draws the entropy input, then the nonce, then every reseed its own input
+    let mut entropy: Vec<&str> = values("EntropyInput").collect();
+    if derivation_function {
+        entropy.extend(values("Nonce"));
+    }
+    entropy.extend(values("EntropyInputReseed").chain(values("EntropyInputPR")));
+    let source = replay(&entropy);
+
+    let personalization =
+        hex::decode(values("PersonalizationString").next().unwrap_or("")).unwrap();
+    let mut drbg = if derivation_function {
+        CtrDrbg::instantiate(source, &personalization, prediction_resistance)?
+    } else {
+        CtrDrbg::instantiate_no_df(source, &personalization, prediction_resistance)?
+    };
+    if let Some(additional_input) = values("AdditionalInputReseed").next() {
+        drbg.reseed(&hex::decode(additional_input).unwrap())?;
+    }
+
+    let returned_bits = values("ReturnedBits").next().unwrap_or("");
+    let mut output = vec![0u8; returned_bits.len() / 2];
+    for additional_input in values("AdditionalInput") {
+        drbg.generate(&mut output, &hex::decode(additional_input).unwrap())?;
+    }
+    Ok(hex::encode(output))
+}
+
+#[test]
+fn cavp_use_df() -> Result<(), Error> {
+    // CTR_DRBG.rsp, [AES-256 use df], [PredictionResistance = False], COUNT = 0 : two generate
+    // calls, the second one returns the bits
+    let source = replay(&[
+        "36401940fa8b1fba91a1661f211d78a0b9389a74e5bccfece8d766af1a6d3b14",
+        "496f25b0f1301b4f501be30380a137eb",
+    ]);
+    let mut drbg = CtrDrbg::instantiate(source, &[], false)?;
+    let mut returned_bits = [0u8; 64];
+    drbg.generate(&mut returned_bits, &[])?;
+    drbg.generate(&mut returned_bits, &[])?;
+    assert_eq!(
+        hex::encode(returned_bits),
+        "5862eb38bd558dd978a696e6df164782ddd887e7e9a6c9f3f1fbafb78941b535\
+         a64912dfd224c6dc7454e5250b3d97165e16260c2faf1cc7735cb75fb4f07e1d"
+    );
+    assert_eq!(drbg.reseed_counter(), 3);
+    Ok(())
+}
+
+#[test]
+fn cavp_aes_256() {
+    let mut passed = 0;
+    let mut failures = Vec::new();
+    for path in cavp_files() {
+        let name = path
+            .strip_prefix(env!("CARGO_MANIFEST_DIR"))
+            .unwrap_or(&path)
+            .display()
+            .to_string();
+        let text = fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", name, e));
+
+        // (use df, prediction resistance) of the current section, None outside AES-256
+        let mut section = None;
+        let mut prediction_resistance = false;
+        let mut fields = Vec::new();
+        for line in text.lines().map(str::trim) {
+            if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                if let Some(pr) = header.strip_prefix("PredictionResistance = ") {
+                    prediction_resistance = pr == "True";
+                } else if header.ends_with(" df") {
+                    section = header.strip_prefix("AES-256 ").map(|df| df == "use df");
+                }
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let (key, value) = (key.trim(), value.trim());
+            if key == "COUNT" {
+                fields.clear();
+            }
+            fields.push((key.to_string(), value.to_string()));
+
+            let Some(derivation_function) = section else {
+                continue;
+            };
+            if key == "ReturnedBits" {
+                let count = &fields[0].1;
+                match run_cavp_case(derivation_function, prediction_resistance, &fields) {
+                    Ok(bits) if bits == value => passed += 1,
+                    result => failures.push(format!(
+                        "{} [AES-256 {}, PR = {}] COUNT = {}: {:?}",
+                        name,
+                        if derivation_function {
+                            "use df"
+                        } else {
+                            "no df"
+                        },
+                        prediction_resistance,
+                        count,
+                        result
+                    )),
+                }
+            }
+        }
+    }
+
+    println!("{} passed, {} failed", passed, failures.len());
+    assert!(passed > 0, "no CTR_DRBG test cases found");
+    assert!(
+        failures.is_empty(),
+        "CTR_DRBG failures:\n{}",
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn no_df_matches_pqcgenkat() -> Result<(), Error> {
+    // `randombytes_init` of PQCgenKAT_kem with the entropy input 0, 1, ..., 47 : first seed of
+    // the published KAT files
+    let entropy_input: Vec<u8> = (0..48).collect();
+    let source = replay(&[&hex::encode(entropy_input)]);
+    let mut drbg = CtrDrbg::instantiate_no_df(source, &[], false)?;
+    let mut seed = [0u8; 48];
+    drbg.generate(&mut seed, &[])?;
+    assert_eq!(
+        hex::encode_upper(seed),
+        "061550234D158C5EC95595FE04EF7A25767F2E24CC2BC479D09D86DC9ABCFDE7\
+         056A8C266F9EF97ED08541DBD2E1FFA1"
+    );
+
+    assert_eq!(
+        drbg.generate(&mut seed, &[0u8; 49]).err(),
+        Some(Error::InvalidInputLength)
+    );
+    Ok(())
+}
+
+#[test]
+fn prediction_resistance_reseeds_every_request() -> Result<(), Error> {
+    let entropy = "00".repeat(32);
+    let nonce = "11".repeat(16);
+    let source = replay(&[&entropy, &nonce, &"22".repeat(32), &"33".repeat(32)]);
+    let mut drbg = CtrDrbg::instantiate(source, b"personalization", true)?;
+    assert!(drbg.prediction_resistance());
+
+    let mut first = [0u8; 32];
+    drbg.generate(&mut first, b"additional input")?;
+    assert_eq!(drbg.reseed_counter(), 2);
+    let mut second = [0u8; 32];
+    drbg.generate(&mut second, &[])?;
+    assert_ne!(first, second);
+
+    // the source is exhausted: no output without fresh entropy
+    let mut third = [0xffu8; 32];
+    assert_eq!(
+        drbg.generate(&mut third, &[]).err(),
+        Some(Error::RngFailure)
+    );
+    assert_eq!(third, [0xffu8; 32]);
+
+    // an explicit reseed with the same inputs gives the same output
+    let source = replay(&[&entropy, &nonce, &"22".repeat(32)]);
+    let mut drbg = CtrDrbg::instantiate(source, b"personalization", false)?;
+    drbg.reseed(b"additional input")?;
+    assert_eq!(drbg.reseed_counter(), 1);
+    let mut reseeded = [0u8; 32];
+    drbg.generate(&mut reseeded, &[])?;
+    assert_eq!(reseeded, first);
+    Ok(())
+}
+
+#[test]
+fn request_limits() -> Result<(), Error> {
+    let mut drbg = CtrDrbg::instantiate(OsRng, &[], false)?;
+    let mut large = vec![0u8; (1 << 16) + 1];
+    assert_eq!(
+        drbg.generate(&mut large, &[]).err(),
+        Some(Error::InvalidInputLength)
+    );
+
+    // `fill_bytes` splits large requests
+    drbg.fill_bytes(&mut large);
+    assert_eq!(drbg.reseed_counter(), 3);
+    assert!(large.iter().any(|&b| b != 0));
+    Ok(())
+}
+
+#[test]
+fn approved_randomness_for_kem() -> Result<(), Error> {
+    let kem = Kyber768::new();
+    let mut drbg = CtrDrbg::instantiate(OsRng, b"kyber-nz test", true)?;
+    let (ek, dk) = kem.key_gen(&mut drbg)?;
+    let (k, c) = kem.encaps(&ek, &mut drbg)?;
+    assert_eq!(kem.decaps(&dk, &c)?, k);
+
+    // an exhausted source surfaces as an error of key_gen
+    let source = replay(&[&"00".repeat(32), &"11".repeat(16)]);
+    let mut drbg = CtrDrbg::instantiate(source, &[], true)?;
+    assert_eq!(kem.key_gen(&mut drbg).err(), Some(Error::RngFailure));
+    Ok(())
+}
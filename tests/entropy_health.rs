@@ -0,0 +1,72 @@
+//! A noise source failing its health tests puts the whole module in its error state, so this
+//! runs in its own process, as a single test.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use kyber_nz::entropy_health::{self, EntropyHealth, HealthCutoffs};
+use kyber_nz::errors::{Error, ErrorKind};
+use kyber_nz::traits::KemScheme;
+use kyber_nz::Kyber768;
+use rand::rngs::{OsRng, StdRng};
+use rand::{RngCore, SeedableRng};
+
+mod common;
+
+use common::FillRng;
+
+/// Noise source that gets stuck on one value after `healthy` bytes.
+fn stuck_source(
+    mut rng: StdRng,
+    mut healthy: usize,
+) -> FillRng<impl FnMut(&mut [u8]) -> Result<(), rand::Error>> {
+    FillRng(move |dest: &mut [u8]| {
+        for byte in dest {
+            *byte = if healthy > 0 {
+                healthy -= 1;
+                rng.next_u32() as u8
+            } else {
+                0x55
+            };
+        }
+        Ok(())
+    })
+}
+
+#[test]
+fn failure_stops_the_module() -> Result<(), Error> {
+    let kem = Kyber768::new();
+    let cutoffs = HealthCutoffs::for_min_entropy(2.0)?;
+    let mut source = EntropyHealth::new(
+        stuck_source(StdRng::seed_from_u64(1), 1024 + 64 + 32),
+        cutoffs,
+    );
+
+    let (ek, dk) = kem.key_gen(&mut source)?;
+    let (k, c) = kem.encaps(&ek, &mut source)?;
+    assert_eq!(kem.decaps(&dk, &c)?, k);
+    assert!(!entropy_health::failed());
+
+    let error = kem.encaps(&ek, &mut source).err();
+    assert_eq!(error, Some(Error::EntropyHealthFailed));
+    assert_eq!(error.unwrap().kind(), ErrorKind::Environment);
+    assert!(entropy_health::failed());
+
+    // the module refuses service, whatever the entropy source
+    assert_eq!(
+        kem.key_gen(&mut OsRng).err(),
+        Some(Error::EntropyHealthFailed)
+    );
+    assert_eq!(
+        kem.encaps(&ek, &mut OsRng).err(),
+        Some(Error::EntropyHealthFailed)
+    );
+    assert_eq!(kem.decaps(&dk, &c).err(), Some(Error::EntropyHealthFailed));
+    assert_eq!(
+        kem.key_gen_from_entropy(&[7u8; 64]).err(),
+        Some(Error::EntropyHealthFailed)
+    );
+
+    let mut bytes = [0xffu8; 16];
+    assert!(source.try_fill_bytes(&mut bytes).is_err());
+    Ok(())
+}
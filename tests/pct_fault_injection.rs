@@ -0,0 +1,46 @@
+//! Pairwise consistency test failures injected through the public hook (feature
+//! `pct-fault-injection`).
+
+#![cfg(feature = "pct-fault-injection")]
+
+use kyber_nz::errors::Error;
+use kyber_nz::kem_scheme::inject_pct_fault;
+use kyber_nz::traits::KemScheme;
+use kyber_nz::Kyber768;
+use rand::rngs::OsRng;
+
+#[test]
+fn injected_fault_fails_the_next_key_gen_only() -> Result<(), Error> {
+    let kem = Kyber768::new();
+
+    inject_pct_fault();
+    assert_eq!(
+        kem.key_gen(&mut OsRng).err(),
+        Some(Error::PairwiseConsistencyFailed)
+    );
+    let (ek, dk) = kem.key_gen(&mut OsRng)?;
+    let (k, c) = kem.encaps(&ek, &mut OsRng)?;
+    assert_eq!(kem.decaps(&dk, &c)?, k);
+
+    inject_pct_fault();
+    assert_eq!(
+        kem.key_gen_from_entropy(&[0x42; 64]).err(),
+        Some(Error::PairwiseConsistencyFailed)
+    );
+    assert!(kem.key_gen_from_entropy(&[0x42; 64]).is_ok());
+    Ok(())
+}
+
+#[test]
+fn injected_fault_stays_on_its_thread() -> Result<(), Error> {
+    let kem = Kyber768::new();
+
+    inject_pct_fault();
+    let other = std::thread::spawn(|| Kyber768::new().key_gen(&mut OsRng).map(|_| ()));
+    assert_eq!(other.join().unwrap(), Ok(()));
+    assert_eq!(
+        kem.key_gen(&mut OsRng).err(),
+        Some(Error::PairwiseConsistencyFailed)
+    );
+    Ok(())
+}
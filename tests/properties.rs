@@ -0,0 +1,111 @@
+//! Property-based tests of the arithmetic, encoding and sampling layers, and of the KEM
+//! round trip on all three parameter sets.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use kyber_nz::constants::{KyberParams, PolyParams};
+use kyber_nz::conversion::{byte_decode, byte_encode, compress, decompress};
+use kyber_nz::kem_scheme::MlKem;
+use kyber_nz::params::{Kyber1024Params, Kyber512Params, Kyber768Params, SecurityLevel};
+use kyber_nz::polynomial::PolynomialNTT;
+use kyber_nz::traits::KemScheme;
+use kyber_nz::KyberPoly;
+use proptest::prelude::*;
+
+const Q: i16 = KyberParams::Q;
+
+fn coeffs() -> impl Strategy<Value = Vec<i16>> {
+    prop::collection::vec(0..Q, 256)
+}
+
+/// d in 1..=12 with an array of 256 elements of Z_m (m = 2^d, or Q for d = 12).
+fn encodable() -> impl Strategy<Value = (usize, Vec<i16>)> {
+    (1usize..=12).prop_flat_map(|d| {
+        let m = if d == 12 { Q } else { 1 << d };
+        (Just(d), prop::collection::vec(0..m, 256))
+    })
+}
+
+/// x mod± Q, in [-(Q-1)/2, (Q-1)/2]
+fn centered(x: i32) -> i32 {
+    let r = x.rem_euclid(Q as i32);
+    if r > (Q as i32 - 1) / 2 {
+        r - Q as i32
+    } else {
+        r
+    }
+}
+
+fn round_trip<const K: usize, S: SecurityLevel>(d: &[u8; 32], z: &[u8; 32], m: &[u8; 32]) {
+    let kem = MlKem::<K, S, KyberParams>::new();
+    let (ek, dk) = kem.key_gen_internal(d, z).unwrap();
+    let (shared_secret, ct) = kem.encaps_internal(&ek, m).unwrap();
+    assert_eq!(kem.decaps(&dk, &ct).unwrap(), shared_secret);
+}
+
+proptest! {
+    #[test]
+    fn ntt_inverse_is_identity(f in coeffs()) {
+        let f = KyberPoly::from_slice(&f).unwrap();
+        prop_assert_eq!(KyberPoly::from_ntt(&f.to_ntt()).coeffs, f.coeffs);
+    }
+
+    #[test]
+    fn ntt_multiplication_matches_schoolbook(f in coeffs(), g in coeffs()) {
+        let (f, g) = (KyberPoly::from_slice(&f).unwrap(), KyberPoly::from_slice(&g).unwrap());
+        let product = KyberPoly::from_ntt(&(&f.to_ntt() * &g.to_ntt()));
+        prop_assert_eq!(product.coeffs, (&f * &g).coeffs);
+    }
+
+    #[test]
+    fn byte_decode_inverts_byte_encode((d, f) in encodable()) {
+        let mut bytes = vec![0u8; 32 * d];
+        byte_encode(&f, d, &mut bytes).unwrap();
+        let mut decoded = [0i16; 256];
+        byte_decode(&bytes, d, Q, &mut decoded).unwrap();
+        prop_assert_eq!(&decoded[..], &f[..]);
+    }
+
+    #[test]
+    fn compress_error_is_bounded(x in 0..Q, d in 1usize..12) {
+        let y = compress(x, d, Q);
+        prop_assert!((0..1 << d).contains(&y));
+
+        // |Decompress_d(Compress_d(x)) - x mod± Q| <= round(Q / 2^(d+1))
+        let bound = (Q as i32 + (1 << d)) >> (d + 1);
+        let error = centered(decompress(y, d, Q) as i32 - x as i32);
+        prop_assert!(error.abs() <= bound, "x = {}, d = {}, error = {}", x, d, error);
+    }
+
+    #[test]
+    fn decompress_then_compress_is_identity(d in 1usize..12, seed in any::<u16>()) {
+        let y = (seed % (1 << d)) as i16;
+        prop_assert_eq!(compress(decompress(y, d, Q), d, Q), y);
+    }
+
+    #[test]
+    fn cbd_is_bounded_by_eta(eta in 2usize..=3, seed in prop::collection::vec(any::<u8>(), 192)) {
+        let f = KyberPoly::sample_poly_cbd(&seed[..64 * eta], eta).unwrap();
+        for &c in f.coeffs.iter() {
+            prop_assert!((0..Q).contains(&c));
+            prop_assert!(centered(c as i32).abs() <= eta as i32);
+        }
+    }
+
+    #[test]
+    fn sample_ntt_is_reduced(seed in prop::collection::vec(any::<u8>(), 34)) {
+        let a = PolynomialNTT::<KyberParams>::sample_ntt(&seed.try_into().unwrap());
+        prop_assert!(a.coeffs.iter().all(|c| (0..Q).contains(c)));
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn kem_round_trip(d in any::<[u8; 32]>(), z in any::<[u8; 32]>(), m in any::<[u8; 32]>()) {
+        round_trip::<2, Kyber512Params>(&d, &z, &m);
+        round_trip::<3, Kyber768Params>(&d, &z, &m);
+        round_trip::<4, Kyber1024Params>(&d, &z, &m);
+    }
+}
@@ -18,14 +18,20 @@ fn run_kem_test<const K: usize, S: SecurityLevel>(test_name: &str) {
     );
 
     let (k_encaps, c) = kem.encaps(&ek, &mut OsRng).unwrap();
-    println!("  Encapsulated key (K) : {}", hex::encode(&k_encaps.0));
+    println!(
+        "  Encapsulated key (K) : {}",
+        hex::encode(k_encaps.as_bytes())
+    );
     println!("  Ciphertext generated (c) : {} bytes", c.len());
 
     let k_decaps = kem.decaps(&dk, &c).unwrap();
-    println!("  Decapsulated key (K') : {}", hex::encode(&k_decaps.0));
+    println!(
+        "  Decapsulated key (K') : {}",
+        hex::encode(k_decaps.as_bytes())
+    );
 
     assert_eq!(
-        k_encaps.0, k_decaps.0,
+        k_encaps, k_decaps,
         "TEST {} FAILED: Keys do not match !",
         test_name
     );
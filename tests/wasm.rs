@@ -0,0 +1,58 @@
+#![cfg(all(feature = "wasm", target_arch = "wasm32"))]
+
+use kyber_nz::wasm::*;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[wasm_bindgen_test]
+fn test_wasm_ml_kem_512() {
+    let keys = ml_kem_512_keygen().unwrap();
+    let encapsulation = ml_kem_512_encaps(&keys.encapsulation_key()).unwrap();
+    let shared_secret =
+        ml_kem_512_decaps(&keys.decapsulation_key(), &encapsulation.ciphertext()).unwrap();
+    assert_eq!(shared_secret, encapsulation.shared_secret());
+}
+
+#[wasm_bindgen_test]
+fn test_wasm_ml_kem_768() {
+    let keys = ml_kem_768_keygen().unwrap();
+    let encapsulation = ml_kem_768_encaps(&keys.encapsulation_key()).unwrap();
+    let shared_secret =
+        ml_kem_768_decaps(&keys.decapsulation_key(), &encapsulation.ciphertext()).unwrap();
+    assert_eq!(shared_secret, encapsulation.shared_secret());
+}
+
+#[wasm_bindgen_test]
+fn test_wasm_ml_kem_1024() {
+    let keys = ml_kem_1024_keygen().unwrap();
+    let encapsulation = ml_kem_1024_encaps(&keys.encapsulation_key()).unwrap();
+    let shared_secret =
+        ml_kem_1024_decaps(&keys.decapsulation_key(), &encapsulation.ciphertext()).unwrap();
+    assert_eq!(shared_secret, encapsulation.shared_secret());
+}
+
+#[wasm_bindgen_test]
+fn test_wasm_derand_and_errors() {
+    let keys = ml_kem_768_keygen_derand(&[1u8; 32], &[2u8; 32]).unwrap();
+    let again = ml_kem_768_keygen_derand(&[1u8; 32], &[2u8; 32]).unwrap();
+    assert_eq!(keys.encapsulation_key(), again.encapsulation_key());
+
+    let first = ml_kem_768_encaps_derand(&keys.encapsulation_key(), &[3u8; 32]).unwrap();
+    let second = ml_kem_768_encaps_derand(&keys.encapsulation_key(), &[3u8; 32]).unwrap();
+    assert_eq!(first.ciphertext(), second.ciphertext());
+
+    assert!(ml_kem_768_keygen_derand(&[1u8; 31], &[2u8; 32]).is_err());
+    assert!(ml_kem_768_encaps(&keys.encapsulation_key()[1..]).is_err());
+    assert!(ml_kem_768_decaps(&keys.decapsulation_key(), &first.ciphertext()[1..]).is_err());
+}
+
+#[wasm_bindgen_test]
+fn test_wasm_modulus_check() {
+    let keys = ml_kem_768_keygen_derand(&[1u8; 32], &[2u8; 32]).unwrap();
+    // First coefficient of t-hat set to 4095, which is not reduced modulo q.
+    let mut ek = keys.encapsulation_key();
+    ek[0] = 0xff;
+    ek[1] |= 0x0f;
+
+    assert!(ml_kem_768_encaps(&ek).is_err());
+    assert!(ml_kem_768_encaps_derand(&ek, &[3u8; 32]).is_err());
+}
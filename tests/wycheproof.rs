@@ -0,0 +1,244 @@
+//! Runner for the ML-KEM test vectors of C2SP/wycheproof.
+//!
+//! By default, the upstream files fetched into `tests/wycheproof/` by
+//! `scripts/fetch-test-vectors.sh` are run, along with the extra cases committed in
+//! `tests/wycheproof/extra/`. A missing upstream file fails the test, unless
+//! `KYBER_NZ_SKIP_MISSING_VECTORS` is set to skip it with a warning. `WYCHEPROOF_VECTORS_DIR`
+//! overrides them with every `*.json` file of another directory.
+//!
+//! Three group types are supported:
+//! - `MLKEMTest` : KeyGen from `seed` (d || z), then Decaps of `c`, expecting `ek` and `K`.
+//! - `MLKEMEncapsTest` : Encaps to `ek` with randomness `m`, expecting `c` and `K`.
+//! - `MLKEMDecapsValidationTest` : Decaps of `c` with the (semi-expanded) key `dk`, expecting `K`
+//!   when the test case has one.
+//!
+//! A `valid` test must succeed with the expected outputs, an `invalid` one must be rejected with
+//! an error. No test may panic.
+
+use kyber_nz::constants::KyberParams;
+use kyber_nz::kem_scheme::{KemDecapsKey, KemEncapsKey, MlKem};
+use kyber_nz::params::{Kyber1024Params, Kyber512Params, Kyber768Params, SecurityLevel};
+use kyber_nz::traits::KemScheme;
+use serde_json::Value;
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+
+mod common;
+
+use common::FillRng;
+
+/// Hands out the fixed encapsulation randomness m of a test case.
+fn fixed_rng(m: [u8; 32]) -> FillRng<impl FnMut(&mut [u8]) -> Result<(), rand::Error>> {
+    FillRng(move |dest: &mut [u8]| {
+        dest.copy_from_slice(&m[..dest.len()]);
+        Ok(())
+    })
+}
+
+/// Outputs of a test case, compared with the expected fields of a `valid` test.
+type Outputs = Vec<(&'static str, Vec<u8>)>;
+
+/// Files of `testvectors_v1/` of C2SP/wycheproof, for each parameter set
+const UPSTREAM_FILES: [&str; 3] = [
+    "mlkem_{}_test.json",
+    "mlkem_{}_encaps_test.json",
+    "mlkem_{}_semi_expanded_decaps_test.json",
+];
+
+fn json_files(dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap_or_else(|e| panic!("cannot read {}: {}", dir.display(), e))
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty(), "no test vectors in {}", dir.display());
+    paths
+}
+
+fn vector_files() -> Vec<PathBuf> {
+    if let Some(dir) = std::env::var_os("WYCHEPROOF_VECTORS_DIR") {
+        return json_files(Path::new(&dir));
+    }
+
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/wycheproof");
+    let required = std::env::var_os("KYBER_NZ_SKIP_MISSING_VECTORS").is_none();
+    let mut paths = Vec::new();
+    for level in ["512", "768", "1024"] {
+        for file in UPSTREAM_FILES {
+            let path = dir.join(file.replace("{}", level));
+            if path.exists() {
+                paths.push(path);
+            } else {
+                assert!(
+                    !required,
+                    "{} not found, run scripts/fetch-test-vectors.sh \
+                     (or set KYBER_NZ_SKIP_MISSING_VECTORS to skip it)",
+                    path.display()
+                );
+                eprintln!(
+                    "⚠️  WARNING: File '{}' not found. Test ignored.",
+                    path.display()
+                );
+            }
+        }
+    }
+    paths.extend(json_files(&dir.join("extra")));
+    paths
+}
+
+fn bytes(test: &Value, name: &str) -> Result<Vec<u8>, String> {
+    let field = test
+        .get(name)
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("missing field `{}`", name))?;
+    hex::decode(field).map_err(|e| format!("field `{}`: {}", name, e))
+}
+
+fn ml_kem_test<const K: usize, S: SecurityLevel>(test: &Value) -> Result<Outputs, String> {
+    let kem = MlKem::<K, S, KyberParams>::new();
+    let seed = bytes(test, "seed")?;
+    if seed.len() != 64 {
+        return Err(format!("seed of {} bytes", seed.len()));
+    }
+    let (d, z) = seed.split_at(32);
+    let (ek, dk) = kem
+        .key_gen_internal(d.try_into().unwrap(), z.try_into().unwrap())
+        .map_err(|e| e.to_string())?;
+    let k = kem
+        .decaps(&dk, &bytes(test, "c")?)
+        .map_err(|e| e.to_string())?;
+    Ok(vec![("ek", ek.to_bytes()), ("K", k.as_bytes().to_vec())])
+}
+
+fn encaps_test<const K: usize, S: SecurityLevel>(test: &Value) -> Result<Outputs, String> {
+    let kem = MlKem::<K, S, KyberParams>::new();
+    let m: [u8; 32] = bytes(test, "m")?
+        .try_into()
+        .map_err(|_| "m is not 32 bytes".to_string())?;
+    let ek = KemEncapsKey::<K>::from_bytes(&bytes(test, "ek")?).map_err(|e| e.to_string())?;
+    let (k, c) = kem
+        .encaps(&ek, &mut fixed_rng(m))
+        .map_err(|e| e.to_string())?;
+    Ok(vec![("c", c), ("K", k.as_bytes().to_vec())])
+}
+
+fn decaps_validation_test<const K: usize, S: SecurityLevel>(
+    test: &Value,
+) -> Result<Outputs, String> {
+    let kem = MlKem::<K, S, KyberParams>::new();
+    let dk = KemDecapsKey::<K>::from_bytes(&bytes(test, "dk")?).map_err(|e| e.to_string())?;
+    let k = kem
+        .decaps(&dk, &bytes(test, "c")?)
+        .map_err(|e| e.to_string())?;
+    if test.get("K").is_some() {
+        return Ok(vec![("K", k.as_bytes().to_vec())]);
+    }
+    Ok(Vec::new())
+}
+
+fn run_test<const K: usize, S: SecurityLevel>(
+    group_type: &str,
+    test: &Value,
+) -> Result<Outputs, String> {
+    match group_type {
+        "MLKEMTest" => ml_kem_test::<K, S>(test),
+        "MLKEMEncapsTest" => encaps_test::<K, S>(test),
+        "MLKEMDecapsValidationTest" => decaps_validation_test::<K, S>(test),
+        _ => Err(format!("unsupported test group type `{}`", group_type)),
+    }
+}
+
+/// Checks the outcome of a test case against its `result`.
+fn check(test: &Value, outcome: Result<Outputs, String>) -> Result<(), String> {
+    let result = test.get("result").and_then(Value::as_str).unwrap_or("");
+    match (result, outcome) {
+        ("valid", Ok(outputs)) => {
+            for (name, got) in outputs {
+                if got != bytes(test, name)? {
+                    return Err(format!("{} mismatch", name));
+                }
+            }
+            Ok(())
+        }
+        ("valid", Err(e)) => Err(format!("valid test rejected: {}", e)),
+        ("invalid", Ok(_)) => Err("invalid test accepted".to_string()),
+        ("invalid", Err(_)) | ("acceptable", _) => Ok(()),
+        (other, _) => Err(format!("unknown result `{}`", other)),
+    }
+}
+
+fn run_group<const K: usize, S: SecurityLevel>(
+    label: &str,
+    group: &Value,
+    passed: &mut usize,
+    failures: &mut Vec<String>,
+) {
+    let group_type = group.get("type").and_then(Value::as_str).unwrap_or("");
+    let tests = group.get("tests").and_then(Value::as_array);
+    for test in tests.into_iter().flatten() {
+        let tc_id = test.get("tcId").and_then(Value::as_u64).unwrap_or(0);
+        let result =
+            match panic::catch_unwind(AssertUnwindSafe(|| run_test::<K, S>(group_type, test))) {
+                Ok(outcome) => check(test, outcome),
+                Err(_) => Err("panicked".to_string()),
+            };
+        match result {
+            Ok(()) => {
+                println!("{} tcId {}: PASS", label, tc_id);
+                *passed += 1;
+            }
+            Err(reason) => {
+                let flags = test.get("flags").cloned().unwrap_or_default();
+                println!(
+                    "{} tcId {}: FAIL ({}, flags {})",
+                    label, tc_id, reason, flags
+                );
+                failures.push(format!("{} tcId {}: {}", label, tc_id, reason));
+            }
+        }
+    }
+}
+
+#[test]
+fn wycheproof_vectors() {
+    let paths = vector_files();
+    let (mut passed, mut failures) = (0, Vec::new());
+    for path in &paths {
+        let name = path
+            .strip_prefix(env!("CARGO_MANIFEST_DIR"))
+            .unwrap_or(path)
+            .display();
+        let document: Value = serde_json::from_str(&fs::read_to_string(path).unwrap())
+            .unwrap_or_else(|e| panic!("{}: {}", name, e));
+        let groups = document.get("testGroups").and_then(Value::as_array);
+        for (index, group) in groups.into_iter().flatten().enumerate() {
+            let parameter_set = group
+                .get("parameterSet")
+                .and_then(Value::as_str)
+                .unwrap_or("");
+            let label = format!("{} group {} {}", name, index, parameter_set);
+            match parameter_set {
+                "ML-KEM-512" => {
+                    run_group::<2, Kyber512Params>(&label, group, &mut passed, &mut failures)
+                }
+                "ML-KEM-768" => {
+                    run_group::<3, Kyber768Params>(&label, group, &mut passed, &mut failures)
+                }
+                "ML-KEM-1024" => {
+                    run_group::<4, Kyber1024Params>(&label, group, &mut passed, &mut failures)
+                }
+                _ => failures.push(format!("{}: unknown parameter set", label)),
+            }
+        }
+    }
+
+    println!("{} passed, {} failed", passed, failures.len());
+    assert!(passed > 0, "no test cases found");
+    assert!(
+        failures.is_empty(),
+        "Wycheproof failures:\n{}",
+        failures.join("\n")
+    );
+}